@@ -0,0 +1,370 @@
+//! What `tinty update` changed in each repository.
+//!
+//! `update` records the commit every repository has checked out before it
+//! runs and compares it with the one checked out afterwards. For each
+//! repository that moved, the changelog lists the subjects of the new commits,
+//! the theme files added, removed or modified under an item's `themes-dir`,
+//! and the schemes added, removed or modified in the schemes repository or a
+//! `[[scheme-sources]]` entry. Sources without commits (local directories and
+//! archives) never show up. `touches_scheme` tells `--reapply` whether the
+//! applied scheme is affected.
+
+use crate::config::Config;
+use crate::constants::SCHEMES_REPO_NAME;
+use crate::paths;
+use crate::repo::{self, FileChange};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::Path;
+use std::str::FromStr;
+use tinted_builder::SchemeSystem;
+
+/// How many characters of a commit SHA to print.
+const SHORT_SHA_LEN: usize = 7;
+
+/// What one repository's update changed.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub struct RepoChangelog {
+    pub name: String,
+    pub from: String,
+    pub to: String,
+    /// Subjects of the new commits, newest first.
+    pub commits: Vec<String>,
+    /// Theme files relative to `themes-dir`. `None` for schemes repositories.
+    pub themes: Option<ThemeChanges>,
+    /// Scheme IDs. `None` for items.
+    pub schemes: Option<SchemeChanges>,
+}
+
+#[derive(Serialize, Debug, Default, PartialEq, Eq)]
+pub struct ThemeChanges {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub modified: Vec<String>,
+}
+
+#[derive(Serialize, Debug, Default, PartialEq, Eq)]
+pub struct SchemeChanges {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub modified: Vec<String>,
+}
+
+/// A configured repository and, for an item, its `themes-dir`.
+struct TrackedRepo {
+    name: String,
+    source: String,
+    themes_dir: Option<String>,
+}
+
+/// Every item, then every scheme source, then the schemes repository.
+fn tracked_repos(config: &Config) -> Vec<TrackedRepo> {
+    let items = config.items.iter().flatten().map(|item| TrackedRepo {
+        name: item.name.clone(),
+        source: item.path.clone(),
+        themes_dir: Some(item.themes_dir.clone()),
+    });
+    let scheme_sources = config
+        .scheme_sources
+        .iter()
+        .flatten()
+        .map(|source| TrackedRepo {
+            name: source.name.clone(),
+            source: source.path.clone(),
+            themes_dir: None,
+        });
+    let schemes = TrackedRepo {
+        name: SCHEMES_REPO_NAME.to_string(),
+        source: config.schemes_source().0,
+        themes_dir: None,
+    };
+
+    items
+        .chain(scheme_sources)
+        .chain(std::iter::once(schemes))
+        .collect()
+}
+
+/// The commit each installed Git repository has checked out, keyed by name.
+pub fn heads(config: &Config, data_path: &Path) -> BTreeMap<String, String> {
+    tracked_repos(config)
+        .into_iter()
+        .filter_map(|tracked| {
+            let path = paths::item_repo_path(data_path, &tracked.name);
+            let commit = repo::head_commit(&path, &tracked.source).ok()??;
+            Some((tracked.name, commit))
+        })
+        .collect()
+}
+
+/// Compares the commits in `before`, as returned by `heads`, with the ones now
+/// checked out, in config order. A repository whose history can't be read
+/// (e.g. a shallow clone missing the old commit) is still listed, with what
+/// could be read.
+pub fn changelog(
+    config: &Config,
+    data_path: &Path,
+    before: &BTreeMap<String, String>,
+) -> Vec<RepoChangelog> {
+    tracked_repos(config)
+        .into_iter()
+        .filter_map(|tracked| {
+            let path = paths::item_repo_path(data_path, &tracked.name);
+            let from = before.get(&tracked.name)?;
+            let to = repo::head_commit(&path, &tracked.source).ok()??;
+            if *from == to {
+                return None;
+            }
+
+            let changes = repo::changes(&path, &tracked.source, from, &to).unwrap_or_default();
+            let (themes, schemes) = match &tracked.themes_dir {
+                Some(themes_dir) => (Some(theme_changes(&changes.files, themes_dir)), None),
+                None => (None, Some(scheme_changes(&changes.files))),
+            };
+
+            Some(RepoChangelog {
+                name: tracked.name,
+                from: from.clone(),
+                to,
+                commits: changes.subjects,
+                themes,
+                schemes,
+            })
+        })
+        .collect()
+}
+
+/// The changed files under `themes_dir`, relative to it.
+fn theme_changes(files: &[(FileChange, String)], themes_dir: &str) -> ThemeChanges {
+    let themes_dir = themes_dir.trim_start_matches("./").trim_matches('/');
+    let mut themes = ThemeChanges::default();
+
+    for (change, path) in files {
+        let relative = if themes_dir.is_empty() || themes_dir == "." {
+            Some(path.as_str())
+        } else {
+            path.strip_prefix(themes_dir)
+                .and_then(|rest| rest.strip_prefix('/'))
+        };
+        let Some(relative) = relative else {
+            continue;
+        };
+
+        match change {
+            FileChange::Added => themes.added.push(relative.to_string()),
+            FileChange::Removed => themes.removed.push(relative.to_string()),
+            FileChange::Modified => themes.modified.push(relative.to_string()),
+        }
+    }
+
+    themes
+}
+
+/// The IDs of the scheme files (`<system>/<slug>.yaml`) that changed.
+fn scheme_changes(files: &[(FileChange, String)]) -> SchemeChanges {
+    let mut schemes = SchemeChanges::default();
+
+    for (change, path) in files {
+        let Some(id) = scheme_id(path) else {
+            continue;
+        };
+
+        match change {
+            FileChange::Added => schemes.added.push(id),
+            FileChange::Removed => schemes.removed.push(id),
+            FileChange::Modified => schemes.modified.push(id),
+        }
+    }
+
+    schemes
+}
+
+fn scheme_id(path: &str) -> Option<String> {
+    let (system, file_name) = path.split_once('/')?;
+    SchemeSystem::from_str(system).ok()?;
+    let slug = file_name
+        .strip_suffix(".yaml")
+        .or_else(|| file_name.strip_suffix(".yml"))?;
+
+    (!slug.contains('/')).then(|| format!("{system}-{slug}"))
+}
+
+/// Whether `changelogs` change what applying `scheme_id` produces: an item's
+/// theme file for it, or its scheme file in `scheme_source`, the repository
+/// the scheme comes from (`None` for a custom scheme).
+pub fn touches_scheme(
+    changelogs: &[RepoChangelog],
+    config: &Config,
+    scheme_id: &str,
+    scheme_source: Option<&str>,
+) -> bool {
+    changelogs
+        .iter()
+        .any(|changelog| match (&changelog.themes, &changelog.schemes) {
+            (Some(themes), _) => {
+                let extension = config
+                    .items
+                    .iter()
+                    .flatten()
+                    .find(|item| item.name == changelog.name)
+                    .and_then(|item| item.theme_file_extension.as_deref());
+
+                themes
+                    .added
+                    .iter()
+                    .chain(&themes.removed)
+                    .chain(&themes.modified)
+                    .any(|path| is_theme_file(path, scheme_id, extension))
+            }
+            (None, Some(schemes)) => {
+                scheme_source == Some(changelog.name.as_str())
+                    && schemes
+                        .modified
+                        .iter()
+                        .chain(&schemes.removed)
+                        .any(|id| id == scheme_id)
+            }
+            (None, None) => false,
+        })
+}
+
+/// Whether `path`, relative to a `themes-dir`, is the file `find_theme_file`
+/// picks for `scheme_id`.
+fn is_theme_file(path: &str, scheme_id: &str, extension: Option<&str>) -> bool {
+    if path.contains('/') {
+        return false;
+    }
+
+    extension.map_or_else(
+        || {
+            Path::new(path)
+                .file_stem()
+                .is_some_and(|stem| stem == scheme_id)
+        },
+        |extension| path == format!("{scheme_id}{extension}"),
+    )
+}
+
+/// The changelog as text, one block per repository.
+pub fn describe(changelogs: &[RepoChangelog]) -> String {
+    let mut out = String::new();
+
+    for changelog in changelogs {
+        let count = changelog.commits.len();
+        let _ = writeln!(
+            out,
+            "{}: {} -> {}, {count} {}",
+            changelog.name,
+            short_sha(&changelog.from),
+            short_sha(&changelog.to),
+            if count == 1 { "commit" } else { "commits" }
+        );
+        for subject in &changelog.commits {
+            let _ = writeln!(out, "  {subject}");
+        }
+
+        if let Some(themes) = &changelog.themes {
+            let groups = [
+                ("added", '+', &themes.added),
+                ("removed", '-', &themes.removed),
+                ("modified", '~', &themes.modified),
+            ];
+            describe_groups(&mut out, "themes", &groups);
+        }
+        if let Some(schemes) = &changelog.schemes {
+            let groups = [
+                ("added", '+', &schemes.added),
+                ("removed", '-', &schemes.removed),
+                ("modified", '~', &schemes.modified),
+            ];
+            describe_groups(&mut out, "schemes", &groups);
+        }
+    }
+
+    out
+}
+
+/// Writes e.g. `  themes: 1 added, 2 modified` followed by one line per file,
+/// or nothing when every group is empty.
+fn describe_groups(out: &mut String, label: &str, groups: &[(&str, char, &Vec<String>)]) {
+    let counts: Vec<String> = groups
+        .iter()
+        .filter(|(_, _, entries)| !entries.is_empty())
+        .map(|(kind, _, entries)| format!("{} {kind}", entries.len()))
+        .collect();
+    if counts.is_empty() {
+        return;
+    }
+
+    let _ = writeln!(out, "  {label}: {}", counts.join(", "));
+    for (_, marker, entries) in groups {
+        for entry in *entries {
+            let _ = writeln!(out, "    {marker} {entry}");
+        }
+    }
+}
+
+fn short_sha(sha: &str) -> &str {
+    sha.get(..SHORT_SHA_LEN).unwrap_or(sha)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_theme_file, scheme_changes, theme_changes, SchemeChanges, ThemeChanges};
+    use crate::repo::FileChange;
+
+    fn files() -> Vec<(FileChange, String)> {
+        vec![
+            (FileChange::Added, "scripts/base16-new.sh".to_string()),
+            (FileChange::Modified, "scripts/base16-mocha.sh".to_string()),
+            (FileChange::Removed, "base16/old.yaml".to_string()),
+            (FileChange::Added, "base24/new.yml".to_string()),
+            (FileChange::Modified, "README.md".to_string()),
+            (FileChange::Added, "scripts-extra/ignored.sh".to_string()),
+        ]
+    }
+
+    #[test]
+    fn theme_changes_are_relative_to_themes_dir() {
+        assert_eq!(
+            theme_changes(&files(), "scripts/"),
+            ThemeChanges {
+                added: vec!["base16-new.sh".to_string()],
+                removed: Vec::new(),
+                modified: vec!["base16-mocha.sh".to_string()],
+            }
+        );
+        assert_eq!(theme_changes(&files(), ".").modified.len(), 2);
+    }
+
+    #[test]
+    fn scheme_changes_list_added_and_removed_ids() {
+        assert_eq!(
+            scheme_changes(&files()),
+            SchemeChanges {
+                added: vec!["base24-new".to_string()],
+                removed: vec!["base16-old".to_string()],
+                modified: Vec::new(),
+            }
+        );
+    }
+
+    #[test]
+    fn theme_file_matches_like_find_theme_file() {
+        assert!(is_theme_file("base16-mocha.sh", "base16-mocha", None));
+        assert!(!is_theme_file("base16-mocha-dark.sh", "base16-mocha", None));
+        assert!(!is_theme_file("sub/base16-mocha.sh", "base16-mocha", None));
+        assert!(is_theme_file(
+            "base16-mocha.conf.lua",
+            "base16-mocha",
+            Some(".conf.lua")
+        ));
+        assert!(!is_theme_file(
+            "base16-mocha.lua",
+            "base16-mocha",
+            Some(".conf.lua")
+        ));
+    }
+}
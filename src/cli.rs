@@ -1,10 +1,11 @@
 use clap::{
     builder::{styling, PossibleValue},
-    Arg, ArgAction, ArgMatches, Command, ValueHint,
+    Arg, ArgAction, ArgGroup, ArgMatches, Command, ValueHint,
 };
 use clap_complete::Shell;
+use std::num::NonZeroUsize;
 
-use crate::constants::REPO_NAME;
+use crate::constants::{PROFILE_ENV_VAR, REPO_NAME};
 
 /// Builds the command-line interface for the application.
 #[allow(clippy::too_many_lines)]
@@ -31,6 +32,14 @@ pub fn build_cli() -> Command {
                 .global(true)
                 .action(ArgAction::Set)
         )
+        .arg(
+            Arg::new("profile")
+                .help(format!("Optional [profiles.<NAME>] from the config to use. Defaults to ${PROFILE_ENV_VAR}"))
+                .value_name("NAME")
+                .long("profile")
+                .global(true)
+                .action(ArgAction::Set)
+        )
         .subcommand(
             Command::new("build")
                 .about("Builds theme templates: a single template directory, or every installed [[items]] repository when no directory is given")
@@ -63,6 +72,29 @@ pub fn build_cli() -> Command {
                         ])
                         .required(false)
                 )
+                .arg(
+                    Arg::new("with-alias")
+                        .long("with-alias")
+                        .help("Also print the [aliases] from your config that point at the current scheme, e.g. `base16-github (work)`")
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with("property_name")
+                )
+        )
+        .subcommand(
+            Command::new("derive")
+                .about("Writes the [[derived-schemes]] from your config to the custom schemes directory")
+                .arg(
+                    Arg::new("slug")
+                        .help("Slug of a single derived scheme to write. When omitted, every derived scheme is written")
+                        .required(false),
+                )
+                .arg(
+                    Arg::new("quiet")
+                        .long("quiet")
+                        .short('q')
+                        .help("Silence stdout")
+                        .action(ArgAction::SetTrue),
+                ),
         )
         .subcommand(
             Command::new("generate-completion").about("Generates a shell completion script").arg(
@@ -132,14 +164,40 @@ pub fn build_cli() -> Command {
         )
         .subcommand(
             Command::new("generate-scheme")
-                .about("Generates a scheme based on an image")
+                .about("Generates a scheme based on an image, a seed color or at random")
                 .arg(
                     Arg::new("image_path")
                     .help("Which image file to use.")
-                    .required(true)
                     .value_name("IMAGE_FILE")
                     .value_hint(ValueHint::FilePath)
                 )
+                .arg(
+                    Arg::new("from-color")
+                    .long("from-color")
+                    .help("Build the palette around a seed color (e.g. #88c0d0) instead of an image")
+                    .value_name("COLOR")
+                    .value_hint(ValueHint::Other)
+                )
+                .arg(
+                    Arg::new("random")
+                    .long("random")
+                    .help("Build a random palette instead of using an image")
+                    .action(ArgAction::SetTrue)
+                )
+                .arg(
+                    Arg::new("seed")
+                    .long("seed")
+                    .help("Seed for --random, to generate the same palette again")
+                    .value_name("SEED")
+                    .value_parser(clap::value_parser!(u64))
+                    .requires("random")
+                    .conflicts_with_all(["image_path", "from-color"])
+                )
+                .group(
+                    ArgGroup::new("source")
+                    .args(["image_path", "from-color", "random"])
+                    .required(true)
+                )
                 .arg(
                     Arg::new("author")
                     .long("author")
@@ -236,6 +294,78 @@ pub fn build_cli() -> Command {
                         .help("Output as JSON")
                         .action(ArgAction::SetTrue),
                 ))
+        .subcommand(
+            Command::new("search").about("Searches available schemes by name, metadata and colors, best match first")
+                .arg(
+                    Arg::new("query")
+                        .help("Free-text terms matched against the scheme id, slug, name and author")
+                        .num_args(0..)
+                        .required(false),
+                )
+                .arg(
+                    Arg::new("variant")
+                        .long("variant")
+                        .help("Only show dark or light schemes")
+                        .value_parser([
+                            PossibleValue::new("dark"),
+                            PossibleValue::new("light"),
+                        ])
+                        .action(ArgAction::Set),
+                )
+                .arg(
+                    Arg::new("system")
+                        .long("system")
+                        .help("Only show schemes of this system")
+                        .value_parser([
+                            PossibleValue::new("base16"),
+                            PossibleValue::new("base24"),
+                            PossibleValue::new("tinted8"),
+                        ])
+                        .action(ArgAction::Set),
+                )
+                .arg(
+                    Arg::new("author")
+                        .long("author")
+                        .help("Only show schemes whose author contains this text")
+                        .value_name("AUTHOR")
+                        .action(ArgAction::Set),
+                )
+                .arg(
+                    Arg::new("bg-lightness")
+                        .long("bg-lightness")
+                        .help("Only show schemes whose background lightness (L*, 0-100) is in this range, e.g. 5..15, 5.. or ..15")
+                        .value_name("MIN..MAX")
+                        .allow_hyphen_values(true)
+                        .action(ArgAction::Set),
+                )
+                .arg(
+                    Arg::new("near-color")
+                        .long("near-color")
+                        .help("Only show schemes whose palette slot is close to a color, e.g. base0D=#5e81ac. Can be repeated")
+                        .value_name("SLOT=COLOR")
+                        .action(ArgAction::Append),
+                )
+                .arg(
+                    Arg::new("max-delta")
+                        .long("max-delta")
+                        .help("Largest perceptual distance (OKLab delta E, 0-100) allowed by --near-color (default: 10)")
+                        .value_name("DELTA")
+                        .value_parser(clap::value_parser!(f32))
+                        .requires("near-color")
+                        .action(ArgAction::Set),
+                )
+                .arg(
+                    Arg::new("custom-schemes")
+                        .help("Searches availabile custom schemes")
+                        .long("custom-schemes")
+                        .action(ArgAction::SetTrue)
+                )
+                .arg(
+                    Arg::new("json")
+                        .long("json")
+                        .help("Output as JSON")
+                        .action(ArgAction::SetTrue),
+                ))
         .subcommand(
             Command::new("config").about("Provides config related information")
                 .arg(
@@ -254,6 +384,9 @@ pub fn build_cli() -> Command {
                         .conflicts_with("config-path")
                         .action(ArgAction::SetTrue)
                 )
+                .subcommand(
+                    Command::new("validate").about("Checks the config and the files it includes, reporting every problem with its file, line and column")
+                )
         )
         .subcommand(
             Command::new("apply").about("Applies a theme based on the chosen scheme").arg(
@@ -277,6 +410,20 @@ pub fn build_cli() -> Command {
                         .short('q')
                         .help("Silence stdout")
                         .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("jobs")
+                        .long("jobs")
+                        .short('j')
+                        .help("Maximum number of repositories to process at once (defaults to the number of CPUs)")
+                        .value_name("N")
+                        .value_parser(clap::value_parser!(NonZeroUsize)),
+                )
+                .arg(
+                    Arg::new("offline")
+                        .long("offline")
+                        .help("Never use the network; fail if a source would need it")
+                        .action(ArgAction::SetTrue),
                 ),
         )
         .subcommand(
@@ -287,6 +434,128 @@ pub fn build_cli() -> Command {
                         .short('q')
                         .help("Silence stdout")
                         .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("jobs")
+                        .long("jobs")
+                        .short('j')
+                        .help("Maximum number of repositories to process at once (defaults to the number of CPUs)")
+                        .value_name("N")
+                        .value_parser(clap::value_parser!(NonZeroUsize)),
+                )
+                .arg(
+                    Arg::new("offline")
+                        .long("offline")
+                        .help("Never use the network; fail if a source would need it")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("write-lock")
+                        .long("write-lock")
+                        .help("Pin the updated commits in tinty.lock, next to config.toml")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("json")
+                        .long("json")
+                        .help("Print only what changed in each repository, as JSON")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("reapply")
+                        .long("reapply")
+                        .help("Re-apply the current scheme if its theme or scheme file changed (the default with `reapply = true` in config.toml)")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("no-reapply")
+                        .long("no-reapply")
+                        .help("Don't re-apply the current scheme, even with `reapply = true` in config.toml")
+                        .conflicts_with("reapply")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("check")
+                        .long("check")
+                        .help("Only report what would be updated, like `tinty outdated`")
+                        .conflicts_with_all(["write-lock", "offline", "jobs", "reapply", "no-reapply"])
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("bundle").about("Move an installation to a machine without network access")
+                .subcommand_required(true)
+                .arg_required_else_help(true)
+                .subcommand(
+                    Command::new("export").about("Pack the schemes repository and every item into one .tar.gz")
+                        .arg(
+                            Arg::new("output")
+                                .help("Path of the .tar.gz to write")
+                                .required(true)
+                                .value_hint(ValueHint::FilePath),
+                        )
+                        .arg(
+                            Arg::new("quiet")
+                                .long("quiet")
+                                .short('q')
+                                .help("Silence stdout")
+                                .action(ArgAction::SetTrue),
+                        ),
+                ),
+        )
+        .subcommand(
+            Command::new("prune").about("Remove repositories, theme files and symlinks left behind by items no longer in your config, and themes built for deleted custom schemes")
+                .arg(
+                    Arg::new("dry-run")
+                        .long("dry-run")
+                        .help("Only list what would be removed")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("yes")
+                        .long("yes")
+                        .short('y')
+                        .help("Remove without asking for confirmation")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("uninstall").about("Remove an item's repository, theme files and symlinks")
+                .arg(
+                    Arg::new("item_name")
+                        .help("Name of the item to uninstall")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("dry-run")
+                        .long("dry-run")
+                        .help("Only list what would be removed")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("yes")
+                        .long("yes")
+                        .short('y')
+                        .help("Remove without asking for confirmation")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("status").about("Show what is installed, from where and at which commit, with local changes and leftover repositories")
+                .arg(
+                    Arg::new("json")
+                        .long("json")
+                        .help("Print the status as JSON")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("outdated").about("Check whether updates exist for the installed themes without changing anything. Exits with a non-zero status when something is out of date")
+                .arg(
+                    Arg::new("json")
+                        .long("json")
+                        .help("Print the report as JSON")
+                        .action(ArgAction::SetTrue),
                 ),
         )
         .subcommand(
@@ -297,6 +566,39 @@ pub fn build_cli() -> Command {
                         .short('q')
                         .help("Silence stdout")
                         .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("jobs")
+                        .long("jobs")
+                        .short('j')
+                        .help("Maximum number of repositories to process at once (defaults to the number of CPUs)")
+                        .value_name("N")
+                        .value_parser(clap::value_parser!(NonZeroUsize)),
+                )
+                .arg(
+                    Arg::new("offline")
+                        .long("offline")
+                        .help("Never use the network; fail if a source would need it")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("locked")
+                        .long("locked")
+                        .help("Check out exactly the commits pinned in tinty.lock instead of the configured revisions")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("reapply")
+                        .long("reapply")
+                        .help("Re-apply the current scheme if its theme or scheme file changed (the default with `reapply = true` in config.toml)")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("no-reapply")
+                        .long("no-reapply")
+                        .help("Don't re-apply the current scheme, even with `reapply = true` in config.toml")
+                        .conflicts_with("reapply")
+                        .action(ArgAction::SetTrue),
                 ),
         )
         .subcommand(
@@ -0,0 +1,380 @@
+//! Color-space helpers for operations that compute new palette colors rather
+//! than copying them from a scheme file.
+//!
+//! Colors enter and leave as `#rrggbb` hex strings (the format used by scheme
+//! YAML files). In between they are manipulated in HSL or in OKLCH, the
+//! polar form of Björn Ottosson's `OKLab`, which keeps perceived lightness
+//! stable while chroma or hue change. OKLCH colors that fall outside the sRGB
+//! gamut are mapped back by reducing chroma, so the hue and lightness the
+//! caller asked for are preserved.
+
+#![allow(clippy::suboptimal_flops, clippy::many_single_char_names)]
+
+use anyhow::{anyhow, Result};
+use hex_color::HexColor;
+use serde::Deserialize;
+
+/// The color space in which a lightness, saturation or hue adjustment is made.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorSpace {
+    Hsl,
+    #[default]
+    Oklch,
+}
+
+impl ColorSpace {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Hsl => "hsl",
+            Self::Oklch => "oklch",
+        }
+    }
+}
+
+/// An sRGB color with gamma-encoded channels in the `0.0..=1.0` range.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rgb {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+}
+
+/// A color in HSL: hue in degrees, saturation and lightness in `0.0..=1.0`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hsl {
+    pub h: f32,
+    pub s: f32,
+    pub l: f32,
+}
+
+/// A color in OKLCH: lightness in `0.0..=1.0`, chroma (roughly `0.0..=0.37`
+/// for sRGB colors) and hue in degrees.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Oklch {
+    pub l: f32,
+    pub c: f32,
+    pub h: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Oklab {
+    l: f32,
+    a: f32,
+    b: f32,
+}
+
+impl Rgb {
+    /// Parses `#rrggbb`, `rrggbb` or the three-digit shorthand of either.
+    pub fn from_hex(value: &str) -> Result<Self> {
+        let trimmed = value.trim();
+        let prefixed = if trimmed.starts_with('#') {
+            trimmed.to_string()
+        } else {
+            format!("#{trimmed}")
+        };
+        let color = HexColor::parse_rgb(&prefixed)
+            .map_err(|err| anyhow!("Invalid hex color \"{value}\": {err}"))?;
+
+        Ok(Self {
+            r: f32::from(color.r) / 255.0,
+            g: f32::from(color.g) / 255.0,
+            b: f32::from(color.b) / 255.0,
+        })
+    }
+
+    /// Formats the color as a lowercase `#rrggbb` string, clamping channels
+    /// into range first.
+    pub fn to_hex(self) -> String {
+        format!(
+            "#{:02x}{:02x}{:02x}",
+            channel_to_u8(self.r),
+            channel_to_u8(self.g),
+            channel_to_u8(self.b)
+        )
+    }
+
+    pub fn to_hsl(self) -> Hsl {
+        let max = self.r.max(self.g).max(self.b);
+        let min = self.r.min(self.g).min(self.b);
+        let l = f32::midpoint(max, min);
+        let delta = max - min;
+
+        if delta <= f32::EPSILON {
+            return Hsl { h: 0.0, s: 0.0, l };
+        }
+
+        let s = delta / (1.0 - (2.0 * l - 1.0).abs());
+        let h = if (max - self.r).abs() <= f32::EPSILON {
+            60.0 * ((self.g - self.b) / delta).rem_euclid(6.0)
+        } else if (max - self.g).abs() <= f32::EPSILON {
+            60.0 * ((self.b - self.r) / delta + 2.0)
+        } else {
+            60.0 * ((self.r - self.g) / delta + 4.0)
+        };
+
+        Hsl {
+            h: normalize_hue(h),
+            s: s.clamp(0.0, 1.0),
+            l,
+        }
+    }
+
+    pub fn to_oklch(self) -> Oklch {
+        self.to_oklab().to_oklch()
+    }
+
+    /// WCAG relative luminance, `0.0` for black through `1.0` for white.
+    pub fn relative_luminance(self) -> f32 {
+        0.2126 * srgb_to_linear(self.r)
+            + 0.7152 * srgb_to_linear(self.g)
+            + 0.0722 * srgb_to_linear(self.b)
+    }
+
+    /// WCAG contrast ratio between two colors, from `1.0` to `21.0`.
+    pub fn contrast_ratio(self, other: Self) -> f32 {
+        let a = self.relative_luminance();
+        let b = other.relative_luminance();
+
+        (a.max(b) + 0.05) / (a.min(b) + 0.05)
+    }
+
+    /// Perceptual distance to `other`: the Euclidean distance between the two
+    /// colors in `OKLab`, scaled so that `1.0` is roughly a just-noticeable
+    /// difference and `100.0` separates black from white.
+    pub fn delta_e(self, other: Self) -> f32 {
+        let a = self.to_oklab();
+        let b = other.to_oklab();
+
+        (a.l - b.l).hypot(a.a - b.a).hypot(a.b - b.b) * 100.0
+    }
+
+    fn to_oklab(self) -> Oklab {
+        let r = srgb_to_linear(self.r);
+        let g = srgb_to_linear(self.g);
+        let b = srgb_to_linear(self.b);
+
+        let l = (0.412_221_46 * r + 0.536_332_55 * g + 0.051_445_995 * b).cbrt();
+        let m = (0.211_903_5 * r + 0.680_699_5 * g + 0.107_396_96 * b).cbrt();
+        let s = (0.088_302_46 * r + 0.281_718_85 * g + 0.629_978_7 * b).cbrt();
+
+        Oklab {
+            l: 0.210_454_26 * l + 0.793_617_8 * m - 0.004_072_047 * s,
+            a: 1.977_998_5 * l - 2.428_592_2 * m + 0.450_593_7 * s,
+            b: 0.025_904_037 * l + 0.782_771_77 * m - 0.808_675_77 * s,
+        }
+    }
+
+    fn is_in_gamut(self) -> bool {
+        const EPSILON: f32 = 0.000_1;
+        [self.r, self.g, self.b]
+            .iter()
+            .all(|channel| (-EPSILON..=1.0 + EPSILON).contains(channel))
+    }
+
+    const fn clamped(self) -> Self {
+        Self {
+            r: self.r.clamp(0.0, 1.0),
+            g: self.g.clamp(0.0, 1.0),
+            b: self.b.clamp(0.0, 1.0),
+        }
+    }
+}
+
+impl Hsl {
+    pub fn to_rgb(self) -> Rgb {
+        let s = self.s.clamp(0.0, 1.0);
+        let l = self.l.clamp(0.0, 1.0);
+        let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
+        let h = normalize_hue(self.h) / 60.0;
+        let x = c * (1.0 - (h.rem_euclid(2.0) - 1.0).abs());
+        let (r, g, b) = match h {
+            h if h < 1.0 => (c, x, 0.0),
+            h if h < 2.0 => (x, c, 0.0),
+            h if h < 3.0 => (0.0, c, x),
+            h if h < 4.0 => (0.0, x, c),
+            h if h < 5.0 => (x, 0.0, c),
+            _ => (c, 0.0, x),
+        };
+        let m = l - c / 2.0;
+
+        Rgb {
+            r: r + m,
+            g: g + m,
+            b: b + m,
+        }
+        .clamped()
+    }
+}
+
+impl Oklch {
+    /// Converts to sRGB. Colors outside the sRGB gamut keep their lightness
+    /// and hue while chroma is reduced until they fit.
+    pub fn to_rgb(self) -> Rgb {
+        let l = self.l.clamp(0.0, 1.0);
+        let candidate = Self { l, ..self }.to_oklab().to_rgb();
+
+        if candidate.is_in_gamut() {
+            return candidate.clamped();
+        }
+
+        let (mut low, mut high) = (0.0_f32, self.c.max(0.0));
+        for _ in 0..24 {
+            let mid = f32::midpoint(low, high);
+            let probe = Self {
+                l,
+                c: mid,
+                h: self.h,
+            };
+            if probe.to_oklab().to_rgb().is_in_gamut() {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+
+        Self {
+            l,
+            c: low,
+            h: self.h,
+        }
+        .to_oklab()
+        .to_rgb()
+        .clamped()
+    }
+
+    fn to_oklab(self) -> Oklab {
+        let radians = self.h.to_radians();
+
+        Oklab {
+            l: self.l,
+            a: self.c * radians.cos(),
+            b: self.c * radians.sin(),
+        }
+    }
+}
+
+impl Oklab {
+    fn to_oklch(self) -> Oklch {
+        let c = self.a.hypot(self.b);
+        let h = if c <= 0.000_1 {
+            0.0
+        } else {
+            normalize_hue(self.b.atan2(self.a).to_degrees())
+        };
+
+        Oklch { l: self.l, c, h }
+    }
+
+    fn to_rgb(self) -> Rgb {
+        let l = (self.l + 0.396_337_78 * self.a + 0.215_803_76 * self.b).powi(3);
+        let m = (self.l - 0.105_561_346 * self.a - 0.063_854_17 * self.b).powi(3);
+        let s = (self.l - 0.089_484_18 * self.a - 1.291_485_5 * self.b).powi(3);
+
+        Rgb {
+            r: linear_to_srgb(4.076_741_7 * l - 3.307_711_6 * m + 0.230_969_94 * s),
+            g: linear_to_srgb(-1.268_438 * l + 2.609_757_4 * m - 0.341_319_38 * s),
+            b: linear_to_srgb(-0.004_196_086_3 * l - 0.703_418_6 * m + 1.707_614_7 * s),
+        }
+    }
+}
+
+/// Wraps a hue in degrees into `0.0..360.0`.
+pub fn normalize_hue(hue: f32) -> f32 {
+    hue.rem_euclid(360.0)
+}
+
+fn srgb_to_linear(channel: f32) -> f32 {
+    if channel <= 0.040_45 {
+        channel / 12.92
+    } else {
+        ((channel + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(channel: f32) -> f32 {
+    if channel <= 0.003_130_8 {
+        channel * 12.92
+    } else {
+        1.055 * channel.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+#[allow(
+    clippy::as_conversions,
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss
+)]
+fn channel_to_u8(channel: f32) -> u8 {
+    // Clamped to 0..=255 first, so the cast can neither truncate nor wrap.
+    (channel.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ColorSpace, Hsl, Oklch, Rgb};
+
+    #[test]
+    fn hex_round_trips_through_hsl_and_oklch() {
+        for hex in ["#1e1e2e", "#89b4fa", "#f38ba8", "#000000", "#ffffff"] {
+            let rgb = Rgb::from_hex(hex).unwrap();
+            assert_eq!(rgb.to_hsl().to_rgb().to_hex(), hex);
+            assert_eq!(rgb.to_oklch().to_rgb().to_hex(), hex);
+        }
+    }
+
+    #[test]
+    fn from_hex_accepts_missing_hash_and_shorthand() {
+        assert_eq!(Rgb::from_hex("ff0000").unwrap().to_hex(), "#ff0000");
+        assert_eq!(Rgb::from_hex("#F00").unwrap().to_hex(), "#ff0000");
+        assert!(Rgb::from_hex("#ggg").is_err());
+    }
+
+    #[test]
+    fn out_of_gamut_oklch_keeps_lightness_and_hue() {
+        let requested = Oklch {
+            l: 0.7,
+            c: 0.4,
+            h: 150.0,
+        };
+        let mapped = requested.to_rgb().to_oklch();
+
+        assert!((mapped.l - requested.l).abs() < 0.01);
+        assert!((mapped.h - requested.h).abs() < 1.0);
+        assert!(mapped.c < requested.c);
+    }
+
+    #[test]
+    fn hsl_to_rgb_matches_known_values() {
+        let blue = Hsl {
+            h: 240.0,
+            s: 1.0,
+            l: 0.5,
+        };
+        assert_eq!(blue.to_rgb().to_hex(), "#0000ff");
+    }
+
+    #[test]
+    fn contrast_ratio_matches_wcag_extremes() {
+        let black = Rgb::from_hex("#000000").unwrap();
+        let white = Rgb::from_hex("#ffffff").unwrap();
+
+        assert!((black.contrast_ratio(white) - 21.0).abs() < 0.01);
+        assert!((white.contrast_ratio(white) - 1.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn delta_e_is_zero_for_identical_colors_and_100_for_black_and_white() {
+        let black = Rgb::from_hex("#000000").unwrap();
+        let white = Rgb::from_hex("#ffffff").unwrap();
+        let blue = Rgb::from_hex("#5e81ac").unwrap();
+
+        assert!(blue.delta_e(blue) < 0.01);
+        assert!((black.delta_e(white) - 100.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn color_space_defaults_to_oklch() {
+        assert_eq!(ColorSpace::default(), ColorSpace::Oklch);
+    }
+}
@@ -1,14 +1,26 @@
-use crate::constants::{REPO_NAME, SCHEMES_REPO_NAME, SCHEMES_REPO_REVISION, SCHEMES_REPO_URL};
-use crate::utils::replace_tilde_slash_with_home;
+use crate::color::{ColorSpace, Rgb};
+use crate::constants::{
+    PROFILE_ENV_VAR, REPO_NAME, SCHEMES_REPO_NAME, SCHEMES_REPO_REVISION, SCHEMES_REPO_URL,
+    SCHEME_SOURCE_SEPARATOR, TEMPLATES_DIR,
+};
+use crate::includes;
+use crate::repo::{self, CloneOptions, NetworkOptions, RepoCache};
+use crate::utils::{
+    command_exists, hostname, interpolate_env_vars, replace_tilde_slash_with_home, wildcard_regex,
+};
 use anyhow::{anyhow, Context, Result};
 use home::home_dir;
+use serde::de::{self, DeserializeOwned};
 use serde::Deserialize;
-use std::collections::HashSet;
-use std::fmt;
-use std::fs;
-use std::path::Path;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::{self, Write as _};
+use std::num::NonZeroU32;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 use tinted_builder::SchemeSystem;
+use toml::{Table, Value};
 use url::Url;
+use xdg::BaseDirectories;
 
 pub const DEFAULT_CONFIG_SHELL: &str = "sh -c '{}'";
 pub const CONFIG_FILE_NAME: &str = "config.toml";
@@ -39,6 +51,375 @@ pub struct ConfigItem {
     /// refused without touching the working tree. Defaults to `false`.
     #[serde(default, rename = "allow-dirty-update")]
     pub allow_dirty_update: bool,
+    /// Number of commits of history to clone and keep. Unset clones full
+    /// history.
+    #[serde(rename = "clone-depth")]
+    pub clone_depth: Option<NonZeroU32>,
+    /// When `true`, only `themes-dir`, `templates/` and files at the
+    /// repository root are fetched and checked out. Defaults to `false`.
+    #[serde(default, rename = "sparse-checkout")]
+    pub sparse_checkout: bool,
+    /// When `true`, `install` and `update` refuse to check out a commit that
+    /// is not signed by an allowed key. Defaults to `false`.
+    #[serde(default, rename = "verify-signatures")]
+    pub verify_signatures: bool,
+    /// The keys `verify-signatures` accepts: an SSH allowed signers file, or a
+    /// GPG home directory holding the trusted public keys. Unset uses git's
+    /// own configuration and the user's GPG keyring.
+    #[serde(rename = "allowed-signers")]
+    pub allowed_signers: Option<String>,
+    /// Overrides the global `git-timeout` for this item.
+    #[serde(rename = "git-timeout")]
+    pub git_timeout: Option<u64>,
+    /// Overrides the global `git-retries` for this item.
+    #[serde(rename = "git-retries")]
+    pub git_retries: Option<u32>,
+    /// Limits the item to the machines this holds on. Elsewhere `read` moves
+    /// it to `Config::skipped_items`.
+    pub when: Option<ItemCondition>,
+}
+
+impl ConfigItem {
+    /// How much of the item's repository to fetch and check out, and which
+    /// commits may be checked out. The network settings and cache come from
+    /// `defaults`, with this item's own `git-timeout` and `git-retries`
+    /// applied.
+    pub fn clone_options(&self, defaults: &CloneOptions) -> CloneOptions {
+        let sparse_paths = self.sparse_checkout.then(|| {
+            let themes_dir = self.themes_dir.trim_matches('/');
+
+            [themes_dir, TEMPLATES_DIR]
+                .into_iter()
+                .filter(|dir| !dir.is_empty() && *dir != ".")
+                .map(str::to_string)
+                .collect()
+        });
+
+        CloneOptions {
+            depth: self.clone_depth,
+            sparse_paths,
+            verify_signatures: self.verify_signatures,
+            allowed_signers: self.allowed_signers.as_ref().map(PathBuf::from),
+            network: self.network_options(defaults.network),
+            cache: defaults.cache.clone(),
+        }
+    }
+
+    /// `defaults`, the config's network settings, with this item's own
+    /// `git-timeout` and `git-retries` applied.
+    pub fn network_options(&self, defaults: NetworkOptions) -> NetworkOptions {
+        network_options(defaults, self.git_timeout, self.git_retries)
+    }
+}
+
+/// An item's `when` table. Every key that is set must hold for the item to be
+/// used.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct ItemCondition {
+    /// Host name patterns, any of which may match.
+    #[serde(default, deserialize_with = "string_or_list")]
+    pub hostname: Vec<String>,
+    /// Operating systems as named by `std::env::consts::OS`, e.g. `linux` or
+    /// `macos`, any of which may match.
+    #[serde(default, deserialize_with = "string_or_list")]
+    pub os: Vec<String>,
+    /// Commands that must all be found in `PATH`.
+    #[serde(
+        default,
+        rename = "command-exists",
+        deserialize_with = "string_or_list"
+    )]
+    pub command_exists: Vec<String>,
+    /// Environment variables that must be set to a value matching the
+    /// pattern, e.g. `WAYLAND_DISPLAY = "*"`.
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+}
+
+impl ItemCondition {
+    /// Why the condition doesn't hold on this machine, or `None` when it
+    /// does.
+    pub fn mismatch(&self) -> Result<Option<String>> {
+        if !self.hostname.is_empty() {
+            let hostname = hostname()?;
+            if !matches_any(&self.hostname, &hostname)? {
+                return Ok(Some(format!("the host name is \"{hostname}\"")));
+            }
+        }
+        if !self.os.is_empty() && !self.os.iter().any(|os| os == std::env::consts::OS) {
+            return Ok(Some(format!("the OS is \"{}\"", std::env::consts::OS)));
+        }
+        if let Some(command) = self
+            .command_exists
+            .iter()
+            .find(|command| !command_exists(command))
+        {
+            return Ok(Some(format!("\"{command}\" is not in PATH")));
+        }
+        for (name, pattern) in &self.env {
+            let value = std::env::var(name).unwrap_or_default();
+            if std::env::var_os(name).is_none()
+                || !matches_any(std::slice::from_ref(pattern), &value)?
+            {
+                return Ok(Some(format!("${name} doesn't match \"{pattern}\"")));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+impl fmt::Display for ItemCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let quoted = |values: &[String]| {
+            values
+                .iter()
+                .map(|value| format!("\"{value}\""))
+                .collect::<Vec<String>>()
+                .join(", ")
+        };
+        let mut keys = Vec::new();
+        if !self.hostname.is_empty() {
+            keys.push(format!("hostname = [{}]", quoted(&self.hostname)));
+        }
+        if !self.os.is_empty() {
+            keys.push(format!("os = [{}]", quoted(&self.os)));
+        }
+        if !self.command_exists.is_empty() {
+            keys.push(format!(
+                "command-exists = [{}]",
+                quoted(&self.command_exists)
+            ));
+        }
+        if !self.env.is_empty() {
+            let env: Vec<String> = self
+                .env
+                .iter()
+                .map(|(name, pattern)| format!("{name} = \"{pattern}\""))
+                .collect();
+            keys.push(format!("env = {{ {} }}", env.join(", ")));
+        }
+
+        write!(f, "{{ {} }}", keys.join(", "))
+    }
+}
+
+/// Whether any of the wildcard `patterns` matches `value`.
+fn matches_any(patterns: &[String], value: &str) -> Result<bool> {
+    for pattern in patterns {
+        if wildcard_regex(pattern)?.is_match(value) {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+/// Deserializes a string as a one-element list.
+fn string_or_list<'de, D>(deserializer: D) -> std::result::Result<Vec<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StringOrList {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    Ok(match StringOrList::deserialize(deserializer)? {
+        StringOrList::One(value) => vec![value],
+        StringOrList::Many(values) => values,
+    })
+}
+
+/// The item used when the config has no `[[items]]`.
+fn default_item() -> ConfigItem {
+    ConfigItem {
+        path: BASE16_SHELL_REPO_URL.to_string(),
+        name: BASE16_SHELL_REPO_NAME.to_string(),
+        themes_dir: BASE16_SHELL_THEMES_DIR.to_string(),
+        hook: Some(BASE16_SHELL_HOOK.to_string()),
+        supported_systems: Some(vec![SchemeSystem::Base16]), // DEFAULT_SCHEME_SYSTEM
+        theme_file_extension: None,
+        revision: None,
+        write_to_file: None,
+        allow_dirty_update: false,
+        clone_depth: None,
+        sparse_checkout: false,
+        verify_signatures: false,
+        allowed_signers: None,
+        git_timeout: None,
+        git_retries: None,
+        when: None,
+    }
+}
+
+/// Deserializes `table` as a config, calling `on_ignored` with the path of
+/// each key it doesn't know, e.g. `["items", "0", "theme-dir"]`.
+pub fn deserialize(
+    table: Table,
+    mut on_ignored: impl FnMut(Vec<String>),
+) -> Result<Config, toml::de::Error> {
+    serde_ignored::deserialize(Value::Table(table), |path| {
+        let mut segments = Vec::new();
+        push_path_segments(&path, &mut segments);
+        on_ignored(segments);
+    })
+}
+
+fn push_path_segments(path: &serde_ignored::Path, segments: &mut Vec<String>) {
+    match path {
+        serde_ignored::Path::Root => {}
+        serde_ignored::Path::Seq { parent, index } => {
+            push_path_segments(parent, segments);
+            segments.push(index.to_string());
+        }
+        serde_ignored::Path::Map { parent, key } => {
+            push_path_segments(parent, segments);
+            segments.push(key.clone());
+        }
+        serde_ignored::Path::Some { parent }
+        | serde_ignored::Path::NewtypeStruct { parent }
+        | serde_ignored::Path::NewtypeVariant { parent } => push_path_segments(parent, segments),
+    }
+}
+
+/// The keys `T` is deserialized from, when it is a struct.
+pub fn field_names<T: DeserializeOwned>() -> &'static [&'static str] {
+    /// Reads the field names `T` asks for, without deserializing anything.
+    struct FieldNames<'a>(&'a mut &'static [&'static str]);
+
+    impl<'de> de::Deserializer<'de> for FieldNames<'_> {
+        type Error = de::value::Error;
+
+        fn deserialize_any<V: de::Visitor<'de>>(self, _: V) -> Result<V::Value, Self::Error> {
+            Err(de::Error::custom("not a struct"))
+        }
+
+        fn deserialize_struct<V: de::Visitor<'de>>(
+            self,
+            _: &'static str,
+            fields: &'static [&'static str],
+            _: V,
+        ) -> Result<V::Value, Self::Error> {
+            *self.0 = fields;
+            Err(de::Error::custom("only the field names are read"))
+        }
+
+        serde::forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+            bytes byte_buf option unit unit_struct newtype_struct seq tuple
+            tuple_struct map enum identifier ignored_any
+        }
+    }
+
+    let mut fields: &'static [&'static str] = &[];
+    // Always an error, since nothing is deserialized
+    let _ = T::deserialize(FieldNames(&mut fields));
+    fields
+}
+
+/// Refuses keys an item's `when` doesn't know, since the item would otherwise
+/// be used on every machine.
+fn ensure_condition_keys_are_known(config: &Config, ignored: &[Vec<String>]) -> Result<()> {
+    for path in ignored {
+        let [items, index, when, key] = path.as_slice() else {
+            continue;
+        };
+        if items != "items" || when != "when" {
+            continue;
+        }
+
+        let name = index
+            .parse::<usize>()
+            .ok()
+            .and_then(|index| config.items.as_ref()?.get(index))
+            .map_or("", |item| item.name.as_str());
+        return Err(anyhow!(
+            "config.toml [[items]] \"{name}\" has an unknown when key `{key}`. Use {}",
+            field_names::<ItemCondition>()
+                .iter()
+                .map(|key| format!("`{key}`"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+
+    Ok(())
+}
+
+/// Expands environment variables in `key`'s `value`.
+fn expand_env_var(key: &str, value: &mut String) -> Result<()> {
+    *value = interpolate_env_vars(value)
+        .with_context(|| format!("Unable to expand config.toml {key} = \"{value}\""))?;
+
+    Ok(())
+}
+
+/// Expands environment variables in every `path` and `write-to-file` value.
+/// Items `when` left out are skipped, since the variables they use may only be
+/// set where they're used. `hook`, `hooks` and `shell` are left for the shell
+/// to expand when it runs them, so values are never pasted into a command.
+fn expand_env_vars(config: &mut Config) -> Result<()> {
+    for item in config.items.iter_mut().flatten() {
+        expand_env_var(&format!("items.{}.path", item.name), &mut item.path)?;
+        // Only the file path, not the markers after it
+        if let Some(target) = item
+            .write_to_file
+            .as_mut()
+            .and_then(|args| args.first_mut())
+        {
+            expand_env_var(&format!("items.{}.write-to-file", item.name), target)?;
+        }
+    }
+    if let Some(path) = config.schemes.path.as_mut() {
+        expand_env_var("schemes.path", path)?;
+    }
+    for source in config.scheme_sources.iter_mut().flatten() {
+        expand_env_var(
+            &format!("scheme-sources.{}.path", source.name),
+            &mut source.path,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Moves the items whose `when` doesn't hold on this machine out of `items`,
+/// each with the reason.
+fn skip_unmatched_items(items: &mut Vec<ConfigItem>) -> Result<Vec<(ConfigItem, String)>> {
+    let mut skipped = Vec::new();
+    let mut kept = Vec::new();
+    for item in items.drain(..) {
+        let mismatch = item
+            .when
+            .as_ref()
+            .map(ItemCondition::mismatch)
+            .transpose()?;
+        match mismatch.flatten() {
+            Some(reason) => skipped.push((item, reason)),
+            None => kept.push(item),
+        }
+    }
+    *items = kept;
+
+    Ok(skipped)
+}
+
+/// `defaults` with a `git-timeout` (in seconds, `0` for none) and
+/// `git-retries` applied where they are set.
+fn network_options(
+    defaults: NetworkOptions,
+    git_timeout: Option<u64>,
+    git_retries: Option<u32>,
+) -> NetworkOptions {
+    NetworkOptions {
+        timeout: git_timeout.map_or(defaults.timeout, |seconds| {
+            (seconds > 0).then(|| Duration::from_secs(seconds))
+        }),
+        retries: git_retries.unwrap_or(defaults.retries),
+    }
 }
 
 impl fmt::Display for ConfigItem {
@@ -69,6 +450,27 @@ impl fmt::Display for ConfigItem {
         if self.allow_dirty_update {
             writeln!(f, "allow-dirty-update = true")?;
         }
+        if let Some(clone_depth) = self.clone_depth {
+            writeln!(f, "clone-depth = {clone_depth}")?;
+        }
+        if self.sparse_checkout {
+            writeln!(f, "sparse-checkout = true")?;
+        }
+        if self.verify_signatures {
+            writeln!(f, "verify-signatures = true")?;
+        }
+        if let Some(allowed_signers) = &self.allowed_signers {
+            writeln!(f, "allowed-signers = \"{allowed_signers}\"")?;
+        }
+        if let Some(git_timeout) = self.git_timeout {
+            writeln!(f, "git-timeout = {git_timeout}")?;
+        }
+        if let Some(git_retries) = self.git_retries {
+            writeln!(f, "git-retries = {git_retries}")?;
+        }
+        if let Some(when) = &self.when {
+            writeln!(f, "when = {when}")?;
+        }
         writeln!(f, "supported-systems = [{system_text}]")?;
         write!(f, "themes-dir = \"{}\"", self.themes_dir)
     }
@@ -97,6 +499,127 @@ impl fmt::Display for ConfigRing {
     }
 }
 
+/// The adjustment a `[[derived-schemes]]` operation makes to each selected
+/// palette slot.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum DeriveOperationKind {
+    /// Raise lightness by `amount` points on a 0-100 scale.
+    Lighten,
+    /// Lower lightness by `amount` points on a 0-100 scale.
+    Darken,
+    /// Scale saturation (HSL) or chroma (OKLCH) up by `amount` percent.
+    Saturate,
+    /// Scale saturation (HSL) or chroma (OKLCH) down by `amount` percent.
+    Desaturate,
+    /// Rotate the hue by `amount` degrees.
+    HueRotate,
+}
+
+impl DeriveOperationKind {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Lighten => "lighten",
+            Self::Darken => "darken",
+            Self::Saturate => "saturate",
+            Self::Desaturate => "desaturate",
+            Self::HueRotate => "hue-rotate",
+        }
+    }
+}
+
+/// A single color adjustment applied while deriving a scheme.
+#[derive(Deserialize, Debug, Clone)]
+pub struct DeriveOperation {
+    pub op: DeriveOperationKind,
+    pub amount: f32,
+    /// Palette slots to adjust, e.g. `["base00", "base01"]`. All slots are
+    /// adjusted when omitted.
+    pub slots: Option<Vec<String>>,
+    #[serde(default)]
+    pub space: ColorSpace,
+}
+
+impl fmt::Display for DeriveOperation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{{ op = \"{}\", amount = {}",
+            self.op.as_str(),
+            self.amount
+        )?;
+        if let Some(slots) = &self.slots {
+            let slots_text = slots
+                .iter()
+                .map(|slot| format!("\"{slot}\""))
+                .collect::<Vec<String>>()
+                .join(", ");
+            write!(f, ", slots = [{slots_text}]")?;
+        }
+        write!(f, ", space = \"{}\" }}", self.space.as_str())
+    }
+}
+
+/// Structure for configuration derived schemes: a new scheme defined as an
+/// installed base scheme plus a list of color operations and slot overrides.
+/// Derived schemes are written to `custom-schemes` on `install`, `update` and
+/// `derive`, so they follow upstream changes to their base scheme.
+#[derive(Deserialize, Debug, Clone)]
+pub struct ConfigDerivedScheme {
+    pub slug: String,
+    /// Full id of the scheme to derive from, e.g. `base16-catppuccin-mocha`.
+    pub base: String,
+    pub name: Option<String>,
+    pub author: Option<String>,
+    pub description: Option<String>,
+    /// Applied in order, before `overrides`.
+    #[serde(default)]
+    pub operations: Vec<DeriveOperation>,
+    /// Replaces palette slots with fixed colors, e.g. `base00 = "#11111b"`.
+    #[serde(default)]
+    pub overrides: BTreeMap<String, String>,
+}
+
+impl fmt::Display for ConfigDerivedScheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut lines = vec![
+            "[[derived-schemes]]".to_string(),
+            format!("slug = \"{}\"", self.slug),
+            format!("base = \"{}\"", self.base),
+        ];
+        if let Some(name) = &self.name {
+            lines.push(format!("name = \"{name}\""));
+        }
+        if let Some(author) = &self.author {
+            lines.push(format!("author = \"{author}\""));
+        }
+        if let Some(description) = &self.description {
+            lines.push(format!("description = \"{description}\""));
+        }
+        if !self.operations.is_empty() {
+            lines.push("operations = [".to_string());
+            lines.extend(
+                self.operations
+                    .iter()
+                    .map(|operation| format!("  {operation},")),
+            );
+            lines.push("]".to_string());
+        }
+        if !self.overrides.is_empty() {
+            let overrides_text = self
+                .overrides
+                .iter()
+                .map(|(slot, color)| format!("{slot} = \"{color}\""))
+                .collect::<Vec<String>>()
+                .join(", ");
+            lines.push(format!("overrides = {{ {overrides_text} }}"));
+        }
+
+        writeln!(f)?;
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
 /// Settings for the built-in schemes repository, which has no `[[items]]`
 /// entry of its own. Grouped under a `[schemes]` table so more schemes-repo
 /// specific options can be added here in the future.
@@ -122,6 +645,39 @@ pub struct SchemesConfig {
     pub revision: Option<String>,
 }
 
+/// An extra schemes repository from a `[[scheme-sources]]` entry, installed
+/// into `repos/<name>` next to the items. Its schemes join the official ones in
+/// one namespace: when two sources have a scheme with the same ID, the one with
+/// the higher `priority` wins, and the other stays reachable as `<name>:<id>`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct ConfigSchemeSource {
+    pub name: String,
+    /// A Git URL, a local directory or an archive, like an item's `path`.
+    pub path: String,
+    pub revision: Option<String>,
+    /// Precedence over other sources. The official schemes repository has
+    /// priority 0 and wins ties; between sources, a tie goes to the one listed
+    /// first.
+    #[serde(default)]
+    pub priority: i32,
+}
+
+impl fmt::Display for ConfigSchemeSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f)?;
+        writeln!(f, "[[scheme-sources]]")?;
+        writeln!(f, "name = \"{}\"", self.name)?;
+        write!(f, "path = \"{}\"", self.path)?;
+        if let Some(revision) = &self.revision {
+            write!(f, "\nrevision = \"{revision}\"")?;
+        }
+        if self.priority != 0 {
+            write!(f, "\npriority = {}", self.priority)?;
+        }
+        Ok(())
+    }
+}
+
 /// Rejects a `[schemes].path` (a local directory) that resolves to tinty's own
 /// managed schemes directory (`repos/schemes`). Symlinking that slot to itself,
 /// or cloning it into itself, is a circular reference. Git URL sources can never
@@ -152,6 +708,47 @@ pub fn ensure_schemes_path_not_circular(source: &str, schemes_repo_path: &Path)
     Ok(())
 }
 
+/// Overrides from a `[profiles.<name>]` table, applied by
+/// `Config::read_with_profile` when the profile is selected with `--profile`
+/// or `TINTY_PROFILE`.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct ConfigProfile {
+    #[serde(rename = "default-scheme")]
+    pub default_scheme: Option<String>,
+    /// Names of the `[[items]]` that `apply` themes. Unset themes every item.
+    /// Other commands, such as `install` and `prune`, still see every item.
+    pub items: Option<Vec<String>>,
+    pub hooks: Option<Vec<String>>,
+    pub shell: Option<String>,
+}
+
+impl ConfigProfile {
+    fn fmt_as(&self, f: &mut fmt::Formatter<'_>, name: &str) -> fmt::Result {
+        let quoted = |values: &[String]| {
+            values
+                .iter()
+                .map(|value| format!("\"{value}\""))
+                .collect::<Vec<String>>()
+                .join(", ")
+        };
+
+        writeln!(f, "\n[profiles.{name}]")?;
+        if let Some(default_scheme) = &self.default_scheme {
+            writeln!(f, "default-scheme = \"{default_scheme}\"")?;
+        }
+        if let Some(items) = &self.items {
+            writeln!(f, "items = [{}]", quoted(items))?;
+        }
+        if let Some(hooks) = &self.hooks {
+            writeln!(f, "hooks = [{}]", quoted(hooks))?;
+        }
+        if let Some(shell) = &self.shell {
+            writeln!(f, "shell = \"{shell}\"")?;
+        }
+        Ok(())
+    }
+}
+
 /// Structure for configuration
 #[derive(Deserialize, Debug)]
 pub struct Config {
@@ -165,8 +762,132 @@ pub struct Config {
     pub default_cycle_ring: Option<String>,
     pub items: Option<Vec<ConfigItem>>,
     pub hooks: Option<Vec<String>>,
+    /// Default for `--reapply` on `update` and `sync`.
+    #[serde(default)]
+    pub reapply: bool,
+    /// Seconds a single clone or fetch may take before it is stopped. `0`
+    /// never stops it.
+    #[serde(rename = "git-timeout")]
+    pub git_timeout: Option<u64>,
+    /// How often a clone or fetch that failed because of the network or a
+    /// timeout is tried again.
+    #[serde(rename = "git-retries")]
+    pub git_retries: Option<u32>,
+    /// When `true`, Git sources are cloned and updated through bare mirrors
+    /// shared by every data directory. See [`Config::repo_cache`].
+    #[serde(default, rename = "shared-cache")]
+    pub shared_cache: bool,
+    #[serde(rename = "shared-cache-dir")]
+    pub shared_cache_dir: Option<String>,
     #[serde(default)]
     pub schemes: SchemesConfig,
+    #[serde(rename = "scheme-sources")]
+    pub scheme_sources: Option<Vec<ConfigSchemeSource>>,
+    #[serde(rename = "derived-schemes")]
+    pub derived_schemes: Option<Vec<ConfigDerivedScheme>>,
+    /// Short names for schemes, e.g. `work = "base16-github"`. Accepted
+    /// anywhere a scheme name is, including `default-scheme` and rings.
+    #[serde(default)]
+    pub aliases: BTreeMap<String, String>,
+    #[serde(default)]
+    pub profiles: BTreeMap<String, ConfigProfile>,
+    /// The profile `read` applied, whose `items` limit `apply_items`.
+    #[serde(skip)]
+    pub active_profile: Option<String>,
+    /// The file each value was read from, keyed by its dotted path. See
+    /// [`includes`].
+    #[serde(skip)]
+    pub origins: BTreeMap<String, PathBuf>,
+    /// Items whose `when` doesn't hold on this machine, and why. Left out of
+    /// `items`, but still configured, so `prune` keeps their repositories.
+    #[serde(skip)]
+    pub skipped_items: Vec<(ConfigItem, String)>,
+}
+
+/// Checks every `[profiles.<name>]` table's name and `items`.
+fn validate_profiles(config: &Config) -> Result<()> {
+    let item_names: Vec<&str> = config.items.as_ref().map_or_else(
+        || vec![BASE16_SHELL_REPO_NAME],
+        |items| items.iter().map(|item| item.name.as_str()).collect(),
+    );
+    for (name, settings) in &config.profiles {
+        if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+            return Err(anyhow!(
+                "config.toml [profiles.{name}] has an invalid name. Profile names are used as directory names, so they can't be empty, start with a `.` or contain path separators"
+            ));
+        }
+        for item in settings.items.iter().flatten() {
+            if !item_names.contains(&item.as_str()) {
+                return Err(anyhow!(
+                    "config.toml [profiles.{name}].items includes \"{item}\", but no item with that name exists"
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Validates every profile, then applies `profile`'s overrides to `config`.
+fn apply_profile(config: &mut Config, profile: Option<&str>) -> Result<()> {
+    validate_profiles(config)?;
+    let Some(profile) = profile else {
+        return Ok(());
+    };
+    let Some(settings) = config.profiles.get(profile).cloned() else {
+        let known: Vec<&str> = config.profiles.keys().map(String::as_str).collect();
+        return Err(anyhow!(
+            "Profile \"{profile}\" (from --profile or {PROFILE_ENV_VAR}) is not defined in config.toml. Defined profiles: {}",
+            if known.is_empty() {
+                "none".to_string()
+            } else {
+                known.join(", ")
+            }
+        ));
+    };
+
+    if settings.default_scheme.is_some() {
+        config.default_scheme = settings.default_scheme;
+    }
+    if settings.hooks.is_some() {
+        config.hooks = settings.hooks;
+    }
+    if settings.shell.is_some() {
+        config.shell = settings.shell;
+    }
+    config.active_profile = Some(profile.to_string());
+
+    Ok(())
+}
+
+/// Whether a `path` that is not a URL names an existing local directory or
+/// archive.
+fn is_existing_local_source(path: &str) -> bool {
+    let local_path = Path::new(path);
+
+    local_path.is_dir() || (repo::is_archive(path) && local_path.is_file())
+}
+
+/// Refuses `verify-signatures` on a source without commits to verify, rather
+/// than silently skipping the check, and expands and checks `allowed-signers`.
+fn normalize_signature_settings(item: &mut ConfigItem) -> Result<()> {
+    if item.verify_signatures && repo::is_local_path(&item.path) {
+        return Err(anyhow!("config.toml item \"{}\" has `verify-signatures = true`, but its `path` is not a Git URL. Signatures can only be verified for Git sources", item.name));
+    }
+
+    if let Some(allowed_signers) = item.allowed_signers.as_deref() {
+        let expanded = replace_tilde_slash_with_home(allowed_signers)?;
+        if !expanded.exists() {
+            return Err(anyhow!(
+                "config.toml item \"{}\" has `allowed-signers = \"{}\"`, which does not exist",
+                item.name,
+                expanded.display()
+            ));
+        }
+        item.allowed_signers = Some(expanded.to_string_lossy().into_owned());
+    }
+
+    Ok(())
 }
 
 fn ensure_item_name_is_unique(items: &[ConfigItem]) -> Result<()> {
@@ -185,6 +906,60 @@ fn ensure_item_name_is_unique(items: &[ConfigItem]) -> Result<()> {
     Ok(())
 }
 
+/// Normalizes and validates the optional `[schemes].path` the same way item
+/// paths are handled: expands a leading `~/`, then requires it be a valid URL,
+/// an existing local directory or an archive. Unlike an item, a local
+/// directory need not be a Git repository.
+fn normalize_schemes_path(schemes: &mut SchemesConfig) -> Result<()> {
+    if let Some(raw_path) = schemes.path.clone() {
+        let expanded = replace_tilde_slash_with_home(&raw_path)?
+            .to_string_lossy()
+            .into_owned();
+
+        if Url::parse(&expanded).is_err() && !is_existing_local_source(&expanded) {
+            return Err(anyhow!("config.toml [schemes].path \"{expanded}\" is not a valid url and is not a path to an existing local directory or archive"));
+        }
+
+        schemes.path = Some(expanded);
+    }
+
+    Ok(())
+}
+
+/// Scheme source names share `repos/` with the items and name the source in a
+/// `<name>:<id>` scheme name, so they must be unique across both and can't
+/// contain `:`. Paths are expanded and checked like an item's.
+fn normalize_scheme_sources(
+    scheme_sources: &mut [ConfigSchemeSource],
+    items: &[ConfigItem],
+) -> Result<()> {
+    let mut names: HashSet<&str> = items.iter().map(|item| item.name.as_str()).collect();
+
+    for source in scheme_sources.iter_mut() {
+        if source.name.trim().is_empty() || source.name.contains(SCHEME_SOURCE_SEPARATOR) {
+            return Err(anyhow!("config.toml scheme-sources.name \"{}\" is invalid. Names must be non-empty and can't contain `{SCHEME_SOURCE_SEPARATOR}`.", source.name));
+        }
+
+        if source.name == SCHEMES_REPO_NAME {
+            return Err(anyhow!("config.toml scheme-sources.name \"{SCHEMES_REPO_NAME}\" is reserved for the built-in schemes repository. Please rename this scheme source."));
+        }
+
+        if !names.insert(&source.name) {
+            return Err(anyhow!("config.toml scheme-sources.name \"{}\" is already used by another item or scheme source. Please change this to a unique value.", source.name));
+        }
+
+        let expanded = replace_tilde_slash_with_home(&source.path)?
+            .to_string_lossy()
+            .into_owned();
+        if Url::parse(&expanded).is_err() && !is_existing_local_source(&expanded) {
+            return Err(anyhow!("config.toml scheme-sources.path \"{expanded}\" is not a valid url and is not a path to an existing local directory or archive"));
+        }
+        source.path = expanded;
+    }
+
+    Ok(())
+}
+
 fn ensure_ring_names_are_valid(rings: &[ConfigRing]) -> Result<()> {
     let mut names = HashSet::new();
 
@@ -201,6 +976,60 @@ fn ensure_ring_names_are_valid(rings: &[ConfigRing]) -> Result<()> {
     Ok(())
 }
 
+fn ensure_derived_schemes_are_valid(derived_schemes: &[ConfigDerivedScheme]) -> Result<()> {
+    let mut slugs = HashSet::new();
+
+    for derived in derived_schemes {
+        if derived.slug.trim().is_empty()
+            || derived
+                .slug
+                .chars()
+                .any(|c| c.is_whitespace() || c.is_uppercase())
+        {
+            return Err(anyhow!("config.toml derived-schemes.slug \"{}\" is invalid. Slugs must be non-empty and can not contain white-space or capitalization.", derived.slug));
+        }
+
+        if !slugs.insert(&derived.slug) {
+            return Err(anyhow!("config.toml derived-schemes.slug should be unique values, but \"{}\" is used for more than 1 derived-schemes.slug. Please change this to a unique value.", derived.slug));
+        }
+
+        if !derived.base.contains('-') {
+            return Err(anyhow!("config.toml derived-schemes.base \"{}\" for \"{}\" must be a full scheme name prefixed with its system, eg: base16-mocha", derived.base, derived.slug));
+        }
+
+        for (slot, color) in &derived.overrides {
+            Rgb::from_hex(color).with_context(|| {
+                format!(
+                    "config.toml derived-schemes.overrides.{slot} for \"{}\" is not a valid hex color",
+                    derived.slug
+                )
+            })?;
+        }
+    }
+
+    Ok(())
+}
+
+fn ensure_aliases_are_valid(aliases: &BTreeMap<String, String>) -> Result<()> {
+    for (alias, target) in aliases {
+        if alias.is_empty()
+            || !alias
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(anyhow!("config.toml aliases key \"{alias}\" is invalid. Aliases must be non-empty and can only contain letters, digits, `-` and `_`."));
+        }
+
+        if target.trim().is_empty() {
+            return Err(anyhow!(
+                "config.toml aliases.{alias} should be the name of a scheme, eg: base16-mocha"
+            ));
+        }
+    }
+
+    Ok(())
+}
+
 impl Config {
     /// Resolves the effective source and revision for the built-in schemes
     /// repository from the `[schemes]` table.
@@ -229,7 +1058,117 @@ impl Config {
         )
     }
 
+    /// How long a clone or fetch may take and how often it is retried, from
+    /// `git-timeout` and `git-retries`. An item may override both.
+    pub fn network_options(&self) -> NetworkOptions {
+        network_options(
+            NetworkOptions::default(),
+            self.git_timeout,
+            self.git_retries,
+        )
+    }
+
+    /// The shared repository cache, when `shared-cache` is on: `shared-cache-dir`,
+    /// or `$XDG_CACHE_HOME/tinty`. With `is_offline` its mirrors are used
+    /// without being refreshed.
+    pub fn repo_cache(&self, is_offline: bool) -> Result<Option<RepoCache>> {
+        if !self.shared_cache {
+            return Ok(None);
+        }
+
+        let dir = match &self.shared_cache_dir {
+            Some(dir) => replace_tilde_slash_with_home(dir)?,
+            None => BaseDirectories::with_prefix(REPO_NAME)
+                .context("Unable to find the XDG cache directory")?
+                .get_cache_home(),
+        };
+
+        Ok(Some(RepoCache {
+            dir,
+            refresh: !is_offline,
+        }))
+    }
+
+    /// `CloneOptions` for the schemes repository and `[[scheme-sources]]`,
+    /// which only take the global network settings and the shared cache.
+    /// Items start from these too.
+    pub fn default_clone_options(&self, is_offline: bool) -> Result<CloneOptions> {
+        Ok(CloneOptions {
+            network: self.network_options(),
+            cache: self.repo_cache(is_offline)?,
+            ..CloneOptions::default()
+        })
+    }
+
+    /// The file each value came from, as TOML comments, or `None` when the
+    /// config was read from a single file.
+    pub fn describe_origins(&self) -> Option<String> {
+        let files: HashSet<&PathBuf> = self.origins.values().collect();
+        if files.len() < 2 {
+            return None;
+        }
+
+        let mut out = String::from("# Where each value came from:\n");
+        for (key, file) in &self.origins {
+            let _ = writeln!(out, "# {key} = {}", file.display());
+        }
+        Some(out)
+    }
+
+    /// Every configured item, including the ones `when` leaves out on this
+    /// machine.
+    pub fn all_items(&self) -> impl Iterator<Item = &ConfigItem> {
+        self.items
+            .iter()
+            .flatten()
+            .chain(self.skipped_items.iter().map(|(item, _)| item))
+    }
+
+    /// The items `apply` themes: every item, or only the active profile's
+    /// `items`.
+    pub fn apply_items(&self) -> Vec<&ConfigItem> {
+        let profile_items = self
+            .active_profile
+            .as_ref()
+            .and_then(|profile| self.profiles.get(profile))
+            .and_then(|profile| profile.items.as_ref());
+
+        self.items
+            .iter()
+            .flatten()
+            .filter(|item| profile_items.is_none_or(|names| names.contains(&item.name)))
+            .collect()
+    }
+
+    /// The name and source of every repository tinty manages: each
+    /// `[[items]]` entry, each `[[scheme-sources]]` entry, then the schemes
+    /// repository.
+    pub fn repo_sources(&self) -> Vec<(String, String)> {
+        self.items
+            .iter()
+            .flatten()
+            .map(|item| (item.name.clone(), item.path.clone()))
+            .chain(
+                self.scheme_sources
+                    .iter()
+                    .flatten()
+                    .map(|source| (source.name.clone(), source.path.clone())),
+            )
+            .chain(std::iter::once((
+                SCHEMES_REPO_NAME.to_string(),
+                self.schemes_source().0,
+            )))
+            .collect()
+    }
+
     pub fn read(path: &Path) -> Result<Self> {
+        Self::read_with_profile(path, None)
+    }
+
+    /// Reads the config like `read`, then applies the overrides of `profile`,
+    /// the `[profiles.<name>]` table selected with `--profile` or
+    /// `TINTY_PROFILE`.
+    pub fn read_with_profile(path: &Path, profile: Option<&str>) -> Result<Self> {
         if path.exists() && !path.is_file() {
             return Err(anyhow!(
                 "The provided config path is a directory and not a file: {}",
@@ -237,41 +1176,36 @@ impl Config {
             ));
         }
 
-        let contents = fs::read_to_string(path).unwrap_or_default();
-        let mut config: Self = toml::from_str(contents.as_str()).with_context(|| {
+        let merged = includes::load(path)?;
+        let mut ignored = Vec::new();
+        let mut config = deserialize(merged.table, |path| ignored.push(path)).with_context(|| {
             format!(
                 "Couldn't parse {REPO_NAME} configuration file ({}). Check if it's syntactically correct",
                 path.display()
             )
         })?;
+        config.origins = merged.origins;
+        ensure_condition_keys_are_known(&config, &ignored)?;
 
-        // Create default `item`
-        let shell = config
-            .shell
-            .clone()
-            .unwrap_or_else(|| DEFAULT_CONFIG_SHELL.into());
-        let base16_shell_config_item = ConfigItem {
-            path: BASE16_SHELL_REPO_URL.to_string(),
-            name: BASE16_SHELL_REPO_NAME.to_string(),
-            themes_dir: BASE16_SHELL_THEMES_DIR.to_string(),
-            hook: Some(BASE16_SHELL_HOOK.to_string()),
-            supported_systems: Some(vec![SchemeSystem::Base16]), // DEFAULT_SCHEME_SYSTEM
-            theme_file_extension: None,
-            revision: None,
-            write_to_file: None,
-            allow_dirty_update: false,
-        };
+        apply_profile(&mut config, profile)?;
 
         // Add default `item` if no items exist
-        match config.items.as_ref() {
+        match config.items.as_mut() {
             Some(items) => {
                 ensure_item_name_is_unique(items)?;
+                config.skipped_items = skip_unmatched_items(items)?;
             }
             None => {
-                config.items = Some(vec![base16_shell_config_item]);
+                config.items = Some(vec![default_item()]);
             }
         }
 
+        expand_env_vars(&mut config)?;
+        let shell = config
+            .shell
+            .clone()
+            .unwrap_or_else(|| DEFAULT_CONFIG_SHELL.into());
+
         if let Some(rings) = config.rings.as_ref() {
             ensure_ring_names_are_valid(rings)?;
 
@@ -288,6 +1222,12 @@ impl Config {
             ));
         }
 
+        if let Some(derived_schemes) = config.derived_schemes.as_ref() {
+            ensure_derived_schemes_are_valid(derived_schemes)?;
+        }
+
+        ensure_aliases_are_valid(&config.aliases)?;
+
         // Set default `system` property for missing systems
         if let Some(ref mut items) = config.items {
             for item in items.iter_mut() {
@@ -312,29 +1252,21 @@ impl Config {
                     }
                 }
 
-                // Return Err if path is not a valid url or an existing directory path
-                if Url::parse(item.path.as_str()).is_err()
-                    && !Path::new(item.path.as_str()).is_dir()
+                // Return Err if path is not a valid url, an existing directory path
+                // or an existing archive
+                if Url::parse(item.path.as_str()).is_err() && !is_existing_local_source(&item.path)
                 {
-                    return Err(anyhow!("One of your config.toml items has an invalid `path` value. \"{}\" is not a valid url and is not a path to an existing local directory", item.path));
+                    return Err(anyhow!("One of your config.toml items has an invalid `path` value. \"{}\" is not a valid url and is not a path to an existing local directory or archive", item.path));
                 }
+
+                normalize_signature_settings(item)?;
             }
         }
 
-        // Normalize and validate the optional `[schemes].path` the same way item
-        // paths are handled: expand a leading `~/`, then require it be a valid
-        // URL or an existing local directory. Unlike an item, a local directory
-        // need not be a Git repository.
-        if let Some(raw_path) = config.schemes.path.clone() {
-            let expanded = replace_tilde_slash_with_home(&raw_path)?
-                .to_string_lossy()
-                .into_owned();
-
-            if Url::parse(&expanded).is_err() && !Path::new(&expanded).is_dir() {
-                return Err(anyhow!("config.toml [schemes].path \"{expanded}\" is not a valid url and is not a path to an existing local directory"));
-            }
+        normalize_schemes_path(&mut config.schemes)?;
 
-            config.schemes.path = Some(expanded);
+        if let Some(scheme_sources) = config.scheme_sources.as_mut() {
+            normalize_scheme_sources(scheme_sources, config.items.as_deref().unwrap_or_default())?;
         }
 
         if !shell.contains("{}") {
@@ -375,6 +1307,26 @@ impl fmt::Display for Config {
             writeln!(f, "preferred-schemes = [{preferred_schemes_text}]")?;
         }
 
+        if self.reapply {
+            writeln!(f, "reapply = true")?;
+        }
+
+        if let Some(git_timeout) = self.git_timeout {
+            writeln!(f, "git-timeout = {git_timeout}")?;
+        }
+
+        if let Some(git_retries) = self.git_retries {
+            writeln!(f, "git-retries = {git_retries}")?;
+        }
+
+        if self.shared_cache {
+            writeln!(f, "shared-cache = true")?;
+        }
+
+        if let Some(shared_cache_dir) = &self.shared_cache_dir {
+            writeln!(f, "shared-cache-dir = \"{shared_cache_dir}\"")?;
+        }
+
         if let Some(hooks) = &self.hooks {
             writeln!(f, "hooks = [")?;
             for hook in hooks {
@@ -401,25 +1353,56 @@ impl fmt::Display for Config {
             }
         }
 
+        for (name, profile) in &self.profiles {
+            profile.fmt_as(f, name)?;
+        }
+
+        if let Some(scheme_sources) = &self.scheme_sources {
+            for source in scheme_sources {
+                writeln!(f, "{source}")?;
+            }
+        }
+
+        if !self.aliases.is_empty() {
+            writeln!(f, "\n[aliases]")?;
+            for (alias, target) in &self.aliases {
+                writeln!(f, "{alias} = \"{target}\"")?;
+            }
+        }
+
         if let Some(rings) = &self.rings {
             for ring in rings {
                 writeln!(f, "{ring}")?;
             }
         }
 
+        if let Some(derived_schemes) = &self.derived_schemes {
+            for derived in derived_schemes {
+                writeln!(f, "{derived}")?;
+            }
+        }
+
         if let Some(items) = &self.items {
             for item in items {
                 writeln!(f, "{item}")?;
             }
         }
 
+        for (item, reason) in &self.skipped_items {
+            writeln!(f, "\n# Not used on this machine: {reason}{item}")?;
+        }
+
         Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{Config, ConfigItem};
+    use super::{
+        apply_profile, deserialize, ensure_aliases_are_valid, ensure_condition_keys_are_known,
+        validate_profiles, CloneOptions, Config, ConfigItem, ItemCondition, Table,
+    };
+    use std::time::Duration;
 
     fn item_with(allow_dirty_update: bool) -> ConfigItem {
         ConfigItem {
@@ -432,6 +1415,13 @@ mod tests {
             revision: None,
             write_to_file: None,
             allow_dirty_update,
+            clone_depth: None,
+            sparse_checkout: false,
+            verify_signatures: false,
+            allowed_signers: None,
+            git_timeout: None,
+            git_retries: None,
+            when: None,
         }
     }
 
@@ -447,6 +1437,68 @@ allow-dirty-update = true
         assert!(item.allow_dirty_update);
     }
 
+    #[test]
+    fn item_clone_options_limit_sparse_checkout_to_themes_and_templates() {
+        let toml = r#"
+name = "example"
+path = "https://example.com/repo"
+themes-dir = "colors/"
+clone-depth = 1
+sparse-checkout = true
+"#;
+        let item: ConfigItem = toml::from_str(toml).unwrap();
+        let options = item.clone_options(&CloneOptions::default());
+
+        assert_eq!(options.depth.map(u32::from), Some(1));
+        assert_eq!(
+            options.sparse_paths,
+            Some(vec!["colors".to_string(), "templates".to_string()])
+        );
+        assert_eq!(
+            item_with(false).clone_options(&CloneOptions::default()),
+            CloneOptions::default()
+        );
+    }
+
+    #[test]
+    fn item_network_options_override_global_ones() {
+        let config: Config = toml::from_str(
+            r#"
+git-timeout = 90
+git-retries = 5
+
+[[items]]
+name = "example"
+path = "https://example.com/repo"
+themes-dir = "themes"
+git-timeout = 0
+"#,
+        )
+        .unwrap();
+        let defaults = config.default_clone_options(false).unwrap();
+        let item = config.items.as_ref().unwrap().first().unwrap();
+
+        assert_eq!(defaults.network.timeout, Some(Duration::from_secs(90)));
+        assert_eq!(defaults.network.retries, 5);
+        assert_eq!(item.clone_options(&defaults).network.timeout, None);
+        assert_eq!(item.clone_options(&defaults).network.retries, 5);
+        assert_eq!(
+            item.network_options(config.network_options()),
+            item.clone_options(&defaults).network
+        );
+    }
+
+    #[test]
+    fn item_clone_depth_rejects_zero() {
+        let toml = r#"
+name = "example"
+path = "https://example.com/repo"
+themes-dir = "themes"
+clone-depth = 0
+"#;
+        assert!(toml::from_str::<ConfigItem>(toml).is_err());
+    }
+
     #[test]
     fn item_allow_dirty_update_defaults_to_false_when_absent() {
         let toml = r#"
@@ -492,6 +1544,21 @@ themes-dir = "themes"
         assert!(!off.to_string().contains("[schemes]"));
     }
 
+    #[test]
+    fn aliases_round_trip_and_reject_invalid_names() {
+        let config: Config =
+            toml::from_str("[aliases]\nwork = \"base16-github\"\nnight = \"tokyo-night\"\n")
+                .unwrap();
+        let rendered = config.to_string();
+        assert!(rendered.contains("[aliases]\nnight = \"tokyo-night\"\nwork = \"base16-github\"\n"));
+        let reparsed: Config = toml::from_str(&rendered).unwrap();
+        assert_eq!(reparsed.aliases, config.aliases);
+
+        let invalid: Config =
+            toml::from_str("[aliases]\n\"my work\" = \"base16-github\"\n").unwrap();
+        assert!(ensure_aliases_are_valid(&invalid.aliases).is_err());
+    }
+
     #[test]
     fn schemes_source_defaults_to_builtin_repo_and_revision() {
         // Backwards-compatible default: no `[schemes]` table at all.
@@ -581,4 +1648,81 @@ themes-dir = "themes"
             .expect_err("expected a circular-reference error");
         assert!(err.to_string().contains("circular reference"));
     }
+
+    #[test]
+    fn profile_overrides_and_limits_apply_items() {
+        let mut config: Config = toml::from_str(
+            "default-scheme = \"base16-mocha\"\nhooks = [\"echo default\"]\n\n[[items]]\nname = \"editor\"\npath = \"https://example.com/editor\"\nthemes-dir = \"themes\"\n\n[[items]]\nname = \"terminal\"\npath = \"https://example.com/terminal\"\nthemes-dir = \"themes\"\n\n[profiles.presentation]\ndefault-scheme = \"base16-github\"\nitems = [\"editor\"]\n",
+        )
+        .unwrap();
+        assert_eq!(config.apply_items().len(), 2);
+
+        apply_profile(&mut config, Some("presentation")).unwrap();
+        assert_eq!(config.default_scheme.as_deref(), Some("base16-github"));
+        assert_eq!(config.hooks, Some(vec!["echo default".to_string()]));
+        let names: Vec<&str> = config
+            .apply_items()
+            .iter()
+            .map(|item| item.name.as_str())
+            .collect();
+        assert_eq!(names, ["editor"]);
+
+        let err = apply_profile(&mut config, Some("travel")).unwrap_err();
+        assert!(err.to_string().contains("Defined profiles: presentation"));
+    }
+
+    #[test]
+    fn profiles_with_unknown_items_or_path_names_are_rejected() {
+        let unknown_item: Config =
+            toml::from_str("[profiles.presentation]\nitems = [\"editor\"]\n").unwrap();
+        assert!(validate_profiles(&unknown_item)
+            .unwrap_err()
+            .to_string()
+            .contains("no item with that name exists"));
+
+        let path_name: Config = toml::from_str("[profiles.\"../work\"]\n").unwrap();
+        assert!(validate_profiles(&path_name)
+            .unwrap_err()
+            .to_string()
+            .contains("invalid name"));
+    }
+
+    #[test]
+    fn item_condition_checks_os_commands_and_env() {
+        let condition = |toml: &str| toml::from_str::<ItemCondition>(toml).unwrap();
+
+        assert_eq!(condition("").mismatch().unwrap(), None);
+        assert_eq!(
+            condition(&format!("os = \"{}\"", std::env::consts::OS))
+                .mismatch()
+                .unwrap(),
+            None
+        );
+        assert!(condition("os = [\"plan9\"]").mismatch().unwrap().is_some());
+        assert_eq!(
+            condition("command-exists = \"tinty-no-such-command\"")
+                .mismatch()
+                .unwrap()
+                .as_deref(),
+            Some("\"tinty-no-such-command\" is not in PATH")
+        );
+        assert_eq!(
+            condition("env = { PATH = \"*\" }").mismatch().unwrap(),
+            None
+        );
+        assert!(condition("env = { TINTY_NO_SUCH_VAR = \"*\" }")
+            .mismatch()
+            .unwrap()
+            .is_some());
+        let table: Table = toml::from_str(
+            "[[items]]\nname = \"vim\"\npath = \"https://example.com/vim\"\nthemes-dir = \"colors\"\nwhen.host = \"work\"\n",
+        )
+        .unwrap();
+        let mut ignored = Vec::new();
+        let config = deserialize(table, |path| ignored.push(path)).unwrap();
+        assert!(ensure_condition_keys_are_known(&config, &ignored)
+            .unwrap_err()
+            .to_string()
+            .contains("\"vim\" has an unknown when key `host`"));
+    }
 }
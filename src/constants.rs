@@ -3,6 +3,9 @@ pub const REPO_NAME: &str = env!("CARGO_PKG_NAME");
 pub const REPO_DIR: &str = "repos";
 pub const ARTIFACTS_DIR: &str = "artifacts";
 pub const LOCK_FILE: &str = ".tinty.lock";
+/// Name of the `tinty.lock` file next to `config.toml` that pins repositories
+/// to commits. Not to be confused with `LOCK_FILE`, which guards `apply`.
+pub const LOCKFILE_NAME: &str = "tinty.lock";
 pub const SCHEMES_REPO_URL: &str = "https://github.com/tinted-theming/schemes";
 pub const SCHEMES_REPO_NAME: &str = "schemes";
 pub const CUSTOM_SCHEMES_DIR_NAME: &str = "custom-schemes";
@@ -11,3 +14,17 @@ pub const DEFAULT_SCHEME_SYSTEM: &str = "base16";
 pub const SCHEMES_REPO_REVISION: &str = "spec-0.11";
 /// Fallback Git revision used when a repository has no configured `revision`.
 pub const DEFAULT_REVISION: &str = "main";
+/// Directory holding a template repository's `config.yaml` and mustache
+/// templates, which `tinty build` reads.
+pub const TEMPLATES_DIR: &str = "templates";
+/// Separates a `[[scheme-sources]]` name from a scheme ID or slug, as in
+/// `company:base16-brand`.
+pub const SCHEME_SOURCE_SEPARATOR: char = ':';
+/// Selects a `[profiles.<name>]` table, like `--profile`, which sets it for
+/// hooks and any `tinty` they run too.
+pub const PROFILE_ENV_VAR: &str = "TINTY_PROFILE";
+/// Directory under the data directory holding each profile's own artifacts.
+pub const PROFILES_DIR: &str = "profiles";
+/// Host name used for `{hostname}` in `include` patterns and for
+/// `when.hostname`, instead of the machine's own.
+pub const HOSTNAME_ENV_VAR: &str = "TINTY_HOSTNAME";
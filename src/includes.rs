@@ -0,0 +1,361 @@
+//! `include` files merged into `config.toml`.
+//!
+//! A config file may list other TOML files in `include`, as paths relative to
+//! itself. A pattern may use `*` and `?` in its file name, which match in
+//! alphabetical order, and `{hostname}`, the machine's short host name (or
+//! `TINTY_HOSTNAME`). Patterns that match nothing are skipped, so a host
+//! without an overlay needs no file.
+//!
+//! A file's own values come first and each included file is merged on top,
+//! in order, with its own includes merged right after it:
+//!
+//! - `[[items]]`, `[[rings]]` and `[[scheme-sources]]` entries are merged by
+//!   `name`, and `[[derived-schemes]]` entries by `slug`. An entry with a new
+//!   name is appended.
+//! - Tables, such as `[schemes]` or `[aliases]`, are merged key by key.
+//! - Anything else, including arrays such as `hooks`, is replaced.
+//!
+//! Every value merged is recorded with the file it came from, so that
+//! `tinty config` can show where the merged config came from.
+
+use crate::constants::REPO_NAME;
+use crate::utils::{hostname, replace_tilde_slash_with_home, wildcard_regex};
+use anyhow::{anyhow, Context, Result};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml::{Table, Value};
+
+/// The key listing the files to include.
+pub const INCLUDE_KEY: &str = "include";
+
+/// Arrays of tables merged entry by entry, and the key naming an entry.
+const NAMED_ARRAYS: [(&str, &str); 4] = [
+    ("items", "name"),
+    ("rings", "name"),
+    ("scheme-sources", "name"),
+    ("derived-schemes", "slug"),
+];
+
+/// The merged config, and the file each value came from, keyed by its dotted
+/// path, e.g. `items.tinted-vim.revision`.
+pub struct MergedConfig {
+    pub table: Table,
+    pub origins: BTreeMap<String, PathBuf>,
+    /// Every file read, in the order they were merged.
+    pub files: Vec<PathBuf>,
+}
+
+/// Reads `path` and every file it includes. A missing `path` reads as an
+/// empty config.
+pub fn load(path: &Path) -> Result<MergedConfig> {
+    let mut merged = MergedConfig {
+        table: Table::new(),
+        origins: BTreeMap::new(),
+        files: Vec::new(),
+    };
+    let contents = fs::read_to_string(path).unwrap_or_default();
+    let mut stack = Vec::new();
+    merge_file(path, &contents, &mut stack, &mut merged)?;
+
+    Ok(merged)
+}
+
+/// Merges the file at `path` and then its includes into `merged`. `stack`
+/// holds the files being included, to refuse cycles.
+fn merge_file(
+    path: &Path,
+    contents: &str,
+    stack: &mut Vec<PathBuf>,
+    merged: &mut MergedConfig,
+) -> Result<()> {
+    let mut table: Table = toml::from_str(contents).with_context(|| {
+        format!(
+            "Couldn't parse {REPO_NAME} configuration file ({}). Check if it's syntactically correct",
+            path.display()
+        )
+    })?;
+    let patterns = include_patterns(table.remove(INCLUDE_KEY), path)?;
+    merge_table(&mut merged.table, table, "", path, &mut merged.origins);
+    merged.files.push(path.to_path_buf());
+
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    stack.push(fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf()));
+    for pattern in patterns {
+        for included in expand(&pattern, dir)? {
+            let canonical = fs::canonicalize(&included)?;
+            if stack.contains(&canonical) {
+                return Err(anyhow!(
+                    "Include cycle: {} includes {} again",
+                    path.display(),
+                    included.display()
+                ));
+            }
+            let contents = fs::read_to_string(&included).with_context(|| {
+                format!(
+                    "Unable to read {}, included by {}",
+                    included.display(),
+                    path.display()
+                )
+            })?;
+            merge_file(&included, &contents, stack, merged)?;
+        }
+    }
+    stack.pop();
+
+    Ok(())
+}
+
+fn include_patterns(value: Option<Value>, path: &Path) -> Result<Vec<String>> {
+    let invalid = || {
+        anyhow!(
+            "`{INCLUDE_KEY}` in {} should be an array of file paths",
+            path.display()
+        )
+    };
+
+    match value {
+        None => Ok(Vec::new()),
+        Some(Value::Array(patterns)) => patterns
+            .into_iter()
+            .map(|pattern| match pattern {
+                Value::String(pattern) => Ok(pattern),
+                _ => Err(invalid()),
+            })
+            .collect(),
+        Some(_) => Err(invalid()),
+    }
+}
+
+/// The files `pattern` names, relative to `dir`.
+fn expand(pattern: &str, dir: &Path) -> Result<Vec<PathBuf>> {
+    let pattern = if pattern.contains("{hostname}") {
+        pattern.replace("{hostname}", &hostname()?)
+    } else {
+        pattern.to_string()
+    };
+    let path = dir.join(replace_tilde_slash_with_home(&pattern)?);
+    let (Some(parent), Some(file_name)) = (path.parent(), path.file_name()) else {
+        return Ok(Vec::new());
+    };
+    let file_name = file_name.to_string_lossy();
+    if parent.to_string_lossy().contains(['*', '?']) {
+        return Err(anyhow!(
+            "`{INCLUDE_KEY}` pattern \"{pattern}\" has a wildcard in a directory name, but only file names can have wildcards"
+        ));
+    }
+    if !file_name.contains(['*', '?']) {
+        return Ok(if path.is_file() {
+            vec![path]
+        } else {
+            Vec::new()
+        });
+    }
+
+    let regex = wildcard_regex(&file_name)?;
+    let Ok(entries) = fs::read_dir(parent) else {
+        return Ok(Vec::new());
+    };
+    let mut matches: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .file_name()
+                    .is_some_and(|name| regex.is_match(&name.to_string_lossy()))
+        })
+        .collect();
+    matches.sort();
+
+    Ok(matches)
+}
+
+fn key_path(prefix: &str, key: &str) -> String {
+    if prefix.is_empty() {
+        key.to_string()
+    } else {
+        format!("{prefix}.{key}")
+    }
+}
+
+/// The key naming the entries of the top-level array `key`, if it is merged
+/// entry by entry.
+fn entry_key(prefix: &str, key: &str) -> Option<&'static str> {
+    if !prefix.is_empty() {
+        return None;
+    }
+
+    NAMED_ARRAYS
+        .iter()
+        .find(|(array, _)| *array == key)
+        .map(|(_, entry_key)| *entry_key)
+}
+
+fn entry_name<'a>(entry: &'a Value, entry_key: &str) -> Option<&'a str> {
+    entry.get(entry_key).and_then(Value::as_str)
+}
+
+fn merge_table(
+    base: &mut Table,
+    overlay: Table,
+    prefix: &str,
+    origin: &Path,
+    origins: &mut BTreeMap<String, PathBuf>,
+) {
+    for (key, value) in overlay {
+        let path = key_path(prefix, &key);
+        let entry_key = entry_key(prefix, &key);
+
+        match (base.get_mut(&key), value, entry_key) {
+            (Some(Value::Table(base)), Value::Table(overlay), _) => {
+                merge_table(base, overlay, &path, origin, origins);
+            }
+            (Some(Value::Array(base)), Value::Array(overlay), Some(entry_key)) => {
+                merge_entries(base, overlay, entry_key, &path, origin, origins);
+            }
+            (_, value, entry_key) => {
+                record(&value, entry_key, &path, origin, origins);
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Merges each entry of `overlay` into the `base` entry of the same name, or
+/// appends it.
+fn merge_entries(
+    base: &mut Vec<Value>,
+    overlay: Vec<Value>,
+    entry_key: &str,
+    path: &str,
+    origin: &Path,
+    origins: &mut BTreeMap<String, PathBuf>,
+) {
+    for entry in overlay {
+        let existing = entry_name(&entry, entry_key).and_then(|name| {
+            base.iter_mut()
+                .find(|existing| entry_name(existing, entry_key) == Some(name))
+        });
+
+        match (existing, entry) {
+            (Some(Value::Table(existing)), Value::Table(entry)) => {
+                let entry_path = key_path(path, entry_name_of(existing, entry_key));
+                merge_table(existing, entry, &entry_path, origin, origins);
+            }
+            (_, entry) => {
+                record_entry(&entry, entry_key, path, origin, origins);
+                base.push(entry);
+            }
+        }
+    }
+}
+
+fn entry_name_of<'a>(entry: &'a Table, entry_key: &str) -> &'a str {
+    entry
+        .get(entry_key)
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+}
+
+/// Records `origin` for every value in `value`, found at `path`.
+fn record(
+    value: &Value,
+    entry_key: Option<&str>,
+    path: &str,
+    origin: &Path,
+    origins: &mut BTreeMap<String, PathBuf>,
+) {
+    match (value, entry_key) {
+        (Value::Table(table), _) => {
+            for (key, value) in table {
+                record(value, None, &key_path(path, key), origin, origins);
+            }
+        }
+        (Value::Array(entries), Some(entry_key)) => {
+            for entry in entries {
+                record_entry(entry, entry_key, path, origin, origins);
+            }
+        }
+        _ => {
+            origins.insert(path.to_string(), origin.to_path_buf());
+        }
+    }
+}
+
+fn record_entry(
+    entry: &Value,
+    entry_key: &str,
+    path: &str,
+    origin: &Path,
+    origins: &mut BTreeMap<String, PathBuf>,
+) {
+    let entry_path = key_path(path, entry_name(entry, entry_key).unwrap_or_default());
+    record(entry, None, &entry_path, origin, origins);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::load;
+    use std::fs;
+    use std::path::Path;
+
+    fn write(path: &Path, contents: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    #[test]
+    fn includes_merge_items_by_name_and_override_scalars() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = dir.path().join("config.toml");
+        write(
+            &config,
+            "include = [\"items/*.toml\"]\ndefault-scheme = \"base16-mocha\"\nhooks = [\"echo base\"]\n\n[[items]]\nname = \"vim\"\npath = \"https://example.com/vim\"\nthemes-dir = \"colors\"\n",
+        );
+        write(
+            &dir.path().join("items/a.toml"),
+            "default-scheme = \"base16-github\"\nhooks = [\"echo a\"]\n\n[[items]]\nname = \"vim\"\nrevision = \"v2\"\n\n[[items]]\nname = \"shell\"\npath = \"https://example.com/shell\"\nthemes-dir = \"scripts\"\n",
+        );
+        write(&dir.path().join("items/b.txt"), "not = \"included\"\n");
+
+        let merged = load(&config).unwrap();
+
+        assert_eq!(
+            merged.table.get("default-scheme").unwrap().as_str(),
+            Some("base16-github")
+        );
+        assert_eq!(
+            merged.table.get("hooks").unwrap().as_array().unwrap().len(),
+            1
+        );
+        assert!(merged.table.get("not").is_none());
+        let items = merged.table.get("items").unwrap().as_array().unwrap();
+        assert_eq!(items.len(), 2);
+        let vim = items.first().unwrap();
+        assert_eq!(vim.get("themes-dir").unwrap().as_str(), Some("colors"));
+        assert_eq!(vim.get("revision").unwrap().as_str(), Some("v2"));
+        assert_eq!(merged.origins.get("items.vim.themes-dir"), Some(&config));
+        assert_eq!(
+            merged.origins.get("items.vim.revision"),
+            Some(&dir.path().join("items/a.toml"))
+        );
+        assert_eq!(
+            merged.origins.get("items.shell.path"),
+            Some(&dir.path().join("items/a.toml"))
+        );
+    }
+
+    #[test]
+    fn include_cycles_are_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = dir.path().join("config.toml");
+        write(&config, "include = [\"other.toml\"]\n");
+        write(
+            &dir.path().join("other.toml"),
+            "include = [\"config.toml\"]\n",
+        );
+
+        let err = load(&config).err().unwrap();
+        assert!(err.to_string().contains("Include cycle"));
+    }
+}
@@ -0,0 +1,78 @@
+//! Runs per-repository work, such as cloning or pulling every `[[items]]`
+//! entry, concurrently on a bounded thread pool.
+//!
+//! Each job returns the message it would have printed instead of printing it,
+//! so the output of concurrent jobs is not interleaved: once every job has
+//! finished, the messages are printed in the order the jobs were given, which
+//! is the order of the config. A failing job does not stop the others; like
+//! `build_all_items`, every failure is collected into one error at the end.
+
+use anyhow::{anyhow, Result};
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use rayon::ThreadPoolBuilder;
+use std::num::NonZeroUsize;
+
+/// A unit of work for one repository. `run` returns the message to print when
+/// it succeeds.
+pub struct Job<'a> {
+    pub name: String,
+    pub run: Box<dyn Fn() -> Result<String> + Send + Sync + 'a>,
+}
+
+impl<'a> Job<'a> {
+    pub fn new(
+        name: impl Into<String>,
+        run: impl Fn() -> Result<String> + Send + Sync + 'a,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            run: Box::new(run),
+        }
+    }
+}
+
+/// Runs `jobs` with at most `max_jobs` at a time (one per CPU when `None`),
+/// then prints their output in order. `action` names what the jobs do in the
+/// aggregated error, e.g. "install".
+pub fn run_jobs(
+    action: &str,
+    jobs: &[Job],
+    max_jobs: Option<NonZeroUsize>,
+    is_quiet: bool,
+) -> Result<()> {
+    let pool = ThreadPoolBuilder::new()
+        // Zero lets rayon pick the number of threads.
+        .num_threads(max_jobs.map_or(0, NonZeroUsize::get))
+        .build()?;
+    let results: Vec<Result<String>> =
+        pool.install(|| jobs.par_iter().map(|job| (job.run)()).collect());
+
+    let mut failures: Vec<String> = Vec::new();
+
+    for (job, result) in jobs.iter().zip(results) {
+        match result {
+            Ok(output) => {
+                if !is_quiet && !output.is_empty() {
+                    println!("{output}");
+                }
+            }
+            Err(err) => {
+                if !is_quiet {
+                    eprintln!("{} failed to {action}: {err:#}", job.name);
+                }
+                failures.push(format!("{}: {err:#}", job.name));
+            }
+        }
+    }
+
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "Failed to {action} {} of {} repositories:\n{}",
+            failures.len(),
+            jobs.len(),
+            failures.join("\n")
+        ))
+    }
+}
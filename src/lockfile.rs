@@ -0,0 +1,270 @@
+//! `tinty.lock`, which pins every Git repository tinty manages to a commit.
+//!
+//! An item's or `[schemes]`'s `revision` may be a branch, so two machines that
+//! sync a week apart can end up with different templates. The lockfile lives
+//! next to `config.toml` and records the commit each repository was resolved
+//! to: `install` pins repositories that are not in it yet, `update
+//! --write-lock` moves the pins to whatever `update` checked out, and `sync
+//! --locked` checks out exactly the pinned commits. Local-directory sources
+//! are used as-is and are never pinned.
+
+use crate::config::Config;
+use crate::constants::{LOCKFILE_NAME, REPO_NAME, SCHEMES_REPO_NAME};
+use crate::paths;
+use crate::repo;
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The contents of `tinty.lock`: pinned repositories, keyed by item name
+/// (`schemes` for the built-in schemes repository).
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+pub struct Lockfile {
+    #[serde(default)]
+    pub repos: BTreeMap<String, LockedRepo>,
+}
+
+/// A repository pinned to a commit. `source` is kept so a lock entry made for
+/// a different URL is recognised as stale rather than checked out.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct LockedRepo {
+    pub source: String,
+    pub commit: String,
+}
+
+/// A Git repository from the config that can be pinned.
+struct LockableRepo {
+    name: String,
+    source: String,
+    path: PathBuf,
+}
+
+/// `tinty.lock` in the same directory as `config_path`.
+pub fn lockfile_path(config_path: &Path) -> PathBuf {
+    config_path.parent().map_or_else(
+        || PathBuf::from(LOCKFILE_NAME),
+        |dir| dir.join(LOCKFILE_NAME),
+    )
+}
+
+impl Lockfile {
+    /// Reads the lockfile at `path`, or `None` when there is none yet.
+    pub fn read(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let lockfile = toml::from_str(&contents).with_context(|| {
+            format!(
+                "Couldn't parse {}. Run `{REPO_NAME} update --write-lock` to regenerate it",
+                path.display()
+            )
+        })?;
+
+        Ok(Some(lockfile))
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let contents = format!(
+            "# Generated by {REPO_NAME}. Run `{REPO_NAME} update --write-lock` to update it.\n\n{}",
+            toml::to_string(self)?
+        );
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        fs::write(path, contents).with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+/// The configured Git repositories: every `[[items]]` and `[[scheme-sources]]`
+/// entry with a URL `path`, plus the schemes repository unless
+/// `[schemes].path` is a local directory.
+fn lockable_repos(config: &Config, data_path: &Path) -> Vec<LockableRepo> {
+    let items = config.items.iter().flatten().map(|item| LockableRepo {
+        name: item.name.clone(),
+        source: item.path.clone(),
+        path: paths::item_repo_path(data_path, &item.name),
+    });
+    let scheme_sources = config
+        .scheme_sources
+        .iter()
+        .flatten()
+        .map(|source| LockableRepo {
+            name: source.name.clone(),
+            source: source.path.clone(),
+            path: paths::item_repo_path(data_path, &source.name),
+        });
+    let (schemes_source, _) = config.schemes_source();
+    let schemes = LockableRepo {
+        name: SCHEMES_REPO_NAME.to_string(),
+        source: schemes_source,
+        path: paths::schemes_repo_path(data_path),
+    };
+
+    items
+        .chain(scheme_sources)
+        .chain(std::iter::once(schemes))
+        .filter(|lockable| !repo::is_local_path(&lockable.source))
+        .collect()
+}
+
+/// The commit checked out for a repository as a lock entry, or `None` when the
+/// repository is not installed.
+fn locked_repo(lockable: &LockableRepo) -> Result<Option<LockedRepo>> {
+    Ok(
+        repo::head_commit(&lockable.path, &lockable.source)?.map(|commit| LockedRepo {
+            source: lockable.source.clone(),
+            commit,
+        }),
+    )
+}
+
+/// Pins the repositories that are installed but missing from the lockfile, or
+/// whose source changed, creating the lockfile if needed. Existing pins are
+/// left alone so they only move with `update --write-lock`.
+pub fn pin_new_repos(config_path: &Path, config: &Config, data_path: &Path) -> Result<()> {
+    let path = lockfile_path(config_path);
+    let mut lockfile = Lockfile::read(&path)?.unwrap_or_default();
+    let mut is_changed = false;
+
+    for lockable in lockable_repos(config, data_path) {
+        let is_pinned = lockfile
+            .repos
+            .get(&lockable.name)
+            .is_some_and(|locked| locked.source == lockable.source);
+        if is_pinned {
+            continue;
+        }
+
+        if let Some(locked) = locked_repo(&lockable)? {
+            lockfile.repos.insert(lockable.name, locked);
+            is_changed = true;
+        }
+    }
+
+    if is_changed {
+        lockfile.write(&path)?;
+    }
+
+    Ok(())
+}
+
+/// Rewrites the lockfile from the commits currently checked out, dropping
+/// repositories that are no longer configured. Pins of items that `when`
+/// leaves out on this machine are kept, since the lockfile is shared.
+pub fn write_checked_out(config_path: &Path, config: &Config, data_path: &Path) -> Result<()> {
+    let path = lockfile_path(config_path);
+    let mut lockfile = Lockfile::default();
+    if let Some(existing) = Lockfile::read(&path)? {
+        lockfile.repos = existing
+            .repos
+            .into_iter()
+            .filter(|(name, _)| {
+                config
+                    .skipped_items
+                    .iter()
+                    .any(|(item, _)| item.name == *name)
+            })
+            .collect();
+    }
+
+    for lockable in lockable_repos(config, data_path) {
+        if let Some(locked) = locked_repo(&lockable)? {
+            lockfile.repos.insert(lockable.name, locked);
+        }
+    }
+
+    lockfile.write(&path)
+}
+
+/// The pinned commit for every configured Git repository, keyed by name. Fails
+/// when there is no lockfile, or when a repository is not pinned or was pinned
+/// for a different source, since `sync --locked` could then not reproduce it.
+pub fn locked_commits(config_path: &Path, config: &Config) -> Result<BTreeMap<String, String>> {
+    let path = lockfile_path(config_path);
+    let Some(lockfile) = Lockfile::read(&path)? else {
+        return Err(anyhow!(
+            "{} does not exist. Run `{REPO_NAME} install` or `{REPO_NAME} update --write-lock` to create it",
+            path.display()
+        ));
+    };
+
+    // The data path only matters for reading commits, which is not done here.
+    lockable_repos(config, Path::new(""))
+        .into_iter()
+        .map(|lockable| match lockfile.repos.get(&lockable.name) {
+            Some(locked) if locked.source == lockable.source => {
+                Ok((lockable.name, locked.commit.clone()))
+            }
+            Some(locked) => Err(anyhow!(
+                "{} pins {} to {}, but it is now configured as {}. Run `{REPO_NAME} update --write-lock` to update the lockfile",
+                path.display(),
+                lockable.name,
+                locked.source,
+                lockable.source
+            )),
+            None => Err(anyhow!(
+                "{} has no entry for {}. Run `{REPO_NAME} update --write-lock` to update the lockfile",
+                path.display(),
+                lockable.name
+            )),
+        })
+        .collect()
+}
+
+/// Fails unless each repository in `commits` has its pinned commit checked
+/// out, e.g. because `update` kept local changes instead of checking it out.
+pub fn ensure_checked_out(
+    config: &Config,
+    data_path: &Path,
+    commits: &BTreeMap<String, String>,
+) -> Result<()> {
+    for lockable in lockable_repos(config, data_path) {
+        let Some(commit) = commits.get(&lockable.name) else {
+            continue;
+        };
+        let head = repo::head_commit(&lockable.path, &lockable.source)?;
+
+        if head.as_deref() != Some(commit.as_str()) {
+            return Err(anyhow!(
+                "{} is not at the locked commit {commit} (found {}). Resolve the issue above and run `{REPO_NAME} sync --locked` again",
+                lockable.name,
+                head.as_deref().unwrap_or("nothing")
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{lockfile_path, LockedRepo, Lockfile};
+    use std::path::Path;
+
+    #[test]
+    fn lockfile_round_trips() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = lockfile_path(&temp.path().join("config.toml"));
+        let mut lockfile = Lockfile::default();
+        lockfile.repos.insert(
+            "tinted-shell".to_string(),
+            LockedRepo {
+                source: "https://github.com/tinted-theming/tinted-shell".to_string(),
+                commit: "0123456789abcdef0123456789abcdef01234567".to_string(),
+            },
+        );
+
+        lockfile.write(&path).unwrap();
+
+        assert_eq!(path, temp.path().join("tinty.lock"));
+        assert_eq!(Lockfile::read(&path).unwrap(), Some(lockfile));
+        assert_eq!(Lockfile::read(Path::new("/does/not/exist")).unwrap(), None);
+    }
+}
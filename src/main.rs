@@ -1,33 +1,47 @@
 mod cli;
+mod color;
 mod config;
 mod constants;
 mod operations {
     pub mod apply;
     pub mod build;
+    pub mod bundle;
     pub mod config;
     pub mod current;
     pub mod cycle;
+    pub mod derive;
     pub mod gallery;
     pub mod generate_scheme;
     pub mod info;
     pub mod init;
     pub mod install;
     pub mod list;
+    pub mod outdated;
+    pub mod prune;
+    pub mod search;
+    pub mod status;
     pub mod sync;
     pub mod update;
 }
+mod changelog;
+mod includes;
+mod jobs;
+mod lockfile;
 mod paths;
 mod repo;
+mod scheme_index;
 mod utils;
+mod validate;
 
 use crate::cli::{build_cli, get_matches};
 use anyhow::{anyhow, Context, Result};
-use clap::Command;
+use clap::{ArgMatches, Command};
 use clap_complete::{generate, Generator, Shell};
-use config::{CONFIG_FILE_NAME, ORG_NAME};
-use constants::{CUSTOM_SCHEMES_DIR_NAME, REPO_NAME};
+use config::{Config, CONFIG_FILE_NAME, ORG_NAME};
+use constants::{CUSTOM_SCHEMES_DIR_NAME, PROFILE_ENV_VAR, REPO_NAME};
 use operations::generate_scheme;
-use std::path::PathBuf;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
 use std::string::String;
 use tinted_builder::{SchemeSystem, SchemeVariant};
 use utils::{ensure_directory_exists, replace_tilde_slash_with_home};
@@ -39,6 +53,14 @@ fn main() -> Result<()> {
     // Parse the command line arguments
     let matches = get_matches();
 
+    // `--profile`, or `TINTY_PROFILE` when it isn't given
+    let profile = matches
+        .get_one::<String>("profile")
+        .cloned()
+        .or_else(|| std::env::var(PROFILE_ENV_VAR).ok())
+        .filter(|profile| !profile.is_empty());
+    let profile = profile.as_deref();
+
     // Other configuration paths
     let config_path_result: Result<PathBuf> = matches.get_one::<String>("config").map_or_else(
         || {
@@ -70,6 +92,11 @@ fn main() -> Result<()> {
     };
     let data_repo_path = paths::repos_dir(&data_path);
 
+    // Reject an unknown profile whichever subcommand it is given to
+    if profile.is_some() {
+        Config::read_with_profile(&config_path, profile)?;
+    }
+
     // Ensure config dirs exist
     ensure_directory_exists(&data_path)
         .with_context(|| format!("Failed to create data directory at {}", data_path.display()))?;
@@ -99,20 +126,40 @@ fn main() -> Result<()> {
                 .get_one::<String>("property_name")
                 .map(String::as_str)
                 .unwrap_or_default();
+            let with_alias = sub_matches.get_flag("with-alias");
 
-            operations::current::current(&data_path, property_name)?;
+            operations::current::current(
+                &config_path,
+                &data_path,
+                profile,
+                property_name,
+                with_alias,
+            )?;
         }
         Some(("config", sub_matches)) => {
+            if let Some(("validate", _)) = sub_matches.subcommand() {
+                return operations::config::validate(&config_path, &data_path);
+            }
+
             let data_dir_path_flag = sub_matches.get_flag("data-dir-path");
             let config_path_flag = sub_matches.get_flag("config-path");
 
             operations::config::config(
                 &config_path,
                 &data_path,
+                profile,
                 config_path_flag,
                 data_dir_path_flag,
             )?;
         }
+        Some(("derive", sub_matches)) => {
+            let is_quiet = sub_matches
+                .get_one::<bool>("quiet")
+                .is_some_and(ToOwned::to_owned);
+            let slug = sub_matches.get_one::<String>("slug").map(String::as_str);
+
+            operations::derive::derive(&config_path, &data_path, slug, is_quiet)?;
+        }
         Some(("generate-completion", sub_matches)) => {
             if let Some(generator) = sub_matches.get_one::<Shell>("shell_name") {
                 let mut cmd = build_cli();
@@ -139,9 +186,22 @@ fn main() -> Result<()> {
             // the static site to a directory, and `--no-rc` opens the static
             // gallery locally (no server, no system changes).
             if dump_dir.is_some() || is_no_rc {
-                operations::gallery::gallery(&data_path, is_custom, dump_dir, should_open)?;
+                operations::gallery::gallery(
+                    &data_path,
+                    profile,
+                    is_custom,
+                    dump_dir,
+                    should_open,
+                )?;
             } else {
-                operations::gallery::serve(&config_path, &data_path, is_custom, port, should_open)?;
+                operations::gallery::serve(
+                    &config_path,
+                    &data_path,
+                    profile,
+                    is_custom,
+                    port,
+                    should_open,
+                )?;
             }
         }
         Some(("info", sub_matches)) => {
@@ -154,7 +214,9 @@ fn main() -> Result<()> {
             let scheme_name_option = sub_matches.get_one::<String>("scheme-name");
 
             operations::info::info(
+                &config_path,
                 &data_path,
+                profile,
                 scheme_name_option,
                 is_custom,
                 is_exhaustive_list,
@@ -165,7 +227,7 @@ fn main() -> Result<()> {
                 .get_one::<bool>("verbose")
                 .is_some_and(ToOwned::to_owned);
 
-            operations::init::init(&config_path, &data_path, is_verbose)?;
+            operations::init::init(&config_path, &data_path, profile, is_verbose)?;
         }
         Some(("list", sub_matches)) => {
             let is_custom = sub_matches
@@ -175,7 +237,35 @@ fn main() -> Result<()> {
                 .get_one::<bool>("json")
                 .is_some_and(ToOwned::to_owned);
 
-            operations::list::list(&data_path, is_custom, is_json)?;
+            operations::list::list(&config_path, &data_path, is_custom, is_json)?;
+        }
+        Some(("search", sub_matches)) => {
+            let is_custom = sub_matches.get_flag("custom-schemes");
+            let is_json = sub_matches.get_flag("json");
+            let query = operations::search::SearchQuery {
+                terms: sub_matches
+                    .get_many::<String>("query")
+                    .map(|terms| terms.cloned().collect())
+                    .unwrap_or_default(),
+                variant: sub_matches.get_one::<String>("variant").cloned(),
+                system: sub_matches.get_one::<String>("system").cloned(),
+                author: sub_matches.get_one::<String>("author").cloned(),
+                bg_lightness: sub_matches
+                    .get_one::<String>("bg-lightness")
+                    .map(|range| operations::search::parse_lightness_range(range))
+                    .transpose()?,
+                near_colors: sub_matches
+                    .get_many::<String>("near-color")
+                    .unwrap_or_default()
+                    .map(|value| operations::search::parse_near_color(value))
+                    .collect::<Result<Vec<_>>>()?,
+                max_delta: sub_matches
+                    .get_one::<f32>("max-delta")
+                    .copied()
+                    .unwrap_or(operations::search::DEFAULT_MAX_DELTA),
+            };
+
+            operations::search::search(&config_path, &data_path, is_custom, is_json, &query)?;
         }
         Some(("apply", sub_matches)) => {
             if let Some(theme) = sub_matches.get_one::<String>("scheme-name") {
@@ -184,8 +274,15 @@ fn main() -> Result<()> {
                     .is_some_and(ToOwned::to_owned);
 
                 let scheme_name = theme.as_str();
-                operations::apply::apply(&config_path, &data_path, scheme_name, is_quiet, None)
-                    .with_context(|| format!("Failed to apply theme \"{scheme_name}\""))?;
+                operations::apply::apply(
+                    &config_path,
+                    &data_path,
+                    profile,
+                    scheme_name,
+                    is_quiet,
+                    None,
+                )
+                .with_context(|| format!("Failed to apply theme \"{scheme_name}\""))?;
             }
         }
         Some(("cycle", sub_matches)) => {
@@ -194,7 +291,7 @@ fn main() -> Result<()> {
                 .is_some_and(ToOwned::to_owned);
             let ring_name = sub_matches.get_one::<String>("ring").map(String::as_str);
 
-            operations::cycle::cycle(&config_path, &data_path, is_quiet, ring_name, None)
+            operations::cycle::cycle(&config_path, &data_path, profile, is_quiet, ring_name, None)
                 .context("Failed to cycle to your next preferred theme")?;
         }
         Some(("install", sub_matches)) => {
@@ -202,21 +299,101 @@ fn main() -> Result<()> {
                 .get_one::<bool>("quiet")
                 .is_some_and(ToOwned::to_owned);
 
-            operations::install::install(&config_path, &data_path, is_quiet)?;
+            let max_jobs = sub_matches.get_one::<NonZeroUsize>("jobs").copied();
+
+            let is_offline = sub_matches.get_flag("offline");
+
+            operations::install::install(&config_path, &data_path, is_quiet, max_jobs, is_offline)?;
         }
         Some(("update", sub_matches)) => {
             let is_quiet = sub_matches
                 .get_one::<bool>("quiet")
                 .is_some_and(ToOwned::to_owned);
 
-            operations::update::update(&config_path, &data_path, is_quiet)?;
+            let is_json = sub_matches.get_flag("json");
+
+            if sub_matches.get_flag("check") {
+                operations::outdated::outdated(&config_path, &data_path, is_json)?;
+            } else {
+                let write_lock = sub_matches.get_flag("write-lock");
+                let max_jobs = sub_matches.get_one::<NonZeroUsize>("jobs").copied();
+                let is_offline = sub_matches.get_flag("offline");
+
+                let changelogs = operations::update::update(
+                    &config_path,
+                    &data_path,
+                    is_quiet,
+                    is_json,
+                    write_lock,
+                    max_jobs,
+                    is_offline,
+                )?;
+                operations::update::reapply_if_changed(
+                    &config_path,
+                    &data_path,
+                    profile,
+                    &changelogs,
+                    reapply_flag(sub_matches),
+                    "update",
+                    is_quiet || is_json,
+                )?;
+            }
+        }
+        Some(("prune", sub_matches)) => {
+            let is_dry_run = sub_matches.get_flag("dry-run");
+            let is_yes = sub_matches.get_flag("yes");
+
+            operations::prune::prune(&config_path, &data_path, is_dry_run, is_yes)?;
+        }
+        Some(("uninstall", sub_matches)) => {
+            let item_name = sub_matches
+                .get_one::<String>("item_name")
+                .context("item_name is required")?;
+            let is_dry_run = sub_matches.get_flag("dry-run");
+            let is_yes = sub_matches.get_flag("yes");
+
+            operations::prune::uninstall(&config_path, &data_path, item_name, is_dry_run, is_yes)?;
+        }
+        Some(("status", sub_matches)) => {
+            let is_json = sub_matches.get_flag("json");
+
+            operations::status::status(&config_path, &data_path, profile, is_json)?;
+        }
+        Some(("outdated", sub_matches)) => {
+            let is_json = sub_matches.get_flag("json");
+
+            operations::outdated::outdated(&config_path, &data_path, is_json)?;
         }
         Some(("sync", sub_matches)) => {
             let is_quiet = sub_matches
                 .get_one::<bool>("quiet")
                 .is_some_and(ToOwned::to_owned);
 
-            operations::sync::sync(&config_path, &data_path, is_quiet)?;
+            let is_locked = sub_matches.get_flag("locked");
+            let max_jobs = sub_matches.get_one::<NonZeroUsize>("jobs").copied();
+
+            let is_offline = sub_matches.get_flag("offline");
+
+            operations::sync::sync(
+                &config_path,
+                &data_path,
+                profile,
+                is_quiet,
+                is_locked,
+                max_jobs,
+                is_offline,
+                reapply_flag(sub_matches),
+            )?;
+        }
+        Some(("bundle", sub_matches)) => {
+            if let Some(("export", export_matches)) = sub_matches.subcommand() {
+                let output = export_matches
+                    .get_one::<String>("output")
+                    .context("output is required")?;
+                let is_quiet = export_matches.get_flag("quiet");
+
+                operations::bundle::export(&config_path, &data_path, Path::new(output), is_quiet)?;
+            }
         }
         Some(("generate-scheme", sub_matches)) => {
             let slug_default = "tinty-generated".to_string();
@@ -234,14 +411,25 @@ fn main() -> Result<()> {
             let author = sub_matches
                 .get_one::<String>("author")
                 .unwrap_or(&author_default);
-            let image_path = sub_matches.get_one::<String>("image_path").map_or_else(
-                || Err(anyhow!("No image file specified")),
-                |content| {
-                    PathBuf::from(content)
-                        .canonicalize()
-                        .with_context(|| "Invalid image file supplied")
-                },
-            )?;
+            let source = if let Some(color) = sub_matches.get_one::<String>("from-color") {
+                generate_scheme::SchemeSource::Color(color::Rgb::from_hex(color)?)
+            } else if sub_matches.get_flag("random") {
+                let seed = sub_matches
+                    .get_one::<u64>("seed")
+                    .copied()
+                    .unwrap_or_else(rand::random);
+                generate_scheme::SchemeSource::Random { seed }
+            } else {
+                let image_path = sub_matches.get_one::<String>("image_path").map_or_else(
+                    || Err(anyhow!("No image file specified")),
+                    |content| {
+                        PathBuf::from(content)
+                            .canonicalize()
+                            .with_context(|| "Invalid image file supplied")
+                    },
+                )?;
+                generate_scheme::SchemeSource::Image(image_path)
+            };
             let system = match sub_matches.get_one::<String>("system").map(String::as_str) {
                 Some("base24") => SchemeSystem::Base24,
                 _ => SchemeSystem::Base16,
@@ -284,7 +472,7 @@ fn main() -> Result<()> {
             };
 
             generate_scheme::generate_scheme(
-                image_path,
+                source,
                 outfile_path_option,
                 author.clone(),
                 description,
@@ -303,6 +491,18 @@ fn main() -> Result<()> {
     Ok(())
 }
 
+/// `Some(true)` for `--reapply`, `Some(false)` for `--no-reapply`, or `None` to
+/// use the config's `reapply` default.
+fn reapply_flag(matches: &ArgMatches) -> Option<bool> {
+    if matches.get_flag("reapply") {
+        Some(true)
+    } else if matches.get_flag("no-reapply") {
+        Some(false)
+    } else {
+        None
+    }
+}
+
 fn print_completions<G: Generator>(gen: G, cmd: &mut Command) {
     generate(gen, cmd, cmd.get_name().to_string(), &mut std::io::stdout());
 }
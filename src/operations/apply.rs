@@ -1,9 +1,11 @@
-use crate::config::Config;
+use crate::config::{Config, ConfigItem};
 use crate::constants::{
     ARTIFACTS_DIR, CURRENT_SCHEME_FILE_NAME, CUSTOM_SCHEMES_DIR_NAME, DEFAULT_SCHEME_SYSTEM,
-    LOCK_FILE, REPO_NAME, REPO_URL,
+    LOCK_FILE, PROFILE_ENV_VAR, REPO_NAME, REPO_URL, SCHEMES_REPO_NAME, SCHEME_SOURCE_SEPARATOR,
+    TEMPLATES_DIR,
 };
 use crate::paths;
+use crate::scheme_index::SchemeIndex;
 use crate::utils::{
     create_theme_filename_without_extension, get_all_scheme_file_paths,
     get_shell_command_from_string, write_to_file,
@@ -22,72 +24,58 @@ use tinted_builder_rust::operation_build::utils::SchemeFile;
 
 use super::list::SchemeEntry;
 
-fn str_matches_scheme_system(value: &str) -> bool {
-    match value {
-        _ if value == SchemeSystem::Base16.as_str() => true,
-        _ if value == SchemeSystem::Base24.as_str() => true,
-        _ if value == SchemeSystem::Tinted8.as_str() => true,
-        _ => false,
-    }
-}
-
 /// Apply theme
 ///
 /// For each of the provided config items, copy the theme to the `data_dir` based on the provided
-/// `scheme_name`
+/// `scheme_name`, which may be a scheme ID, a bare slug, a scheme name or an alias (see
+/// `SchemeIndex::resolve`). With a `profile`, its overrides are used and the
+/// themes go to the profile's own artifacts directory.
 #[allow(clippy::too_many_lines)]
 pub fn apply(
     config_path: &Path,
     data_path: &Path,
+    profile: Option<&str>,
     full_scheme_name: &str,
     is_quiet: bool,
     active_operation: Option<&str>,
 ) -> Result<()> {
-    let scheme_name_arr: Vec<String> = full_scheme_name
-        .split('-')
-        .map(ToString::to_string)
-        .collect();
-    let scheme_system_option = scheme_name_arr.first().map(String::to_string);
-
-    // Check provided scheme exists
-    if scheme_name_arr.len() < 2 {
-        return Err(anyhow!(
-            "Invalid scheme name. Make sure the scheme system is prefixed <SCHEME_SYSTEM>-<SCHEME_NAME>, eg: `{DEFAULT_SCHEME_SYSTEM}-ayu-dark`",
-        ));
-    }
-
-    // Check provided scheme is valid
-    if !str_matches_scheme_system(scheme_system_option.clone().unwrap_or_default().as_str()) {
-        return Err(anyhow!(
-            "Invalid scheme name. Make sure your scheme is prefixed with a supprted system (\"{}\", \"{}\" or \"{}\"), eg: {}-{}",
-            SchemeSystem::Base16.as_str(),
-            SchemeSystem::Base24.as_str(),
-            SchemeSystem::Tinted8.as_str(),
-            DEFAULT_SCHEME_SYSTEM,
-            full_scheme_name
-        ));
-    }
+    let schemes_path = &paths::schemes_repo_path(data_path);
+    let custom_schemes_path = &data_path.join(CUSTOM_SCHEMES_DIR_NAME);
+    let builtin_scheme_files = get_all_scheme_file_paths(schemes_path, None)?;
+    let custom_scheme_files = get_all_scheme_file_paths(custom_schemes_path, None).ok();
+    let config = Config::read_with_profile(config_path, profile)?;
+    let scheme_index = SchemeIndex::for_config(&config, data_path).with_aliases(&config.aliases);
+    let full_scheme_name = &scheme_index.resolve(full_scheme_name)?;
+    // A scheme from a `[[scheme-sources]]` entry, as opposed to an official or
+    // custom one. Items have no themes for these, so they are built on the fly.
+    let source_scheme = scheme_index
+        .source_of(full_scheme_name)
+        .filter(|source| *source != SCHEMES_REPO_NAME)
+        .and_then(|_| scheme_index.get(full_scheme_name));
+    let scheme_id = full_scheme_name
+        .split_once(SCHEME_SOURCE_SEPARATOR)
+        .map_or(full_scheme_name.as_str(), |(_, id)| id);
+    let scheme_name_arr: Vec<String> = scheme_id.split('-').map(ToString::to_string).collect();
+    let scheme_system = SchemeSystem::from_str(
+        scheme_name_arr
+            .first()
+            .map_or(DEFAULT_SCHEME_SYSTEM, String::as_str),
+    )?;
 
-    // Create a temporary data directory
+    // Create a temporary data directory next to the artifacts directory, so it
+    // can be renamed into place.
+    let target_path = paths::artifacts_dir(data_path, profile);
+    let artifacts_parent = target_path.parent().unwrap_or(data_path);
+    fs::create_dir_all(artifacts_parent)?;
     let staging_data_dir = tempfile::Builder::new()
         .prefix(format!("{ARTIFACTS_DIR}-").as_str())
-        .tempdir_in(data_path)?;
+        .tempdir_in(artifacts_parent)?;
     let staging_data_path = staging_data_dir.path();
 
-    // Go through custom schemes
-    let scheme_system =
-        SchemeSystem::from_str(&scheme_system_option.unwrap_or_else(|| "base16".to_string()))?;
-    let schemes_path = &paths::schemes_repo_path(data_path);
-    let custom_schemes_path = &data_path.join(CUSTOM_SCHEMES_DIR_NAME);
-    let builtin_scheme_files = get_all_scheme_file_paths(schemes_path, None)?;
-    let custom_scheme_files = get_all_scheme_file_paths(custom_schemes_path, None).ok();
-    let config = Config::read(config_path)?;
-    let builtin_scheme = builtin_scheme_files.get(full_scheme_name);
-    let custom_scheme = custom_scheme_files
-        .as_ref()
-        .and_then(|m| m.get(full_scheme_name));
+    let builtin_scheme = builtin_scheme_files.get(scheme_id);
+    let custom_scheme = custom_scheme_files.as_ref().and_then(|m| m.get(scheme_id));
 
-    let Some(scheme_file) = builtin_scheme.xor(custom_scheme) else {
+    let Some(scheme_file) = source_scheme.or_else(|| builtin_scheme.xor(custom_scheme)) else {
         // We expect the scheme to be a built-in scheme or a custom schemes, not both.
         if builtin_scheme.is_none() {
             return Err(anyhow!("Scheme does not exist: {full_scheme_name}"));
@@ -107,7 +95,7 @@ pub fn apply(
         ));
     };
 
-    if custom_scheme.is_some() {
+    if source_scheme.is_none() && custom_scheme.is_some() {
         build_and_get_custom_scheme_file(custom_schemes_path, data_path, &config)?;
     }
 
@@ -116,18 +104,15 @@ pub fn apply(
         full_scheme_name,
     )?;
 
-    let system_items = config
-        .items
-        .map(|f| {
-            f.into_iter()
-                .filter(|f| {
-                    f.supported_systems
-                        .clone()
-                        .is_some_and(|s| s.contains(&scheme_system))
-                })
-                .collect::<Vec<_>>()
+    let system_items: Vec<&ConfigItem> = config
+        .apply_items()
+        .into_iter()
+        .filter(|f| {
+            f.supported_systems
+                .as_ref()
+                .is_some_and(|s| s.contains(&scheme_system))
         })
-        .unwrap_or_default();
+        .collect();
 
     let mut hook_commands: Vec<Hook> = Vec::new();
 
@@ -144,37 +129,54 @@ pub fn apply(
             )));
         }
 
-        // Find the corresponding theme file for the provided item
-        let theme_dir = fs::read_dir(&themes_path)
-            .map_err(Error::new)
-            .with_context(|| format!("Themes are missing from {}, try running `{REPO_NAME} install` or `{REPO_NAME} update` and try again.", item.name))?;
-        let theme_option = &theme_dir.filter_map(Result::ok).find(|entry| {
-            let path = entry.path();
-            item.theme_file_extension.as_ref().map_or_else(
-                || {
-                    let filename = path.file_stem().and_then(|name| name.to_str());
-                    full_scheme_name == filename.unwrap_or_default()
-                },
-                |extension| {
-                    let filename = path.file_name().and_then(|name| name.to_str());
-                    format!("{full_scheme_name}{extension}") == filename.unwrap_or_default()
-                },
-            )
-        });
+        // Find the corresponding theme file for the provided item. A source
+        // scheme's theme is only ever built from the item's templates: its
+        // `themes-dir` may hold an official theme with the same ID, which the
+        // source scheme shadows.
+        let build_dir = match source_scheme {
+            Some(scheme_file) => {
+                let Some(build_dir) =
+                    build_source_scheme_theme(&repo_path, scheme_file, data_path)?
+                else {
+                    if !is_quiet {
+                        println!(
+                            "{} has no {TEMPLATES_DIR}/ to build a theme for {full_scheme_name} from, skipping",
+                            item.name,
+                        );
+                    }
+                    continue;
+                };
+                Some(build_dir)
+            }
+            None => None,
+        };
+        let theme_option = match &build_dir {
+            Some(build_dir) => {
+                let built_themes_path = build_dir.path().join(&item.themes_dir);
+                find_theme_file(&built_themes_path, item, scheme_id).with_context(|| {
+                    format!(
+                        "Unable to find the theme {} built for {full_scheme_name} in {}",
+                        item.name,
+                        built_themes_path.display(),
+                    )
+                })?
+            }
+            None => find_theme_file(&themes_path, item, scheme_id)
+                .with_context(|| format!("Themes are missing from {}, try running `{REPO_NAME} install` or `{REPO_NAME} update` and try again.", item.name))?,
+        };
 
         // Copy that theme to the data_path or log a message that it isn't found
         match theme_option {
-            Some(theme_file) => {
-                let theme_file_path = &theme_file.path();
+            Some(theme_file_path) => {
                 let extension = theme_file_path.extension().map_or_else(String::new, |ext| {
                     format!(".{}", ext.to_str().unwrap_or_default())
                 });
                 let filename = format!(
                     "{}{extension}",
-                    create_theme_filename_without_extension(&item),
+                    create_theme_filename_without_extension(item),
                 );
                 let data_theme_path = staging_data_path.join(&filename);
-                let theme_content = fs::read_to_string(theme_file.path())?;
+                let theme_content = fs::read_to_string(&theme_file_path)?;
 
                 write_to_file(&data_theme_path, theme_content.as_str())?;
 
@@ -191,7 +193,7 @@ pub fn apply(
                 }
 
                 // Run config.items.write_to_file
-                if let Some(write_to_file_vec) = item.write_to_file {
+                if let Some(write_to_file_vec) = &item.write_to_file {
                     match write_to_file_vec.as_slice() {
                         [target_filepath, start_marker, end_marker] => {
                             let expanded_filepath = expand_tilde(target_filepath);
@@ -255,7 +257,6 @@ pub fn apply(
         lock_path.display()
     ))?;
 
-    let target_path = data_path.join(ARTIFACTS_DIR);
     if target_path.exists() {
         // Replace the existing artifacts directory with the staging one.
         fs::remove_dir_all(&target_path)?;
@@ -264,8 +265,7 @@ pub fn apply(
     std::mem::forget(staging_data_dir);
 
     for hook in hook_commands {
-        let mut child =
-            hook.run_command(&target_path, config_path, full_scheme_name, scheme_file)?;
+        let mut child = hook.run_command(&target_path, &config, full_scheme_name, scheme_file)?;
         child.wait().with_context(|| {
             format!(
                 "Failed to wait for {} hook: {}",
@@ -274,12 +274,16 @@ pub fn apply(
         })?;
     }
 
-    create_symlinks_for_backwards_compat(&target_path, data_path)?;
+    // The links in the data directory that shells source keep pointing at the
+    // default artifacts; a profile's apply leaves them alone.
+    if profile.is_none() {
+        create_symlinks_for_backwards_compat(&target_path, data_path)?;
+    }
 
     // Run global tinty/config.toml hooks
-    if let Some(hooks_vec) = config.hooks {
-        for hook in &hooks_vec {
-            let hook_command_vec = get_shell_command_from_string(config_path, hook.as_str())?;
+    if let Some(hooks_vec) = &config.hooks {
+        for hook in hooks_vec {
+            let hook_command_vec = get_shell_command_from_string(&config, hook.as_str())?;
             let Some(command) = hook_command_vec.first() else {
                 return Err(anyhow!("Unable to extract cli command"));
             };
@@ -288,6 +292,7 @@ pub fn apply(
             };
             Command::new(command)
                 .args(args)
+                .envs(profile_env(&config))
                 .envs(SchemeEntry::from_scheme(&scheme_file.get_scheme()?).to_envs())
                 .status()
                 .with_context(|| format!("Failed to execute global hook: {hook}"))?;
@@ -297,6 +302,33 @@ pub fn apply(
     Ok(())
 }
 
+/// Finds the theme file for `full_scheme_name` in an item's themes directory:
+/// `<full_scheme_name>` plus the item's `theme-file-extension`, or any
+/// extension when it has none.
+pub fn find_theme_file(
+    themes_path: &Path,
+    item: &ConfigItem,
+    full_scheme_name: &str,
+) -> Result<Option<PathBuf>> {
+    let theme_dir = fs::read_dir(themes_path).map_err(Error::new)?;
+
+    Ok(theme_dir
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .find(|path| {
+            item.theme_file_extension.as_ref().map_or_else(
+                || {
+                    let filename = path.file_stem().and_then(|name| name.to_str());
+                    full_scheme_name == filename.unwrap_or_default()
+                },
+                |extension| {
+                    let filename = path.file_name().and_then(|name| name.to_str());
+                    format!("{full_scheme_name}{extension}") == filename.unwrap_or_default()
+                },
+            )
+        }))
+}
+
 fn build_and_get_custom_scheme_file(
     custom_schemes_path: &Path,
     data_path: &Path,
@@ -317,6 +349,66 @@ fn build_and_get_custom_scheme_file(
     Ok(())
 }
 
+/// Builds `item_repo_path`'s theme for a scheme from a `[[scheme-sources]]`
+/// entry into a temporary copy of its templates, so the item's repository is
+/// left untouched. Returns the directory the theme was built under, or `None`
+/// when the item has no templates to build from.
+fn build_source_scheme_theme(
+    item_repo_path: &Path,
+    scheme_file: &SchemeFile,
+    data_path: &Path,
+) -> Result<Option<tempfile::TempDir>> {
+    let templates_path = item_repo_path.join(TEMPLATES_DIR);
+    if !templates_path.is_dir() {
+        return Ok(None);
+    }
+
+    let build_dir = tempfile::Builder::new()
+        .prefix("build-")
+        .tempdir_in(data_path)?;
+    copy_dir_all(&templates_path, &build_dir.path().join(TEMPLATES_DIR))?;
+
+    // Only the one scheme, in its `<system>/` directory, is built.
+    let schemes_dir = tempfile::Builder::new()
+        .prefix("schemes-")
+        .tempdir_in(data_path)?;
+    let scheme_path = scheme_file.get_path();
+    let (Some(system_dir), Some(file_name)) = (
+        scheme_path.parent().and_then(Path::file_name),
+        scheme_path.file_name(),
+    ) else {
+        return Err(anyhow!(
+            "Unexpected scheme file path: {}",
+            scheme_path.display()
+        ));
+    };
+    fs::create_dir_all(schemes_dir.path().join(system_dir))?;
+    fs::copy(
+        &scheme_path,
+        schemes_dir.path().join(system_dir).join(file_name),
+    )?;
+
+    // Quiet for the same reason as in `build_and_get_custom_scheme_file`.
+    build(build_dir.path(), schemes_dir.path(), &[], true)?;
+
+    Ok(Some(build_dir))
+}
+
+fn copy_dir_all(source: &Path, target: &Path) -> Result<()> {
+    fs::create_dir_all(target)?;
+    for entry in fs::read_dir(source)? {
+        let entry = entry?;
+        let target_path = target.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir_all(&entry.path(), &target_path)?;
+        } else {
+            fs::copy(entry.path(), &target_path)?;
+        }
+    }
+
+    Ok(())
+}
+
 fn create_symlinks_for_backwards_compat(source_path: &PathBuf, target_path: &Path) -> Result<()> {
     for entry in fs::read_dir(source_path)? {
         let entry = entry?;
@@ -348,7 +440,7 @@ impl Hook {
     fn run_command(
         &self,
         artifacts_path: &Path,
-        config_path: &Path,
+        config: &Config,
         full_scheme_name: &str,
         scheme_file: &SchemeFile,
     ) -> Result<Child, Error> {
@@ -361,7 +453,7 @@ impl Hook {
             .replace("%o", self.operation.as_str())
             .replace("%f", theme_file_path.as_str())
             .replace("%n", full_scheme_name);
-        let command_vec = get_shell_command_from_string(config_path, hook_script.as_str())?;
+        let command_vec = get_shell_command_from_string(config, hook_script.as_str())?;
         let Some(command) = command_vec.first() else {
             return Err(anyhow!("Unable to extract cli command"));
         };
@@ -372,6 +464,7 @@ impl Hook {
             .args(args)
             .env("TINTY_THEME_FILE_PATH", theme_file_path)
             .env("TINTY_THEME_OPERATION", self.operation.as_str())
+            .envs(profile_env(config))
             .envs(SchemeEntry::from_scheme(&scheme_file.get_scheme()?).to_envs())
             .spawn()
             .with_context(|| {
@@ -383,6 +476,14 @@ impl Hook {
     }
 }
 
+/// `TINTY_PROFILE` for hooks, when a profile is selected.
+fn profile_env(config: &Config) -> Option<(&str, &str)> {
+    config
+        .active_profile
+        .as_deref()
+        .map(|profile| (PROFILE_ENV_VAR, profile))
+}
+
 fn symlink_any(src: &Path, dst: &Path) -> Result<(), Error> {
     std::os::unix::fs::symlink(src, dst)?;
     Ok(())
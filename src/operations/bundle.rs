@@ -0,0 +1,69 @@
+use crate::config::Config;
+use crate::constants::REPO_NAME;
+use crate::paths;
+use anyhow::{anyhow, Context, Result};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs::{self, File};
+use std::path::Path;
+
+/// Packs the installed schemes repository and every installed item into one
+/// `.tar.gz` at `output`, for moving an installation to a machine without
+/// network access. Each repository is a top-level directory named after it,
+/// `.git` included, so once unpacked it can be used as a local directory
+/// source or as a `file://` Git source.
+pub fn export(config_path: &Path, data_path: &Path, output: &Path, is_quiet: bool) -> Result<()> {
+    let is_tar_gz = output
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("tgz"))
+        || output
+            .to_string_lossy()
+            .to_ascii_lowercase()
+            .ends_with(".tar.gz");
+    if !is_tar_gz {
+        return Err(anyhow!("{} must end in .tar.gz or .tgz", output.display()));
+    }
+
+    let config = Config::read(config_path)?;
+    let repos = config.repo_sources();
+    let missing: Vec<&str> = repos
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .filter(|name| !paths::repos_dir(data_path).join(name).is_dir())
+        .collect();
+    if !missing.is_empty() {
+        return Err(anyhow!(
+            "{} not installed. Run `{REPO_NAME} install` and try again",
+            missing.join(", ")
+        ));
+    }
+
+    let file =
+        File::create(output).with_context(|| format!("Failed to create {}", output.display()))?;
+    let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+    // Keep symlinks inside repositories as symlinks; a local-directory source
+    // is itself a symlink, which is resolved below.
+    builder.follow_symlinks(false);
+
+    for (name, _) in &repos {
+        let repo_path = fs::canonicalize(paths::repos_dir(data_path).join(name))?;
+        builder
+            .append_dir_all(name, &repo_path)
+            .with_context(|| format!("Failed to add {name} to {}", output.display()))?;
+
+        if !is_quiet {
+            println!("{name} added");
+        }
+    }
+
+    builder
+        .into_inner()
+        .and_then(GzEncoder::finish)
+        .with_context(|| format!("Failed to write {}", output.display()))?;
+
+    if !is_quiet {
+        println!("Bundle written to {}", output.display());
+    }
+
+    Ok(())
+}
@@ -2,14 +2,16 @@ use anyhow::{anyhow, Result};
 use std::path::Path;
 
 use crate::config::Config;
+use crate::validate::{self, Severity};
 
 pub fn config(
     config_path: &Path,
     data_path: &Path,
+    profile: Option<&str>,
     config_path_flag: bool,
     data_dir_path_flag: bool,
 ) -> Result<()> {
-    let config = Config::read(config_path)?;
+    let config = Config::read_with_profile(config_path, profile)?;
     let path_tuple: (bool, bool) = (config_path_flag, data_dir_path_flag);
 
     match path_tuple {
@@ -21,6 +23,9 @@ pub fn config(
         }
         (false, false) => {
             println!("{config}");
+            if let Some(origins) = config.describe_origins() {
+                println!("{origins}");
+            }
         }
         (true, true) => {
             // This case should already be handled by clap
@@ -32,3 +37,24 @@ pub fn config(
 
     Ok(())
 }
+
+/// Prints every problem in the config, failing if any of them is an error.
+pub fn validate(config_path: &Path, data_path: &Path) -> Result<()> {
+    let diagnostics = validate::validate(config_path, data_path);
+    for diagnostic in &diagnostics {
+        eprintln!("{diagnostic}");
+    }
+
+    let errors = diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.severity == Severity::Error)
+        .count();
+    match errors {
+        0 => {
+            println!("{} is valid", config_path.display());
+            Ok(())
+        }
+        1 => Err(anyhow!("1 problem found")),
+        _ => Err(anyhow!("{errors} problems found")),
+    }
+}
@@ -1,20 +1,26 @@
-use crate::constants::{
-    ARTIFACTS_DIR, CURRENT_SCHEME_FILE_NAME, CUSTOM_SCHEMES_DIR_NAME, REPO_NAME,
-};
+use crate::config::Config;
+use crate::constants::{CURRENT_SCHEME_FILE_NAME, REPO_NAME};
 use crate::paths;
-use crate::utils::get_all_scheme_file_paths;
+use crate::scheme_index::SchemeIndex;
 use anyhow::{anyhow, Result};
 use std::fs;
 use std::path::Path;
 
-pub fn get_current_scheme_slug(data_path: &Path) -> String {
-    fs::read_to_string(data_path.join(ARTIFACTS_DIR).join(CURRENT_SCHEME_FILE_NAME))
+pub fn get_current_scheme_slug(data_path: &Path, profile: Option<&str>) -> String {
+    fs::read_to_string(paths::artifacts_dir(data_path, profile).join(CURRENT_SCHEME_FILE_NAME))
         .unwrap_or_default()
 }
 
-/// Prints out the name of the last scheme applied
-pub fn current(data_path: &Path, property_name: &str) -> Result<()> {
-    let current_scheme_slug = get_current_scheme_slug(data_path);
+/// Prints out the name of the last scheme applied. With `with_alias`, the
+/// `[aliases]` that point at it are printed after it, e.g. `base16-github (work)`
+pub fn current(
+    config_path: &Path,
+    data_path: &Path,
+    profile: Option<&str>,
+    property_name: &str,
+    with_alias: bool,
+) -> Result<()> {
+    let current_scheme_slug = get_current_scheme_slug(data_path, profile);
     let schemes_path = paths::schemes_repo_path(data_path);
 
     if current_scheme_slug.is_empty() {
@@ -24,7 +30,21 @@ pub fn current(data_path: &Path, property_name: &str) -> Result<()> {
     }
 
     if property_name.is_empty() {
-        println!("{current_scheme_slug}");
+        let aliases = if with_alias {
+            let config = Config::read_with_profile(config_path, profile)?;
+            SchemeIndex::for_config(&config, data_path)
+                .with_aliases(&config.aliases)
+                .aliases_for(&current_scheme_slug)
+                .join(", ")
+        } else {
+            String::new()
+        };
+
+        if aliases.is_empty() {
+            println!("{current_scheme_slug}");
+        } else {
+            println!("{current_scheme_slug} ({aliases})");
+        }
 
         return Ok(());
     }
@@ -35,14 +55,8 @@ pub fn current(data_path: &Path, property_name: &str) -> Result<()> {
         ));
     }
 
-    let custom_schemes_path = data_path.join(CUSTOM_SCHEMES_DIR_NAME);
-    let mut scheme_files = get_all_scheme_file_paths(&schemes_path, None)?;
-    if custom_schemes_path.is_dir() {
-        let custom_scheme_files = get_all_scheme_file_paths(&custom_schemes_path, None)?;
-        scheme_files.extend(custom_scheme_files);
-    }
-
-    let current_scheme_container = scheme_files
+    let config = Config::read_with_profile(config_path, profile)?;
+    let current_scheme_container = SchemeIndex::for_config(&config, data_path)
         .get(&current_scheme_slug)
         .and_then(|scheme_file| scheme_file.get_scheme().ok());
 
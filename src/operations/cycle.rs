@@ -1,23 +1,38 @@
 use crate::config::Config;
 use crate::operations::apply::apply;
 use crate::operations::current::get_current_scheme_slug;
+use crate::scheme_index::SchemeIndex;
 use crate::utils::{cycle_scheme_list, next_scheme_in_cycle};
-use anyhow::Result;
+use anyhow::{Context, Result};
 use std::path::Path;
 
 /// Cycle to next scheme in a configured ring.
 pub fn cycle(
     config_path: &Path,
     data_path: &Path,
+    profile: Option<&str>,
     is_quiet: bool,
     ring_name: Option<&str>,
     active_operation: Option<&str>,
 ) -> Result<()> {
-    let config = Config::read(config_path)?;
+    let config = Config::read_with_profile(config_path, profile)?;
 
-    let current_scheme_slug = get_current_scheme_slug(data_path);
+    let current_scheme_slug = get_current_scheme_slug(data_path, profile);
 
-    let schemes = cycle_scheme_list(&config, ring_name)?;
+    // Ring entries may use any spelling `apply` accepts, so resolve them to
+    // scheme IDs before comparing against the current scheme ID.
+    let scheme_index = SchemeIndex::for_config(&config, data_path).with_aliases(&config.aliases);
+    let schemes = cycle_scheme_list(&config, ring_name)?
+        .iter()
+        .map(|scheme| {
+            scheme_index.resolve(scheme).with_context(|| {
+                let ring = ring_name
+                    .or(config.default_cycle_ring.as_deref())
+                    .unwrap_or_default();
+                format!("Invalid scheme \"{scheme}\" in ring \"{ring}\"")
+            })
+        })
+        .collect::<Result<Vec<String>>>()?;
     let next_theme = next_scheme_in_cycle(&current_scheme_slug, &schemes);
 
     if !is_quiet {
@@ -27,6 +42,7 @@ pub fn cycle(
     apply(
         config_path,
         data_path,
+        profile,
         &next_theme,
         is_quiet,
         active_operation,
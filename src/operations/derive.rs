@@ -0,0 +1,345 @@
+use crate::color::{ColorSpace, Rgb};
+use crate::config::{Config, ConfigDerivedScheme, DeriveOperation, DeriveOperationKind};
+use crate::constants::{CUSTOM_SCHEMES_DIR_NAME, REPO_NAME};
+use crate::paths;
+use crate::utils::{ensure_directory_exists, get_all_scheme_file_paths, write_to_file};
+use anyhow::{anyhow, Context, Result};
+use serde_yaml::{Mapping, Value};
+use std::fs;
+use std::path::Path;
+use tinted_builder::SchemeSystem;
+
+/// First line of every file written by this module. Used to recognise a
+/// derived scheme on disk so a hand-written custom scheme with the same slug
+/// is never overwritten.
+const DERIVED_SCHEME_HEADER: &str =
+    "# Generated by tinty from a [[derived-schemes]] entry in config.toml.";
+
+/// Materializes `[[derived-schemes]]` from the config
+///
+/// Writes every configured derived scheme (or only `slug`, when provided) to the custom schemes
+/// directory, where `apply`, `list --custom-schemes` and the rest of tinty pick it up like any
+/// other custom scheme.
+pub fn derive(
+    config_path: &Path,
+    data_path: &Path,
+    slug: Option<&str>,
+    is_quiet: bool,
+) -> Result<()> {
+    let config = Config::read(config_path)?;
+    let derived_schemes = config.derived_schemes.unwrap_or_default();
+
+    if derived_schemes.is_empty() {
+        return Err(anyhow!(
+            "No `[[derived-schemes]]` found in config. Add a derived scheme to your config and run `{REPO_NAME} derive` again."
+        ));
+    }
+
+    match slug {
+        Some(slug) => {
+            let derived = derived_schemes
+                .iter()
+                .find(|derived| derived.slug == slug)
+                .ok_or_else(|| {
+                    let available = derived_schemes
+                        .iter()
+                        .map(|derived| derived.slug.as_str())
+                        .collect::<Vec<&str>>()
+                        .join(", ");
+                    anyhow!("No derived scheme with slug \"{slug}\" exists. Available derived schemes: {available}")
+                })?;
+
+            derive_schemes(std::slice::from_ref(derived), data_path, is_quiet)
+        }
+        None => derive_schemes(&derived_schemes, data_path, is_quiet),
+    }
+}
+
+/// Writes each derived scheme to `custom-schemes/<system>/<slug>.yaml`, in order, so a derived
+/// scheme may use an earlier one as its base. Called by `install` and `update` so derived schemes
+/// stay in sync with their (possibly updated) base schemes.
+pub fn derive_schemes(
+    derived_schemes: &[ConfigDerivedScheme],
+    data_path: &Path,
+    is_quiet: bool,
+) -> Result<()> {
+    for derived in derived_schemes {
+        let scheme_id = write_derived_scheme(derived, data_path)
+            .with_context(|| format!("Failed to derive scheme \"{}\"", derived.slug))?;
+
+        if !is_quiet {
+            println!("{scheme_id} derived from {}", derived.base);
+        }
+    }
+
+    Ok(())
+}
+
+fn write_derived_scheme(derived: &ConfigDerivedScheme, data_path: &Path) -> Result<String> {
+    let schemes_path = paths::schemes_repo_path(data_path);
+    let custom_schemes_path = data_path.join(CUSTOM_SCHEMES_DIR_NAME);
+    let builtin_scheme_files = get_all_scheme_file_paths(&schemes_path, None)?;
+    let custom_scheme_files = get_all_scheme_file_paths(&custom_schemes_path, None).ok();
+    let base_scheme_file = builtin_scheme_files
+        .get(&derived.base)
+        .or_else(|| custom_scheme_files.as_ref().and_then(|m| m.get(&derived.base)))
+        .ok_or_else(|| {
+            anyhow!(
+                "Base scheme does not exist: {}. Run `{REPO_NAME} list` to see the available schemes",
+                derived.base
+            )
+        })?;
+
+    let base_content = fs::read_to_string(base_scheme_file.get_path())?;
+    let mut scheme: Value = serde_yaml::from_str(&base_content)
+        .with_context(|| format!("Unable to parse base scheme {}", derived.base))?;
+    let Value::Mapping(ref mut mapping) = scheme else {
+        return Err(anyhow!(
+            "Base scheme {} is not a YAML mapping",
+            derived.base
+        ));
+    };
+
+    let system = match mapping.get("system").and_then(Value::as_str) {
+        Some(system) if system == SchemeSystem::Base16.as_str() => SchemeSystem::Base16,
+        Some(system) if system == SchemeSystem::Base24.as_str() => SchemeSystem::Base24,
+        _ => {
+            return Err(anyhow!(
+                "Base scheme {} is not a {} or {} scheme. Only these systems can be derived from",
+                derived.base,
+                SchemeSystem::Base16.as_str(),
+                SchemeSystem::Base24.as_str(),
+            ))
+        }
+    };
+    let scheme_id = format!("{system}-{}", derived.slug);
+
+    if scheme_id == derived.base {
+        return Err(anyhow!("A derived scheme can not use itself as its base"));
+    }
+
+    if builtin_scheme_files.contains_key(&scheme_id) {
+        return Err(anyhow!(
+            "{scheme_id} is already an official tinted-theming/schemes name, please choose a different slug"
+        ));
+    }
+
+    let base_name = mapping
+        .get("name")
+        .and_then(Value::as_str)
+        .unwrap_or(derived.base.as_str())
+        .to_string();
+    let Some(Value::Mapping(palette)) = mapping.get_mut("palette") else {
+        return Err(anyhow!("Base scheme {} has no palette", derived.base));
+    };
+
+    for operation in &derived.operations {
+        apply_operation(palette, operation)?;
+    }
+
+    for (slot, color) in &derived.overrides {
+        let key = Value::String(slot.clone());
+        if !palette.contains_key(&key) {
+            return Err(anyhow!(
+                "Unknown palette slot \"{slot}\" in overrides for {system} scheme"
+            ));
+        }
+        palette.insert(key, Value::String(Rgb::from_hex(color)?.to_hex()));
+    }
+
+    set_derived_metadata(mapping, derived, &base_name);
+
+    let system_dir = custom_schemes_path.join(system.as_str());
+    let output_path = system_dir.join(format!("{}.yaml", derived.slug));
+
+    if output_path.exists() {
+        let existing = fs::read_to_string(&output_path).unwrap_or_default();
+        if !existing.starts_with(DERIVED_SCHEME_HEADER) {
+            return Err(anyhow!(
+                "A custom scheme that was not derived by {REPO_NAME} already exists at {}. Rename the derived scheme or remove that file",
+                output_path.display()
+            ));
+        }
+    }
+
+    ensure_directory_exists(&system_dir)?;
+    write_to_file(
+        &output_path,
+        &format!(
+            "{DERIVED_SCHEME_HEADER}\n# It is rewritten by `{REPO_NAME} install`, `{REPO_NAME} update` and `{REPO_NAME} derive`; edit config.toml instead.\n{}",
+            serde_yaml::to_string(&scheme)?
+        ),
+    )?;
+
+    Ok(scheme_id)
+}
+
+/// Replaces the base scheme's identity with the derived scheme's. The author is
+/// kept unless the derived scheme overrides it.
+fn set_derived_metadata(mapping: &mut Mapping, derived: &ConfigDerivedScheme, base_name: &str) {
+    mapping.insert(
+        Value::String("name".to_string()),
+        Value::String(
+            derived
+                .name
+                .clone()
+                .unwrap_or_else(|| format!("{base_name} (derived)")),
+        ),
+    );
+    mapping.insert(
+        Value::String("slug".to_string()),
+        Value::String(derived.slug.clone()),
+    );
+    if let Some(author) = &derived.author {
+        mapping.insert(
+            Value::String("author".to_string()),
+            Value::String(author.clone()),
+        );
+    }
+    mapping.insert(
+        Value::String("description".to_string()),
+        Value::String(
+            derived
+                .description
+                .clone()
+                .unwrap_or_else(|| format!("Derived from {} by {REPO_NAME}", derived.base)),
+        ),
+    );
+}
+
+fn apply_operation(palette: &mut Mapping, operation: &DeriveOperation) -> Result<()> {
+    let slots: Vec<String> = operation.slots.clone().unwrap_or_else(|| {
+        palette
+            .keys()
+            .filter_map(Value::as_str)
+            .map(ToString::to_string)
+            .collect()
+    });
+
+    for slot in slots {
+        let key = Value::String(slot.clone());
+        let color = palette.get(&key).and_then(Value::as_str).ok_or_else(|| {
+            anyhow!(
+                "Unknown palette slot \"{slot}\" in {} operation",
+                operation.op.as_str()
+            )
+        })?;
+        let adjusted = adjust_color(Rgb::from_hex(color)?, operation);
+
+        palette.insert(key, Value::String(adjusted.to_hex()));
+    }
+
+    Ok(())
+}
+
+/// Applies a single operation to a color. Lightness amounts are points on a 0-100 scale,
+/// saturation amounts are percentages and hue amounts are degrees.
+fn adjust_color(color: Rgb, operation: &DeriveOperation) -> Rgb {
+    let amount = operation.amount;
+    match operation.space {
+        ColorSpace::Hsl => {
+            let mut hsl = color.to_hsl();
+            match operation.op {
+                DeriveOperationKind::Lighten => hsl.l += amount / 100.0,
+                DeriveOperationKind::Darken => hsl.l -= amount / 100.0,
+                DeriveOperationKind::Saturate => hsl.s *= 1.0 + amount / 100.0,
+                DeriveOperationKind::Desaturate => hsl.s *= (1.0 - amount / 100.0).max(0.0),
+                DeriveOperationKind::HueRotate => hsl.h += amount,
+            }
+            hsl.to_rgb()
+        }
+        ColorSpace::Oklch => {
+            let mut oklch = color.to_oklch();
+            match operation.op {
+                DeriveOperationKind::Lighten => oklch.l += amount / 100.0,
+                DeriveOperationKind::Darken => oklch.l -= amount / 100.0,
+                DeriveOperationKind::Saturate => oklch.c *= 1.0 + amount / 100.0,
+                DeriveOperationKind::Desaturate => oklch.c *= (1.0 - amount / 100.0).max(0.0),
+                DeriveOperationKind::HueRotate => oklch.h += amount,
+            }
+            oklch.to_rgb()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn operation(op: DeriveOperationKind, amount: f32, space: ColorSpace) -> DeriveOperation {
+        DeriveOperation {
+            op,
+            amount,
+            slots: None,
+            space,
+        }
+    }
+
+    #[test]
+    fn darken_and_lighten_move_lightness_in_both_spaces() {
+        let color = Rgb::from_hex("#1e1e2e").unwrap();
+        for space in [ColorSpace::Hsl, ColorSpace::Oklch] {
+            let darker = adjust_color(color, &operation(DeriveOperationKind::Darken, 5.0, space));
+            let lighter = adjust_color(color, &operation(DeriveOperationKind::Lighten, 5.0, space));
+
+            assert!(darker.to_oklch().l < color.to_oklch().l);
+            assert!(lighter.to_oklch().l > color.to_oklch().l);
+        }
+    }
+
+    #[test]
+    fn desaturate_by_100_percent_produces_grey() {
+        let color = Rgb::from_hex("#89b4fa").unwrap();
+        let grey = adjust_color(
+            color,
+            &operation(DeriveOperationKind::Desaturate, 100.0, ColorSpace::Hsl),
+        );
+
+        assert!(grey.to_hsl().s < f32::EPSILON);
+    }
+
+    #[test]
+    fn hue_rotate_by_full_turn_is_identity() {
+        let color = Rgb::from_hex("#f38ba8").unwrap();
+        let rotated = adjust_color(
+            color,
+            &operation(DeriveOperationKind::HueRotate, 360.0, ColorSpace::Oklch),
+        );
+
+        assert_eq!(rotated.to_hex(), color.to_hex());
+    }
+
+    #[test]
+    fn apply_operation_limits_changes_to_listed_slots() {
+        let mut palette = Mapping::new();
+        palette.insert("base00".into(), "#1e1e2e".into());
+        palette.insert("base05".into(), "#cdd6f4".into());
+        let darken = DeriveOperation {
+            slots: Some(vec!["base00".to_string()]),
+            ..operation(DeriveOperationKind::Darken, 10.0, ColorSpace::Oklch)
+        };
+
+        apply_operation(&mut palette, &darken).unwrap();
+
+        assert_ne!(
+            palette.get("base00").and_then(Value::as_str),
+            Some("#1e1e2e")
+        );
+        assert_eq!(
+            palette.get("base05").and_then(Value::as_str),
+            Some("#cdd6f4")
+        );
+    }
+
+    #[test]
+    fn apply_operation_rejects_unknown_slots() {
+        let mut palette = Mapping::new();
+        palette.insert("base00".into(), "#1e1e2e".into());
+        let darken = DeriveOperation {
+            slots: Some(vec!["base42".to_string()]),
+            ..operation(DeriveOperationKind::Darken, 10.0, ColorSpace::Oklch)
+        };
+
+        assert!(apply_operation(&mut palette, &darken).is_err());
+    }
+}
@@ -1,8 +1,8 @@
 mod server;
 
 use crate::{
-    constants::ARTIFACTS_DIR,
     operations::list::{scheme_entries_json, schemes_dir_path},
+    paths,
     utils::{ensure_directory_exists, write_to_file},
 };
 use anyhow::{Context, Result};
@@ -112,6 +112,7 @@ fn hostname_label() -> String {
 pub fn serve(
     config_path: &Path,
     data_path: &Path,
+    profile: Option<&str>,
     is_custom: bool,
     port: Option<u16>,
     should_open: bool,
@@ -136,6 +137,7 @@ pub fn serve(
         assets,
         config_path.to_path_buf(),
         data_path.to_path_buf(),
+        profile.map(ToString::to_string),
         port,
         should_open,
     )
@@ -143,6 +145,7 @@ pub fn serve(
 
 pub fn gallery(
     data_path: &Path,
+    profile: Option<&str>,
     is_custom: bool,
     dump_dir: Option<&str>,
     should_open: bool,
@@ -150,7 +153,7 @@ pub fn gallery(
     let schemes_path = schemes_dir_path(data_path, is_custom)?;
     let schemes_json = scheme_entries_json(&schemes_path)?;
     let output_dir = dump_dir.map_or_else(
-        || data_path.join(ARTIFACTS_DIR).join(GALLERY_DIR_NAME),
+        || paths::artifacts_dir(data_path, profile).join(GALLERY_DIR_NAME),
         PathBuf::from,
     );
 
@@ -10,8 +10,10 @@
 //! used elsewhere). The only clients are this gallery's own JavaScript and the
 //! browser fetching assets, so the request surface is small and controlled.
 
+use crate::config::Config;
 use crate::operations::apply::apply;
 use crate::operations::current::get_current_scheme_slug;
+use crate::scheme_index::SchemeIndex;
 use anyhow::{anyhow, Context as _, Result};
 use serde::Deserialize;
 use serde_json::{json, Value};
@@ -42,6 +44,7 @@ struct ServerContext {
     assets: Assets,
     config_path: PathBuf,
     data_path: PathBuf,
+    profile: Option<String>,
     /// Serializes `apply` calls so two in-flight requests can't race on the
     /// artifacts directory.
     apply_lock: Mutex<()>,
@@ -135,6 +138,7 @@ pub fn serve(
     assets: Assets,
     config_path: PathBuf,
     data_path: PathBuf,
+    profile: Option<String>,
     port: Option<u16>,
     should_open: bool,
 ) -> Result<()> {
@@ -149,6 +153,7 @@ pub fn serve(
         assets,
         config_path,
         data_path,
+        profile,
         apply_lock: Mutex::new(()),
     });
 
@@ -261,7 +266,7 @@ fn route(request: &ParsedRequest, context: &ServerContext) -> Response {
 
 /// `GET /api/current` — the currently applied scheme slug, or `null`.
 fn current_response(context: &ServerContext) -> Response {
-    let current = get_current_scheme_slug(&context.data_path);
+    let current = get_current_scheme_slug(&context.data_path, context.profile.as_deref());
     let trimmed = current.trim();
     let scheme = if trimmed.is_empty() {
         Value::Null
@@ -290,16 +295,36 @@ fn apply_response(request: &ParsedRequest, context: &ServerContext) -> Response
         );
     };
 
+    // Resolve up front so the response reports the scheme ID that was applied
+    // even when the request used a bare slug or a scheme name.
+    let scheme_index =
+        match Config::read_with_profile(&context.config_path, context.profile.as_deref()) {
+            Ok(config) => {
+                SchemeIndex::for_config(&config, &context.data_path).with_aliases(&config.aliases)
+            }
+            Err(_) => SchemeIndex::for_data_path(&context.data_path),
+        };
+    let scheme = match scheme_index.resolve(&payload.scheme) {
+        Ok(scheme) => scheme,
+        Err(err) => {
+            return Response::json(
+                "404 Not Found",
+                &json!({ "ok": false, "error": format!("{err:#}") }),
+            );
+        }
+    };
+
     match apply(
         &context.config_path,
         &context.data_path,
-        &payload.scheme,
+        context.profile.as_deref(),
+        &scheme,
         true,
         None,
     ) {
         Ok(()) => {
-            println!("Applied {}", payload.scheme);
-            Response::json("200 OK", &json!({ "ok": true, "scheme": payload.scheme }))
+            println!("Applied {scheme}");
+            Response::json("200 OK", &json!({ "ok": true, "scheme": scheme }))
         }
         Err(err) => Response::json(
             "400 Bad Request",
@@ -1,14 +1,57 @@
+use std::collections::HashMap;
 use std::path::PathBuf;
 
-use anyhow::Result;
-use tinted_builder::{SchemeSystem, SchemeVariant};
+use anyhow::{anyhow, Result};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use tinted_builder::{base16::Scheme as Base16Scheme, Color, SchemeSystem, SchemeVariant};
 use tinted_scheme_extractor::{create_scheme_from_image, SchemeParams};
 
+use crate::color::{normalize_hue, Oklch, Rgb};
+use crate::constants::REPO_NAME;
 use crate::utils::write_to_file;
 
+/// Where `generate-scheme` takes its colors from.
+pub enum SchemeSource {
+    Image(PathBuf),
+    Color(Rgb),
+    Random { seed: u64 },
+}
+
+/// Accent slots and the OKLCH hue each one conventionally carries.
+const ACCENT_HUES: [(&str, f32); 7] = [
+    ("base08", 25.0),
+    ("base09", 55.0),
+    ("base0A", 95.0),
+    ("base0B", 145.0),
+    ("base0C", 195.0),
+    ("base0D", 255.0),
+    ("base0E", 310.0),
+];
+
+/// Base24 bright accents and the base16 accent each one brightens.
+const BRIGHT_ACCENTS: [(&str, &str); 6] = [
+    ("base12", "base08"),
+    ("base13", "base0A"),
+    ("base14", "base0B"),
+    ("base15", "base0C"),
+    ("base16", "base0D"),
+    ("base17", "base0E"),
+];
+
+/// OKLCH lightness of `base00` through `base07` for dark and light variants.
+const DARK_RAMP: [f32; 8] = [0.20, 0.25, 0.31, 0.45, 0.62, 0.84, 0.90, 0.95];
+const LIGHT_RAMP: [f32; 8] = [0.97, 0.92, 0.86, 0.70, 0.55, 0.35, 0.28, 0.22];
+
+/// Minimum WCAG contrast against `base00` for comments, dark foreground,
+/// default foreground and accents.
+const COMMENT_CONTRAST: f32 = 3.0;
+const SUBTLE_CONTRAST: f32 = 4.5;
+const FOREGROUND_CONTRAST: f32 = 7.0;
+const ACCENT_CONTRAST: f32 = 4.5;
+
 #[allow(clippy::too_many_arguments)]
 pub fn generate_scheme(
-    image_path: PathBuf,
+    source: SchemeSource,
     output_file_path_option: Option<PathBuf>,
     author: String,
     description: Option<String>,
@@ -17,17 +60,44 @@ pub fn generate_scheme(
     system: SchemeSystem,
     variant: SchemeVariant,
 ) -> Result<()> {
-    let params = SchemeParams {
-        author,
-        description,
-        image_path,
-        name,
-        slug,
-        system,
-        variant,
-        verbose: false,
+    let scheme = match source {
+        SchemeSource::Image(image_path) => {
+            let params = SchemeParams {
+                author,
+                description,
+                image_path,
+                name,
+                slug,
+                system,
+                variant,
+                verbose: false,
+            };
+            create_scheme_from_image(params)?
+        }
+        SchemeSource::Color(color) => Base16Scheme {
+            description: description
+                .or_else(|| Some(format!("Generated by {REPO_NAME} from {}", color.to_hex()))),
+            palette: palette_from_seed(&PaletteSeed::from_color(color), &system, &variant)?,
+            author,
+            name,
+            slug,
+            system,
+            variant,
+        },
+        SchemeSource::Random { seed } => Base16Scheme {
+            description: description.or_else(|| {
+                Some(format!(
+                    "Generated by {REPO_NAME} with --random --seed {seed}"
+                ))
+            }),
+            palette: palette_from_seed(&PaletteSeed::random(seed), &system, &variant)?,
+            author,
+            name,
+            slug,
+            system,
+            variant,
+        },
     };
-    let scheme = create_scheme_from_image(params)?;
 
     match output_file_path_option {
         Some(path) => {
@@ -42,3 +112,253 @@ pub fn generate_scheme(
 
     Ok(())
 }
+
+/// The inputs a palette is built from. `color` is the seed color when the user
+/// supplied one; it is placed verbatim in the accent slot closest in hue,
+/// provided it has enough contrast against the background.
+struct PaletteSeed {
+    hue: f32,
+    chroma: f32,
+    background_chroma: f32,
+    color: Option<Oklch>,
+}
+
+impl PaletteSeed {
+    fn from_color(color: Rgb) -> Self {
+        let oklch = color.to_oklch();
+
+        Self {
+            hue: oklch.h,
+            chroma: oklch.c.clamp(0.06, 0.2),
+            background_chroma: (oklch.c * 0.2).min(0.03),
+            color: Some(oklch),
+        }
+    }
+
+    fn random(seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        Self {
+            hue: rng.gen_range(0.0..360.0),
+            chroma: rng.gen_range(0.08..0.18),
+            background_chroma: rng.gen_range(0.0..0.04),
+            color: None,
+        }
+    }
+}
+
+fn palette_from_seed(
+    seed: &PaletteSeed,
+    system: &SchemeSystem,
+    variant: &SchemeVariant,
+) -> Result<HashMap<String, Color>> {
+    let is_light = matches!(variant, SchemeVariant::Light);
+    let ramp = if is_light { LIGHT_RAMP } else { DARK_RAMP };
+    let background_color = |l: f32| Oklch {
+        l,
+        c: seed.background_chroma,
+        h: seed.hue,
+    };
+    let base00 = background_color(ramp[0]).to_rgb();
+    let mut colors: Vec<(String, Rgb)> = Vec::new();
+
+    for (index, lightness) in ramp.iter().enumerate() {
+        let color = background_color(*lightness);
+        let min_contrast = match index {
+            3 => COMMENT_CONTRAST,
+            4 => SUBTLE_CONTRAST,
+            5 => FOREGROUND_CONTRAST,
+            _ => 1.0,
+        };
+        colors.push((
+            format!("base0{index}"),
+            with_contrast(color, base00, min_contrast, is_light),
+        ));
+    }
+
+    let accents = accent_colors(seed, is_light);
+
+    for (slot, accent) in &accents {
+        let min_contrast = if slot == "base0F" {
+            COMMENT_CONTRAST
+        } else {
+            ACCENT_CONTRAST
+        };
+        colors.push((
+            slot.clone(),
+            with_contrast(*accent, base00, min_contrast, is_light),
+        ));
+    }
+
+    if matches!(system, SchemeSystem::Base24) {
+        let darker = if is_light { 0.015 } else { -0.04 };
+        colors.push((
+            "base10".to_string(),
+            background_color(ramp[0] + darker).to_rgb(),
+        ));
+        colors.push((
+            "base11".to_string(),
+            background_color(ramp[0] + darker * 2.0).to_rgb(),
+        ));
+
+        let brighter = if is_light { -0.08 } else { 0.08 };
+        for (slot, accent_slot) in BRIGHT_ACCENTS {
+            if let Some((_, accent)) = accents.iter().find(|(s, _)| s == accent_slot) {
+                let bright = Oklch {
+                    l: accent.l + brighter,
+                    ..*accent
+                };
+                colors.push((
+                    slot.to_string(),
+                    with_contrast(bright, base00, ACCENT_CONTRAST, is_light),
+                ));
+            }
+        }
+    }
+
+    colors
+        .into_iter()
+        .map(|(slot, rgb)| {
+            Color::new(&rgb.to_hex(), None, None)
+                .map(|color| (slot, color))
+                .map_err(|err| anyhow!("Unable to generate color: {err}"))
+        })
+        .collect()
+}
+
+/// The base16 accents (`base08` to `base0F`) before contrast adjustment.
+fn accent_colors(seed: &PaletteSeed, is_light: bool) -> Vec<(String, Oklch)> {
+    // Rotate every accent by the distance from the seed hue to the nearest
+    // conventional accent hue, so red stays red-ish and blue stays blue-ish
+    // while the whole set leans towards the seed.
+    let offset = ACCENT_HUES
+        .iter()
+        .map(|(_, hue)| hue_difference(*hue, seed.hue))
+        .min_by(|a, b| a.abs().total_cmp(&b.abs()))
+        .unwrap_or(0.0);
+    let accent_lightness = if is_light { 0.55 } else { 0.72 };
+    let mut accents: Vec<(String, Oklch)> = ACCENT_HUES
+        .iter()
+        .map(|(slot, hue)| {
+            (
+                (*slot).to_string(),
+                Oklch {
+                    l: accent_lightness,
+                    c: seed.chroma,
+                    h: normalize_hue(hue + offset),
+                },
+            )
+        })
+        .collect();
+
+    if let Some(seed_color) = seed.color {
+        if let Some((_, accent)) = accents.iter_mut().min_by(|(_, a), (_, b)| {
+            hue_difference(a.h, seed_color.h)
+                .abs()
+                .total_cmp(&hue_difference(b.h, seed_color.h).abs())
+        }) {
+            *accent = seed_color;
+        }
+    }
+
+    // base0F is conventionally a muted brown next to the orange accent.
+    accents.push((
+        "base0F".to_string(),
+        Oklch {
+            l: if is_light { 0.48 } else { 0.62 },
+            c: seed.chroma * 0.6,
+            h: normalize_hue(45.0 + offset),
+        },
+    ));
+
+    accents
+}
+
+/// Moves `color` away from the background in lightness until it reaches
+/// `min_contrast` against it, or the end of the lightness range.
+fn with_contrast(color: Oklch, background: Rgb, min_contrast: f32, is_light: bool) -> Rgb {
+    let step = if is_light { -0.01 } else { 0.01 };
+    let mut candidate = color;
+
+    for _ in 0..100 {
+        let rgb = candidate.to_rgb();
+        if rgb.contrast_ratio(background) >= min_contrast
+            || !(0.0..=1.0).contains(&(candidate.l + step))
+        {
+            return rgb;
+        }
+        candidate.l += step;
+    }
+
+    candidate.to_rgb()
+}
+
+/// Signed shortest angle in degrees from hue `from` to hue `to`.
+fn hue_difference(from: f32, to: f32) -> f32 {
+    (to - from + 540.0).rem_euclid(360.0) - 180.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{palette_from_seed, PaletteSeed, ACCENT_CONTRAST};
+    use crate::color::Rgb;
+    use tinted_builder::{SchemeSystem, SchemeVariant};
+
+    fn hex(
+        palette: &std::collections::HashMap<String, tinted_builder::Color>,
+        slot: &str,
+    ) -> String {
+        let color = palette.get(slot).unwrap();
+        format!("#{}{}{}", color.hex.0, color.hex.1, color.hex.2)
+    }
+
+    #[test]
+    fn random_palette_is_reproducible_for_a_seed() {
+        let first = palette_from_seed(
+            &PaletteSeed::random(42),
+            &SchemeSystem::Base16,
+            &SchemeVariant::Dark,
+        )
+        .unwrap();
+        let second = palette_from_seed(
+            &PaletteSeed::random(42),
+            &SchemeSystem::Base16,
+            &SchemeVariant::Dark,
+        )
+        .unwrap();
+
+        assert_eq!(first.len(), 16);
+        for slot in first.keys() {
+            assert_eq!(hex(&first, slot), hex(&second, slot));
+        }
+    }
+
+    #[test]
+    fn from_color_places_seed_in_nearest_accent() {
+        let seed = Rgb::from_hex("#88c0d0").unwrap();
+        let palette = palette_from_seed(
+            &PaletteSeed::from_color(seed),
+            &SchemeSystem::Base16,
+            &SchemeVariant::Dark,
+        )
+        .unwrap();
+
+        assert_eq!(hex(&palette, "base0C"), "#88c0d0");
+    }
+
+    #[test]
+    fn accents_have_contrast_against_background() {
+        for variant in [SchemeVariant::Dark, SchemeVariant::Light] {
+            let palette =
+                palette_from_seed(&PaletteSeed::random(7), &SchemeSystem::Base24, &variant)
+                    .unwrap();
+            let background = Rgb::from_hex(&hex(&palette, "base00")).unwrap();
+
+            assert_eq!(palette.len(), 24);
+            for slot in ["base08", "base0A", "base0B", "base0D", "base12", "base16"] {
+                let accent = Rgb::from_hex(&hex(&palette, slot)).unwrap();
+                assert!(accent.contrast_ratio(background) >= ACCENT_CONTRAST - 0.05);
+            }
+        }
+    }
+}
@@ -1,6 +1,8 @@
+use crate::config::Config;
 use crate::constants::{CUSTOM_SCHEMES_DIR_NAME, REPO_NAME, REPO_URL};
 use crate::operations::current::get_current_scheme_slug;
 use crate::paths;
+use crate::scheme_index::SchemeIndex;
 use anyhow::{anyhow, Result};
 use hex_color::HexColor;
 use serde::Deserialize;
@@ -382,43 +384,14 @@ fn print_scheme(scheme_path: &Path) -> Result<()> {
     Ok(())
 }
 
-fn print_single_schemes(files: &[PathBuf], scheme_name: &str) -> Result<()> {
-    let scheme_system_name = scheme_name.split('-').next().unwrap_or_default();
+/// Prints the scheme `scheme_name` resolves to: an ID, slug, name or alias of
+/// an official, custom or `[[scheme-sources]]` scheme, as `apply` accepts.
+fn print_single_scheme(scheme_index: &SchemeIndex, scheme_name: &str) -> Result<()> {
+    let scheme_name = scheme_index.resolve(scheme_name)?;
 
-    if !SchemeSystem::variants()
-        .iter()
-        .map(SchemeSystem::as_str)
-        .any(|x| x == scheme_system_name)
-    {
-        return Err(anyhow!(
-            r#"Invalid scheme system: "{}" from scheme name "{}"
-Make sure to add the system prefix to the theme name. Eg: {}-oceanicnext
-Run `{} list` to get a list of scheme names"#,
-            scheme_system_name,
-            scheme_name,
-            SchemeSystem::default(),
-            REPO_NAME
-        ));
-    }
+    let scheme_file = scheme_index.get(&scheme_name).ok_or_else(|| anyhow!("Scheme file does not exist. Perhaps schemes are outdated, try running `{REPO_NAME} update`\nIf the problem persist please create an issue at {REPO_URL}/issues"))?;
 
-    let scheme_name_without_system = scheme_name
-        .split('-')
-        .skip(1)
-        .collect::<Vec<&str>>()
-        .join("-");
-
-    match files.iter().find(|path|
-        path.parent().is_some_and(|parent_path|
-        path.file_stem().unwrap_or_default().to_string_lossy() == scheme_name_without_system
-            && parent_path.file_name().unwrap_or_default() == scheme_system_name)
-    ) {
-        Some(scheme_path) => {
-            print_scheme(scheme_path)?;
-        }
-        None => return Err(anyhow!("Scheme file does not exist. Perhaps schemes are outdated, try running `{REPO_NAME} update`\nIf the problem persist please create an issue at {REPO_URL}/issues")),
-    }
-
-    Ok(())
+    print_scheme(&scheme_file.get_path())
 }
 
 fn print_all_schemes(files: Vec<PathBuf>) -> Result<()> {
@@ -437,7 +410,9 @@ fn print_all_schemes(files: Vec<PathBuf>) -> Result<()> {
 }
 
 pub fn info(
+    config_path: &Path,
     data_path: &Path,
+    profile: Option<&str>,
     scheme_name_option: Option<&String>,
     is_custom: bool,
     exhaustive_list: bool,
@@ -490,8 +465,14 @@ pub fn info(
     if scheme_name_option.is_some() || !exhaustive_list {
         let scheme_name = scheme_name_option
             .cloned()
-            .unwrap_or_else(|| get_current_scheme_slug(data_path));
-        print_single_schemes(&files, &scheme_name)?;
+            .unwrap_or_else(|| get_current_scheme_slug(data_path, profile));
+        let config = Config::read_with_profile(config_path, profile)?;
+        let scheme_index = if is_custom {
+            SchemeIndex::from_dirs(&[schemes_dir_path])
+        } else {
+            SchemeIndex::for_config(&config, data_path)
+        };
+        print_single_scheme(&scheme_index.with_aliases(&config.aliases), &scheme_name)?;
     } else {
         print_all_schemes(files)?;
     }
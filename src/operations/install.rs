@@ -1,79 +1,39 @@
 use crate::config::{ensure_schemes_path_not_circular, Config};
 use crate::constants::SCHEMES_REPO_NAME;
+use crate::jobs::{self, Job};
+use crate::lockfile;
+use crate::operations::derive::derive_schemes;
 use crate::paths;
-use crate::repo;
-use anyhow::{anyhow, Context, Result};
+use crate::repo::{self, CloneOptions};
+use anyhow::{Context, Result};
 use std::fs::{remove_file as remove_symlink, symlink_metadata};
-use std::os::unix::fs::symlink;
-use std::path::{Path, PathBuf};
-use url::Url;
-
-fn install_git_url(
+use std::num::NonZeroUsize;
+use std::path::Path;
+
+/// Installs an `[[items]]` (or `[schemes]`) source into `data_item_path`
+/// through its repository backend. A Git URL is cloned (and an archive
+/// unpacked) once, after which `update` moves it along. A local directory's
+/// symlink is cheap to recreate, so it is refreshed on every install in case
+/// the configured path changed. `options` only shape a fresh clone.
+/// Returns the message to print.
+fn install_item(
     data_item_path: &Path,
     item_name: &str,
-    item_git_url: &str,
+    source: &str,
     revision: Option<&str>,
-    is_quiet: bool,
-) -> Result<()> {
-    if !data_item_path.is_dir() {
-        repo::install(item_git_url, data_item_path, revision)?;
-
-        if !is_quiet {
-            println!("{item_name} installed");
-        }
-    } else if !is_quiet {
-        println!("{item_name} already installed");
-    }
+    options: &CloneOptions,
+) -> Result<String> {
+    let is_installed = data_item_path.is_dir();
 
-    Ok(())
-}
-
-// TODO(repo-backend): non-URL `[[items]]` (a local path on disk) take this
-// symlink-based path instead of going through `RepositoryBackend`. A future
-// `LocalPathBackend` implementing `RepositoryBackend` would let us route both
-// branches of the dispatch in `install()` below through a single trait, with
-// `is_clean` returning a sensible answer for symlinked source dirs and
-// `update` becoming a no-op (or re-validating the symlink target).
-fn install_dir(
-    data_item_path: &Path,
-    item_name: &str,
-    item_path: &Path,
-    is_quiet: bool,
-) -> Result<()> {
-    if item_path.exists() && !item_path.is_dir() {
-        return Err(anyhow!(
-            "{} is not a symlink to a directory. Please remove it and try again",
-            item_path.display()
-        ));
+    if !is_installed || repo::is_local_dir(source) {
+        repo::install(source, data_item_path, revision, options)?;
     }
 
-    if data_item_path.exists() {
-        match symlink_metadata(data_item_path) {
-            Ok(metadata) => {
-                if metadata.file_type().is_symlink() {
-                    if remove_symlink(data_item_path).is_err() {
-                        return Err(anyhow!("Error trying to remove symlink at \"{}\". Remove it manually and try again", data_item_path.display()));
-                    }
-                    symlink(item_path, data_item_path)?;
-
-                    if !is_quiet {
-                        println!("{item_name} already installed");
-                    }
-                }
-            }
-            Err(_) => {
-                return Err(anyhow!("\"{}\" is a not a symlink, but according to your config it should be. Please remove this directory and try again", data_item_path.display()));
-            }
-        }
+    if is_installed {
+        Ok(format!("{item_name} already installed"))
     } else {
-        symlink(item_path, data_item_path)?;
-
-        if !is_quiet {
-            println!("{item_name} installed");
-        }
+        Ok(format!("{item_name} installed"))
     }
-
-    Ok(())
 }
 
 /// Normalizes a Git URL for equality checks by trimming a trailing slash and a
@@ -88,11 +48,12 @@ fn git_url_eq(a: &str, b: &str) -> bool {
     normalize(a) == normalize(b)
 }
 
-/// Prepares the `repos/schemes` slot to hold a fresh clone of `source`. Removes
-/// a symlink left by a previous local-path source, or a stale clone whose
-/// `origin` points at a different URL, so `install_git_url` re-clones from the
-/// now-configured source. A clone already pointing at `source` (or an empty
-/// slot) is left untouched, so the common case does no extra work.
+/// Prepares the `repos/schemes` slot to hold a fresh clone (or unpacked
+/// archive) of `source`. Removes a symlink left by a previous local-path
+/// source, or a stale clone whose `origin` points at a different source, so
+/// `install_item` re-installs from the now-configured source. A clone already
+/// pointing at `source` (or an empty slot) is left untouched, so the common
+/// case does no extra work.
 fn prepare_clone_slot(schemes_repo_path: &Path, source: &str) -> Result<()> {
     let Ok(metadata) = symlink_metadata(schemes_repo_path) else {
         return Ok(()); // Nothing occupies the slot yet.
@@ -109,8 +70,8 @@ fn prepare_clone_slot(schemes_repo_path: &Path, source: &str) -> Result<()> {
 
     // A real directory: an existing clone. Re-clone only when its origin no
     // longer matches the configured source.
-    let matches_source =
-        repo::origin_url(schemes_repo_path)?.is_some_and(|origin| git_url_eq(&origin, source));
+    let matches_source = repo::origin_url(schemes_repo_path, source)?
+        .is_some_and(|origin| git_url_eq(&origin, source));
     if !matches_source {
         std::fs::remove_dir_all(schemes_repo_path).with_context(|| {
             format!(
@@ -124,9 +85,9 @@ fn prepare_clone_slot(schemes_repo_path: &Path, source: &str) -> Result<()> {
 }
 
 /// Prepares the `repos/schemes` slot to hold a symlink to a local directory.
-/// Removes a clone left by a previous Git-URL source so `install_dir` can create
-/// the symlink. An existing symlink (or empty slot) is left for `install_dir` to
-/// refresh.
+/// Removes a clone left by a previous Git-URL source so `install_item` can
+/// create the symlink. An existing symlink (or empty slot) is left for
+/// `install_item` to refresh.
 fn prepare_symlink_slot(schemes_repo_path: &Path) -> Result<()> {
     let Ok(metadata) = symlink_metadata(schemes_repo_path) else {
         return Ok(()); // Nothing occupies the slot yet.
@@ -145,69 +106,104 @@ fn prepare_symlink_slot(schemes_repo_path: &Path) -> Result<()> {
 }
 
 /// Installs the built-in schemes repository from its configured source. A Git
-/// URL is cloned into `repos/schemes`; a local directory is symlinked as
-/// `repos/schemes` (with `revision` ignored, exactly like a local-path
-/// `[[items]]` entry).
+/// URL is cloned into `repos/schemes` and an archive is unpacked there; a local
+/// directory is symlinked as `repos/schemes` (with `revision` ignored, exactly
+/// like a local-path `[[items]]` entry).
 fn install_schemes_repo(
     schemes_repo_path: &Path,
     source: &str,
     revision: Option<&str>,
-    is_quiet: bool,
-) -> Result<()> {
-    if Url::parse(source).is_ok() {
-        prepare_clone_slot(schemes_repo_path, source)?;
-        install_git_url(
-            schemes_repo_path,
-            SCHEMES_REPO_NAME,
-            source,
-            revision,
-            is_quiet,
-        )
-    } else {
+    options: &CloneOptions,
+) -> Result<String> {
+    if repo::is_local_dir(source) {
         prepare_symlink_slot(schemes_repo_path)?;
-        install_dir(
-            schemes_repo_path,
-            SCHEMES_REPO_NAME,
-            Path::new(source),
-            is_quiet,
-        )
+    } else {
+        prepare_clone_slot(schemes_repo_path, source)?;
     }
+
+    install_item(
+        schemes_repo_path,
+        SCHEMES_REPO_NAME,
+        source,
+        revision,
+        options,
+    )
 }
 
 /// Install cli tool
 ///
 /// Clones the provided config repositories and ensures everything is ready for when the user runs
-/// any other command
-pub fn install(config_path: &Path, data_path: &Path, is_quiet: bool) -> Result<()> {
+/// any other command. Up to `max_jobs` repositories are cloned at a time, and
+/// newly installed repositories are pinned in `tinty.lock`. With `is_offline`,
+/// fails before installing anything if a repository that is not installed yet
+/// would have to be cloned over the network.
+pub fn install(
+    config_path: &Path,
+    data_path: &Path,
+    is_quiet: bool,
+    max_jobs: Option<NonZeroUsize>,
+    is_offline: bool,
+) -> Result<()> {
     let config = Config::read(config_path)?;
     let (schemes_source, schemes_revision) = config.schemes_source();
-    let items = config.items.unwrap_or_default();
-
-    for item in items {
-        let data_item_path = paths::item_repo_path(data_path, &item.name);
-        let item_path = PathBuf::from(item.path.as_str());
-
-        match Url::parse(item.path.as_str()) {
-            Ok(_) => install_git_url(
-                &data_item_path,
-                item.name.as_str(),
-                item.path.as_str(),
-                item.revision.as_deref(),
-                is_quiet,
-            )?,
-            Err(_) => install_dir(&data_item_path, item.name.as_str(), &item_path, is_quiet)?,
-        }
-    }
-
     let schemes_repo_path = paths::schemes_repo_path(data_path);
 
     ensure_schemes_path_not_circular(&schemes_source, &schemes_repo_path)?;
-    install_schemes_repo(
-        &schemes_repo_path,
-        &schemes_source,
-        schemes_revision.as_deref(),
-        is_quiet,
-    )?;
+
+    let defaults = config.default_clone_options(is_offline)?;
+    if is_offline {
+        let not_installed: Vec<(String, String)> = config
+            .repo_sources()
+            .into_iter()
+            .filter(|(name, _)| !paths::repos_dir(data_path).join(name).is_dir())
+            .collect();
+
+        repo::ensure_offline(&not_installed, defaults.cache.as_ref())?;
+    }
+
+    let mut jobs: Vec<Job> = config
+        .items
+        .iter()
+        .flatten()
+        .map(|item| {
+            Job::new(&item.name, || {
+                install_item(
+                    &paths::item_repo_path(data_path, &item.name),
+                    item.name.as_str(),
+                    item.path.as_str(),
+                    item.revision.as_deref(),
+                    &item.clone_options(&defaults),
+                )
+            })
+        })
+        .collect();
+    jobs.extend(config.scheme_sources.iter().flatten().map(|source| {
+        Job::new(&source.name, || {
+            install_item(
+                &paths::item_repo_path(data_path, &source.name),
+                source.name.as_str(),
+                source.path.as_str(),
+                source.revision.as_deref(),
+                &defaults,
+            )
+        })
+    }));
+    jobs.push(Job::new(SCHEMES_REPO_NAME, || {
+        install_schemes_repo(
+            &schemes_repo_path,
+            &schemes_source,
+            schemes_revision.as_deref(),
+            &defaults,
+        )
+    }));
+
+    jobs::run_jobs("install", &jobs, max_jobs, is_quiet)?;
+
+    lockfile::pin_new_repos(config_path, &config, data_path)?;
+
+    if let Some(derived_schemes) = config.derived_schemes.as_ref() {
+        derive_schemes(derived_schemes, data_path, is_quiet)?;
+    }
 
     Ok(())
 }
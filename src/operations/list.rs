@@ -1,12 +1,17 @@
 #![allow(clippy::suboptimal_flops)]
 use crate::{
-    constants::{CUSTOM_SCHEMES_DIR_NAME, REPO_NAME},
+    config::Config,
+    constants::{CUSTOM_SCHEMES_DIR_NAME, REPO_NAME, SCHEME_SOURCE_SEPARATOR},
     paths,
+    scheme_index::SchemeIndex,
     utils::{get_all_scheme_file_paths, get_all_scheme_names},
 };
 use anyhow::{anyhow, Context, Result};
 use io::Write;
-use rayon::{iter::ParallelIterator, slice::ParallelSlice};
+use rayon::{
+    iter::{IntoParallelIterator, ParallelIterator},
+    slice::ParallelSlice,
+};
 use serde::Serialize;
 use std::{
     collections::{BTreeMap, HashMap},
@@ -21,11 +26,30 @@ use tinted_builder_rust::operation_build::utils::SchemeFile;
 /// Lists available color schemes
 ///
 /// Lists colorschemes file which is updated via scripts/install by getting a list of schemes
-/// available in <https://github.com/tinted-theming/schemes>
-pub fn list(data_path: &Path, is_custom: bool, is_json: bool) -> Result<()> {
+/// available in <https://github.com/tinted-theming/schemes>. With `[[scheme-sources]]`
+/// configured, their schemes are listed too, with shadowed ones as `<source>:<id>`.
+pub fn list(config_path: &Path, data_path: &Path, is_custom: bool, is_json: bool) -> Result<()> {
     let schemes_dir_path = schemes_dir_path(data_path, is_custom)?;
 
     let stdout = io::stdout();
+    let config = Config::read(config_path)?;
+    if !is_custom && config.scheme_sources.is_some() {
+        let index = SchemeIndex::for_scheme_sources(&config, data_path);
+        let mut handle = stdout.lock();
+        if is_json {
+            let json = serde_json::to_string(&scheme_index_entries(&index))?;
+            let _ = writeln!(handle, "{json}");
+            return Ok(());
+        }
+
+        for name in index.names() {
+            if writeln!(handle, "{name}").is_err() {
+                break;
+            }
+        }
+        return Ok(());
+    }
+
     if is_json {
         let json = scheme_entries_json(&schemes_dir_path)?;
         let mut handle = stdout.lock();
@@ -63,6 +87,23 @@ pub fn schemes_dir_path(data_path: &Path, is_custom: bool) -> Result<std::path::
     }
 }
 
+/// An entry for every name in `index`, in order. A shadowed scheme's `id` is
+/// its `<source>:<id>` name.
+pub fn scheme_index_entries(index: &SchemeIndex) -> Vec<SchemeEntry> {
+    index
+        .names()
+        .into_par_iter()
+        .filter_map(|name| {
+            let scheme = index.get(&name)?.get_scheme().ok()?;
+            let mut entry = SchemeEntry::from_scheme(&scheme);
+            if name.contains(SCHEME_SOURCE_SEPARATOR) {
+                entry.id = name;
+            }
+            Some(entry)
+        })
+        .collect()
+}
+
 pub fn scheme_entries_json(schemes_dir_path: &Path) -> Result<String> {
     let scheme_files = get_all_scheme_file_paths(schemes_dir_path, None)?;
     let entries = scheme_entries(scheme_files)?;
@@ -72,14 +113,14 @@ pub fn scheme_entries_json(schemes_dir_path: &Path) -> Result<String> {
 
 #[derive(Clone, Serialize)]
 pub struct SchemeEntry {
-    id: String,
-    name: String,
-    author: String,
-    system: SchemeSystem,
-    variant: SchemeVariant,
-    slug: String,
-    palette: BTreeMap<String, ColorOut>,
-    lightness: Option<Lightness>,
+    pub(crate) id: String,
+    pub(crate) name: String,
+    pub(crate) author: String,
+    pub(crate) system: SchemeSystem,
+    pub(crate) variant: SchemeVariant,
+    pub(crate) slug: String,
+    pub(crate) palette: BTreeMap<String, ColorOut>,
+    pub(crate) lightness: Option<Lightness>,
     #[serde(skip_serializing_if = "Option::is_none")]
     ui: Option<BTreeMap<String, ColorOut>>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -87,17 +128,17 @@ pub struct SchemeEntry {
 }
 
 #[derive(Clone, Serialize)]
-struct ColorOut {
-    hex_str: String,
+pub struct ColorOut {
+    pub hex_str: String,
     pub hex: (String, String, String),
     pub rgb: (u8, u8, u8),
     pub dec: (f32, f32, f32),
 }
 
 #[derive(Clone, Serialize)]
-struct Lightness {
-    foreground: f32,
-    background: f32,
+pub struct Lightness {
+    pub foreground: f32,
+    pub background: f32,
 }
 
 impl SchemeEntry {
@@ -324,7 +365,7 @@ impl Lightness {
     }
 }
 
-fn scheme_entries(scheme_files: HashMap<String, SchemeFile>) -> Result<Vec<SchemeEntry>> {
+pub fn scheme_entries(scheme_files: HashMap<String, SchemeFile>) -> Result<Vec<SchemeEntry>> {
     let mut keys: Vec<String> = scheme_files.keys().cloned().collect();
     // Create a thread-safe HashMap to collect results
     let mutex = Arc::new(Mutex::new(HashMap::new()));
@@ -0,0 +1,177 @@
+use crate::config::Config;
+use crate::constants::{DEFAULT_REVISION, REPO_NAME, SCHEMES_REPO_NAME};
+use crate::paths;
+use crate::repo::{self, NetworkOptions};
+use anyhow::{anyhow, Context, Result};
+use serde::Serialize;
+use std::path::Path;
+
+/// Where one configured repository stands relative to its configured revision.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "kebab-case")]
+struct RepoReport {
+    name: String,
+    source: String,
+    /// `None` for local-path sources, which have no revision.
+    revision: Option<String>,
+    installed: bool,
+    current: Option<String>,
+    target: Option<String>,
+    behind: u64,
+    dirty: bool,
+    outdated: bool,
+    /// Why the repository could not be checked, e.g. an unreachable remote.
+    error: Option<String>,
+}
+
+/// Reports, for every item and the schemes repository, whether `tinty update`
+/// would change anything, without changing anything itself. Each Git
+/// repository fetches its revision into a temporary remote and is compared
+/// with its checked-out commit.
+///
+/// A repository that can't be checked is reported as such and the others are
+/// still checked. Fails after printing the report when a repository is behind,
+/// not installed or could not be checked, so scheduled jobs can act on the
+/// exit status.
+pub fn outdated(config_path: &Path, data_path: &Path, is_json: bool) -> Result<()> {
+    let config = Config::read(config_path)?;
+    let (schemes_source, schemes_revision) = config.schemes_source();
+
+    let mut reports = Vec::new();
+    for item in config.items.iter().flatten() {
+        reports.push(check_repo(
+            &item.name,
+            &item.path,
+            &paths::item_repo_path(data_path, &item.name),
+            item.revision.as_deref(),
+            &item.network_options(config.network_options()),
+        ));
+    }
+    for source in config.scheme_sources.iter().flatten() {
+        reports.push(check_repo(
+            &source.name,
+            &source.path,
+            &paths::item_repo_path(data_path, &source.name),
+            source.revision.as_deref(),
+            &config.network_options(),
+        ));
+    }
+    reports.push(check_repo(
+        SCHEMES_REPO_NAME,
+        &schemes_source,
+        &paths::schemes_repo_path(data_path),
+        schemes_revision.as_deref(),
+        &config.network_options(),
+    ));
+
+    if is_json {
+        println!("{}", serde_json::to_string(&reports)?);
+    } else {
+        for report in &reports {
+            println!("{}", describe(report));
+        }
+    }
+
+    let failed_count = reports
+        .iter()
+        .filter(|report| report.error.is_some())
+        .count();
+    let outdated_count = reports.iter().filter(|report| report.outdated).count();
+    match (failed_count, outdated_count) {
+        (0, 0) => Ok(()),
+        (0, _) => Err(anyhow!(
+            "{outdated_count} of {} repositories are out of date. Run `{REPO_NAME} sync` to bring them up to date",
+            reports.len()
+        )),
+        (_, 0) => Err(anyhow!(
+            "{failed_count} of {} repositories could not be checked",
+            reports.len()
+        )),
+        (_, _) => Err(anyhow!(
+            "{failed_count} of {} repositories could not be checked and {outdated_count} are out of date. Run `{REPO_NAME} sync` to bring them up to date",
+            reports.len()
+        )),
+    }
+}
+
+fn check_repo(
+    name: &str,
+    source: &str,
+    repo_path: &Path,
+    revision: Option<&str>,
+    network: &NetworkOptions,
+) -> RepoReport {
+    let is_local = repo::is_local_path(source);
+    let revision = (!is_local).then(|| revision.unwrap_or(DEFAULT_REVISION).to_string());
+    let mut report = RepoReport {
+        name: name.to_string(),
+        source: source.to_string(),
+        revision,
+        installed: repo_path.is_dir(),
+        current: None,
+        target: None,
+        behind: 0,
+        dirty: false,
+        outdated: true,
+        error: None,
+    };
+
+    if !report.installed {
+        return report;
+    }
+
+    let checked = repo::check(repo_path, source, report.revision.as_deref(), network)
+        .with_context(|| format!("Error checking {name} for updates from {source}"))
+        .and_then(|status| Ok((status, repo::is_clean(repo_path, source)?)));
+    match checked {
+        Ok((status, is_clean)) => {
+            report.dirty = !is_clean;
+            report.outdated = status.current != status.target;
+            report.current = status.current;
+            report.target = status.target;
+            report.behind = status.behind;
+        }
+        Err(err) => {
+            report.outdated = false;
+            report.error = Some(format!("{err:#}"));
+        }
+    }
+
+    report
+}
+
+fn describe(report: &RepoReport) -> String {
+    let name = &report.name;
+    if let Some(error) = &report.error {
+        return format!("{name}: could not be checked: {error}");
+    }
+
+    let line = if !report.installed {
+        format!("{name}: not installed (run `{REPO_NAME} install`)")
+    } else if report.revision.is_none() {
+        format!("{name}: local path, nothing to check")
+    } else if report.outdated {
+        format!(
+            "{name}: {} commit{} behind ({} -> {})",
+            report.behind,
+            if report.behind == 1 { "" } else { "s" },
+            short_sha(report.current.as_deref()),
+            short_sha(report.target.as_deref())
+        )
+    } else {
+        format!(
+            "{name}: up to date ({})",
+            short_sha(report.current.as_deref())
+        )
+    };
+
+    if report.dirty {
+        format!("{line}, uncommitted changes")
+    } else {
+        line
+    }
+}
+
+fn short_sha(sha: Option<&str>) -> &str {
+    sha.map_or("none", |sha| sha.get(..7).unwrap_or(sha))
+}
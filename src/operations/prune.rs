@@ -0,0 +1,351 @@
+use crate::config::Config;
+use crate::constants::{ARTIFACTS_DIR, REPO_NAME, SCHEMES_REPO_NAME};
+use crate::paths;
+use crate::repo;
+use crate::scheme_index::SchemeIndex;
+use crate::utils::create_theme_filename_without_extension;
+use anyhow::{anyhow, Context, Result};
+use std::collections::BTreeSet;
+use std::fmt;
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use tinted_builder::SchemeSystem;
+
+/// Suffix `apply` gives every theme file it copies into `artifacts/`.
+const ARTIFACT_STEM_SUFFIX: &str = "-file";
+
+/// Something `prune` or `uninstall` removes.
+enum Removal {
+    /// A clone, or the symlink to a local-path source, in `repos/`.
+    Repo(PathBuf),
+    /// A theme file `apply` copied into `artifacts/`.
+    Artifact(PathBuf),
+    /// A symlink `apply` made in the data directory to an artifact.
+    Symlink(PathBuf),
+    /// A theme an item built for a custom scheme that no longer exists.
+    BuildOutput(PathBuf),
+}
+
+impl Removal {
+    const fn path(&self) -> &PathBuf {
+        match self {
+            Self::Repo(path)
+            | Self::Artifact(path)
+            | Self::Symlink(path)
+            | Self::BuildOutput(path) => path,
+        }
+    }
+
+    fn remove(&self) -> Result<()> {
+        let path = self.path();
+        let is_dir = fs::symlink_metadata(path).is_ok_and(|metadata| metadata.is_dir());
+
+        // A symlinked local-path repo is unlinked; the directory it points at
+        // belongs to the user.
+        if is_dir {
+            fs::remove_dir_all(path)
+        } else {
+            fs::remove_file(path)
+        }
+        .with_context(|| format!("Failed to remove {}", path.display()))
+    }
+}
+
+impl fmt::Display for Removal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            Self::Repo(_) => "repository",
+            Self::Artifact(_) => "artifact",
+            Self::Symlink(_) => "symlink",
+            Self::BuildOutput(_) => "build output",
+        };
+
+        write!(f, "{kind:<12} {}", self.path().display())
+    }
+}
+
+/// Names of directories in `repos/` that are neither the schemes repository
+/// nor a configured item or scheme source, sorted.
+pub fn orphaned_repos(config: &Config, data_path: &Path) -> Result<Vec<String>> {
+    let repos_dir = paths::repos_dir(data_path);
+    if !repos_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let configured: BTreeSet<&str> = config
+        .all_items()
+        .map(|item| item.name.as_str())
+        .chain(
+            config
+                .scheme_sources
+                .iter()
+                .flatten()
+                .map(|source| source.name.as_str()),
+        )
+        .chain(std::iter::once(SCHEMES_REPO_NAME))
+        .collect();
+    let mut orphaned: Vec<String> = fs::read_dir(&repos_dir)?
+        .filter_map(Result::ok)
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .filter(|name| !configured.contains(name.as_str()))
+        .collect();
+    orphaned.sort();
+
+    Ok(orphaned)
+}
+
+/// Removes what removed items left behind: their repositories in `repos/`,
+/// their theme files in `artifacts/` and the symlinks to them in the data
+/// directory, and themes that installed items built for custom or
+/// `[[scheme-sources]]` schemes that no longer exist.
+pub fn prune(config_path: &Path, data_path: &Path, is_dry_run: bool, is_yes: bool) -> Result<()> {
+    let config = Config::read(config_path)?;
+    let configured_artifacts: BTreeSet<String> = config
+        .all_items()
+        .map(create_theme_filename_without_extension)
+        .collect();
+
+    let mut removals: Vec<Removal> = orphaned_repos(&config, data_path)?
+        .into_iter()
+        .map(|name| Removal::Repo(paths::item_repo_path(data_path, &name)))
+        .collect();
+    removals.extend(artifact_removals(data_path, |stem| {
+        !configured_artifacts.contains(stem)
+    })?);
+    removals.extend(stale_symlinks(data_path, &removals)?);
+    removals.extend(stale_build_outputs(&config, data_path)?);
+
+    if removals.is_empty() {
+        println!("Nothing to prune");
+        return Ok(());
+    }
+
+    remove(&removals, is_dry_run, is_yes)
+}
+
+/// Removes one item's repository, its theme files in `artifacts/` and the
+/// symlinks to them. The item does not have to be in the config anymore.
+pub fn uninstall(
+    config_path: &Path,
+    data_path: &Path,
+    item_name: &str,
+    is_dry_run: bool,
+    is_yes: bool,
+) -> Result<()> {
+    if item_name == SCHEMES_REPO_NAME {
+        return Err(anyhow!(
+            "The schemes repository can't be uninstalled, it is needed by every other command"
+        ));
+    }
+
+    let config = Config::read(config_path)?;
+    let configured_item = config.all_items().find(|item| item.name == item_name);
+    let repo_path = paths::item_repo_path(data_path, item_name);
+
+    let mut removals = Vec::new();
+    if fs::symlink_metadata(&repo_path).is_ok() {
+        removals.push(Removal::Repo(repo_path));
+    }
+    let artifacts = if let Some(item) = configured_item {
+        let artifact = create_theme_filename_without_extension(item);
+        artifact_removals(data_path, |stem| stem == artifact)?
+    } else {
+        // Without its `themes-dir` the exact file name is unknown, so match by
+        // name among the artifacts no configured item owns.
+        let configured_artifacts: BTreeSet<String> = config
+            .all_items()
+            .map(create_theme_filename_without_extension)
+            .collect();
+        let prefix = format!("{item_name}-");
+        artifact_removals(data_path, |stem| {
+            stem.starts_with(&prefix) && !configured_artifacts.contains(stem)
+        })?
+    };
+    removals.extend(artifacts);
+    let symlinks = symlinks_to(data_path, &removals);
+    removals.extend(symlinks);
+
+    if removals.is_empty() {
+        return Err(anyhow!("{item_name} is not installed"));
+    }
+
+    remove(&removals, is_dry_run, is_yes)?;
+
+    if configured_item.is_some() && !is_dry_run {
+        println!("{item_name} is still in your config, so `{REPO_NAME} install` will install it again. Remove it from your config to keep it uninstalled");
+    }
+
+    Ok(())
+}
+
+/// Theme files in `artifacts/`, and in every profile's, whose stem (without
+/// extension) `is_stale`.
+fn artifact_removals(data_path: &Path, is_stale: impl Fn(&str) -> bool) -> Result<Vec<Removal>> {
+    let mut removals = Vec::new();
+    for artifacts_dir in paths::all_artifacts_dirs(data_path) {
+        if !artifacts_dir.is_dir() {
+            continue;
+        }
+
+        removals.extend(
+            fs::read_dir(&artifacts_dir)?
+                .filter_map(Result::ok)
+                .map(|entry| entry.path())
+                .filter(|path| path.is_file())
+                .filter(|path| {
+                    path.file_stem()
+                        .and_then(|stem| stem.to_str())
+                        .is_some_and(|stem| stem.ends_with(ARTIFACT_STEM_SUFFIX) && is_stale(stem))
+                })
+                .map(Removal::Artifact),
+        );
+    }
+    removals.sort_by(|a, b| a.path().cmp(b.path()));
+
+    Ok(removals)
+}
+
+/// Symlinks in the data directory to the artifacts in `removals`.
+fn symlinks_to(data_path: &Path, removals: &[Removal]) -> Vec<Removal> {
+    let mut symlinks = Vec::new();
+
+    for removal in removals {
+        let Removal::Artifact(artifact) = removal else {
+            continue;
+        };
+        let Some(file_name) = artifact.file_name() else {
+            continue;
+        };
+        let link = data_path.join(file_name);
+        let is_link_to_artifact = fs::read_link(&link).is_ok_and(|target| target == *artifact);
+
+        if is_link_to_artifact {
+            symlinks.push(Removal::Symlink(link));
+        }
+    }
+
+    symlinks
+}
+
+/// Symlinks in the data directory to the artifacts in `removals`, plus the
+/// ones `apply` made to an artifact that no longer exists. Other broken
+/// symlinks are left alone, since Tinty didn't create them.
+fn stale_symlinks(data_path: &Path, removals: &[Removal]) -> Result<Vec<Removal>> {
+    let mut symlinks = symlinks_to(data_path, removals);
+    if !data_path.is_dir() {
+        return Ok(symlinks);
+    }
+
+    for entry in fs::read_dir(data_path)?.filter_map(Result::ok) {
+        let path = entry.path();
+        let is_symlink = fs::symlink_metadata(&path).is_ok_and(|m| m.file_type().is_symlink());
+        let is_broken = fs::metadata(&path).is_err();
+
+        if is_symlink
+            && is_broken
+            && is_artifact_link(data_path, &path)
+            && !symlinks.iter().any(|s| s.path() == &path)
+        {
+            symlinks.push(Removal::Symlink(path));
+        }
+    }
+    symlinks.sort_by(|a, b| a.path().cmp(b.path()));
+
+    Ok(symlinks)
+}
+
+/// Whether `link` points at a file in an `artifacts/` directory of the data
+/// directory, as the links `apply` makes for backwards compatibility do.
+fn is_artifact_link(data_path: &Path, link: &Path) -> bool {
+    fs::read_link(link).is_ok_and(|target| {
+        target.starts_with(data_path)
+            && target
+                .parent()
+                .and_then(Path::file_name)
+                .is_some_and(|dir| dir == ARTIFACTS_DIR)
+    })
+}
+
+/// Untracked `<system>-<slug>.*` files in installed Git items that match no
+/// built-in, `[[scheme-sources]]` or custom scheme: themes built for a scheme
+/// that has since been deleted. Skipped when the schemes repository is not installed, since
+/// every scheme would then look unknown.
+fn stale_build_outputs(config: &Config, data_path: &Path) -> Result<Vec<Removal>> {
+    if !paths::schemes_repo_path(data_path).is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let index = SchemeIndex::for_config(config, data_path);
+    let mut removals = Vec::new();
+
+    for item in config.items.iter().flatten() {
+        let repo_path = paths::item_repo_path(data_path, &item.name);
+        if !repo_path.is_dir() {
+            continue;
+        }
+
+        for path in repo::untracked_files(&repo_path, &item.path)? {
+            let Some(stem) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            let is_theme = stem
+                .split_once('-')
+                .is_some_and(|(system, _)| SchemeSystem::from_str(system).is_ok());
+
+            if is_theme && !index.contains(stem) {
+                removals.push(Removal::BuildOutput(path));
+            }
+        }
+    }
+
+    Ok(removals)
+}
+
+/// Lists `removals` and removes them once confirmed, or only lists them for a
+/// dry run.
+fn remove(removals: &[Removal], is_dry_run: bool, is_yes: bool) -> Result<()> {
+    if is_dry_run {
+        println!("Would remove:");
+    } else {
+        println!("Will remove:");
+    }
+    for removal in removals {
+        println!("  {removal}");
+    }
+
+    if is_dry_run {
+        return Ok(());
+    }
+
+    if !is_yes && !confirm(&format!("Remove {} paths?", removals.len()))? {
+        println!("Nothing removed");
+        return Ok(());
+    }
+
+    for removal in removals {
+        removal.remove()?;
+    }
+    println!("Removed {} paths", removals.len());
+
+    Ok(())
+}
+
+/// Asks a yes/no question on the terminal. Fails when stdin is not a terminal,
+/// so scripts have to pass `--yes` explicitly.
+fn confirm(question: &str) -> Result<bool> {
+    let stdin = io::stdin();
+    if !stdin.is_terminal() {
+        return Err(anyhow!(
+            "Refusing to remove files without confirmation. Run with `--yes` to remove them or `--dry-run` to only list them"
+        ));
+    }
+
+    print!("{question} [y/N] ");
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    stdin.read_line(&mut answer)?;
+
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
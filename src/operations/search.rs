@@ -0,0 +1,208 @@
+use crate::{
+    color::Rgb,
+    config::Config,
+    operations::list::{scheme_entries, scheme_index_entries, schemes_dir_path, SchemeEntry},
+    scheme_index::SchemeIndex,
+    utils::get_all_scheme_file_paths,
+};
+use anyhow::{anyhow, Result};
+use std::{cmp::Ordering, io::Write, ops::RangeInclusive, path::Path};
+
+/// Default `--max-delta` for `--near-color` when none is given.
+pub const DEFAULT_MAX_DELTA: f32 = 10.0;
+
+/// Filters for `tinty search`. Every filter that is set must match; free-text
+/// terms and `near_colors` also decide the order of the results.
+#[derive(Debug, Default)]
+pub struct SearchQuery {
+    pub terms: Vec<String>,
+    pub variant: Option<String>,
+    pub system: Option<String>,
+    pub author: Option<String>,
+    pub bg_lightness: Option<RangeInclusive<f32>>,
+    pub near_colors: Vec<(String, Rgb)>,
+    pub max_delta: f32,
+}
+
+/// A scheme that matched a search, with what is needed to rank it.
+struct SearchMatch {
+    entry: SchemeEntry,
+    text_score: u32,
+    delta: f32,
+}
+
+/// Searches available schemes and prints the matching scheme IDs, best match
+/// first. With `[[scheme-sources]]`, a scheme another source shadows is
+/// printed as `<source>:<id>`, as in `list`.
+pub fn search(
+    config_path: &Path,
+    data_path: &Path,
+    is_custom: bool,
+    is_json: bool,
+    query: &SearchQuery,
+) -> Result<()> {
+    let schemes_dir_path = schemes_dir_path(data_path, is_custom)?;
+    let config = Config::read(config_path)?;
+    let entries = if !is_custom && config.scheme_sources.is_some() {
+        scheme_index_entries(&SchemeIndex::for_scheme_sources(&config, data_path))
+    } else {
+        scheme_entries(get_all_scheme_file_paths(&schemes_dir_path, None)?)?
+    };
+    let results = search_entries(entries, query);
+
+    let stdout = std::io::stdout();
+    let mut handle = stdout.lock();
+    if is_json {
+        let entries: Vec<&SchemeEntry> = results.iter().map(|result| &result.entry).collect();
+        let _ = writeln!(handle, "{}", serde_json::to_string(&entries)?);
+        return Ok(());
+    }
+
+    for result in results {
+        if writeln!(handle, "{}", result.entry.id).is_err() {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+fn search_entries(entries: Vec<SchemeEntry>, query: &SearchQuery) -> Vec<SearchMatch> {
+    let mut results: Vec<SearchMatch> = entries
+        .into_iter()
+        .filter_map(|entry| match_entry(entry, query))
+        .collect();
+
+    results.sort_by(|a, b| {
+        b.text_score
+            .cmp(&a.text_score)
+            .then_with(|| a.delta.partial_cmp(&b.delta).unwrap_or(Ordering::Equal))
+            .then_with(|| a.entry.id.cmp(&b.entry.id))
+    });
+
+    results
+}
+
+fn match_entry(entry: SchemeEntry, query: &SearchQuery) -> Option<SearchMatch> {
+    if let Some(variant) = &query.variant {
+        if !entry.variant.as_str().eq_ignore_ascii_case(variant) {
+            return None;
+        }
+    }
+    if let Some(system) = &query.system {
+        if !entry.system.as_str().eq_ignore_ascii_case(system) {
+            return None;
+        }
+    }
+    if let Some(author) = &query.author {
+        if !contains_ignore_case(&entry.author, author) {
+            return None;
+        }
+    }
+    if let Some(range) = &query.bg_lightness {
+        let background = entry.lightness.as_ref()?.background;
+        if !range.contains(&background) {
+            return None;
+        }
+    }
+
+    let mut text_score: u32 = 0;
+    for term in &query.terms {
+        text_score = text_score.saturating_add(text_score_for(&entry, term)?);
+    }
+
+    let mut delta = 0.0;
+    for (slot, target) in &query.near_colors {
+        let color = Rgb::from_hex(&entry.palette.get(slot)?.hex_str).ok()?;
+        let slot_delta = color.delta_e(*target);
+        if slot_delta > query.max_delta {
+            return None;
+        }
+        delta += slot_delta;
+    }
+
+    Some(SearchMatch {
+        entry,
+        text_score,
+        delta,
+    })
+}
+
+/// How well a single free-text term matches a scheme, or `None` when it does
+/// not match at all. Exact matches on the ID, slug or name rank highest, then
+/// prefixes, then substrings, then matches on the author only.
+fn text_score_for(entry: &SchemeEntry, term: &str) -> Option<u32> {
+    let term = term.to_lowercase();
+    let fields = [
+        entry.id.to_lowercase(),
+        entry.slug.to_lowercase(),
+        entry.name.to_lowercase(),
+    ];
+
+    if fields.contains(&term) {
+        Some(8)
+    } else if fields.iter().any(|field| field.starts_with(&term)) {
+        Some(4)
+    } else if fields.iter().any(|field| field.contains(&term)) {
+        Some(2)
+    } else if contains_ignore_case(&entry.author, &term) {
+        Some(1)
+    } else {
+        None
+    }
+}
+
+fn contains_ignore_case(haystack: &str, needle: &str) -> bool {
+    haystack.to_lowercase().contains(&needle.to_lowercase())
+}
+
+/// Parses a lightness range such as `5..15`, `5..` or `..15`. Bounds are
+/// inclusive and default to the ends of the `0..100` L* scale.
+pub fn parse_lightness_range(value: &str) -> Result<RangeInclusive<f32>> {
+    let (start, end) = value.split_once("..").ok_or_else(|| {
+        anyhow!("Invalid lightness range \"{value}\": expected <MIN>..<MAX>, e.g. 5..15")
+    })?;
+    let parse_bound = |bound: &str, default: f32| -> Result<f32> {
+        let bound = bound.trim_start_matches('=').trim();
+        if bound.is_empty() {
+            return Ok(default);
+        }
+        bound.parse::<f32>().map_err(|_| {
+            anyhow!("Invalid lightness range \"{value}\": \"{bound}\" is not a number")
+        })
+    };
+
+    Ok(parse_bound(start, 0.0)?..=parse_bound(end, 100.0)?)
+}
+
+/// Parses a `--near-color` value such as `base0D=#5e81ac`.
+pub fn parse_near_color(value: &str) -> Result<(String, Rgb)> {
+    let (slot, color) = value.split_once('=').ok_or_else(|| {
+        anyhow!("Invalid near color \"{value}\": expected <SLOT>=<COLOR>, e.g. base0D=#5e81ac")
+    })?;
+
+    Ok((slot.trim().to_string(), Rgb::from_hex(color)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_lightness_range, parse_near_color};
+
+    #[test]
+    fn parses_open_and_closed_lightness_ranges() {
+        assert_eq!(parse_lightness_range("5..15").unwrap(), 5.0..=15.0);
+        assert_eq!(parse_lightness_range("5..").unwrap(), 5.0..=100.0);
+        assert_eq!(parse_lightness_range("..=15").unwrap(), 0.0..=15.0);
+        assert!(parse_lightness_range("15").is_err());
+        assert!(parse_lightness_range("a..b").is_err());
+    }
+
+    #[test]
+    fn parses_near_color() {
+        let (slot, color) = parse_near_color("base0D=#5e81ac").unwrap();
+
+        assert_eq!(slot, "base0D");
+        assert_eq!(color.to_hex(), "#5e81ac");
+        assert!(parse_near_color("base0D").is_err());
+    }
+}
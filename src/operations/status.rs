@@ -0,0 +1,267 @@
+use crate::config::{Config, ConfigItem};
+use crate::constants::{
+    DEFAULT_REVISION, DEFAULT_SCHEME_SYSTEM, REPO_NAME, SCHEMES_REPO_NAME, SCHEME_SOURCE_SEPARATOR,
+    TEMPLATES_DIR,
+};
+use crate::operations::apply::find_theme_file;
+use crate::operations::current::get_current_scheme_slug;
+use crate::operations::prune::orphaned_repos;
+use crate::paths;
+use crate::repo;
+use crate::scheme_index::SchemeIndex;
+use anyhow::Result;
+use serde::Serialize;
+use std::fmt::Write as _;
+use std::path::Path;
+use std::str::FromStr;
+use tinted_builder::SchemeSystem;
+
+/// Everything `tinty status` reports about an installation.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "kebab-case")]
+struct Status {
+    current_scheme: Option<String>,
+    repos: Vec<RepoStatus>,
+    /// Directories in `repos/` that no configured repository uses.
+    stale_repos: Vec<String>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "kebab-case")]
+struct RepoStatus {
+    name: String,
+    source: String,
+    is_local_path: bool,
+    installed: bool,
+    /// The configured revision; `None` for local-path sources, which ignore it.
+    revision: Option<String>,
+    /// The commit checked out, for installed Git sources.
+    checked_out: Option<String>,
+    /// `None` when the repository is not installed.
+    dirty: Option<bool>,
+    allow_dirty_update: bool,
+    /// `None` for the schemes repository and scheme sources, which have no
+    /// themes directory.
+    themes_dir: Option<ThemesDirStatus>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "kebab-case")]
+struct ThemesDirStatus {
+    path: String,
+    exists: bool,
+    /// Whether the directory holds a theme for the current scheme, or, for a
+    /// scheme from a `[[scheme-sources]]` entry, whether the item has templates
+    /// to build one from. `None` when no scheme is applied, the item does not
+    /// support the scheme's system or the directory is missing.
+    has_current_theme: Option<bool>,
+}
+
+/// The applied scheme's ID, without a `<source>:` prefix, and whether it comes
+/// from a `[[scheme-sources]]` entry, whose themes `apply` builds from each
+/// item's templates.
+struct CurrentScheme<'a> {
+    id: &'a str,
+    is_from_source: bool,
+}
+
+/// Reports the state of the schemes repository and every item — what is
+/// installed, from where, at which commit and with what local changes — and the
+/// leftover repositories that are no longer configured. Nothing is fetched.
+pub fn status(
+    config_path: &Path,
+    data_path: &Path,
+    profile: Option<&str>,
+    is_json: bool,
+) -> Result<()> {
+    let config = Config::read_with_profile(config_path, profile)?;
+    let current_scheme =
+        Some(get_current_scheme_slug(data_path, profile)).filter(|id| !id.is_empty());
+    let scheme_index = SchemeIndex::for_config(&config, data_path);
+    let current = current_scheme.as_deref().map(|name| CurrentScheme {
+        id: name
+            .split_once(SCHEME_SOURCE_SEPARATOR)
+            .map_or(name, |(_, id)| id),
+        is_from_source: scheme_index
+            .source_of(name)
+            .is_some_and(|source| source != SCHEMES_REPO_NAME),
+    });
+    let (schemes_source, schemes_revision) = config.schemes_source();
+
+    let mut repos = vec![repo_status(
+        SCHEMES_REPO_NAME,
+        &schemes_source,
+        &paths::schemes_repo_path(data_path),
+        schemes_revision.as_deref(),
+        config.schemes.allow_dirty_update,
+    )?];
+    for source in config.scheme_sources.iter().flatten() {
+        repos.push(repo_status(
+            &source.name,
+            &source.path,
+            &paths::item_repo_path(data_path, &source.name),
+            source.revision.as_deref(),
+            false,
+        )?);
+    }
+    for item in config.items.iter().flatten() {
+        let repo_path = paths::item_repo_path(data_path, &item.name);
+        let mut status = repo_status(
+            &item.name,
+            &item.path,
+            &repo_path,
+            item.revision.as_deref(),
+            item.allow_dirty_update,
+        )?;
+        status.themes_dir = Some(themes_dir_status(item, &repo_path, current.as_ref())?);
+        repos.push(status);
+    }
+
+    let status = Status {
+        stale_repos: orphaned_repos(&config, data_path)?,
+        current_scheme,
+        repos,
+    };
+
+    if is_json {
+        println!("{}", serde_json::to_string(&status)?);
+    } else {
+        print!("{}", describe(&status, data_path));
+    }
+
+    Ok(())
+}
+
+fn repo_status(
+    name: &str,
+    source: &str,
+    repo_path: &Path,
+    revision: Option<&str>,
+    allow_dirty_update: bool,
+) -> Result<RepoStatus> {
+    let is_local_path = repo::is_local_path(source);
+    let installed = repo_path.is_dir();
+    let (checked_out, dirty) = if installed {
+        (
+            repo::head_commit(repo_path, source)?,
+            Some(!repo::is_clean(repo_path, source)?),
+        )
+    } else {
+        (None, None)
+    };
+
+    Ok(RepoStatus {
+        name: name.to_string(),
+        source: source.to_string(),
+        is_local_path,
+        installed,
+        revision: (!is_local_path).then(|| revision.unwrap_or(DEFAULT_REVISION).to_string()),
+        checked_out,
+        dirty,
+        allow_dirty_update,
+        themes_dir: None,
+    })
+}
+
+fn themes_dir_status(
+    item: &ConfigItem,
+    repo_path: &Path,
+    current_scheme: Option<&CurrentScheme>,
+) -> Result<ThemesDirStatus> {
+    let themes_path = repo_path.join(&item.themes_dir);
+    let exists = themes_path.is_dir();
+    let has_current_theme = match current_scheme {
+        Some(scheme) if exists && supports_scheme(item, scheme.id) => {
+            Some(if scheme.is_from_source {
+                repo_path.join(TEMPLATES_DIR).is_dir()
+            } else {
+                find_theme_file(&themes_path, item, scheme.id)?.is_some()
+            })
+        }
+        _ => None,
+    };
+
+    Ok(ThemesDirStatus {
+        path: item.themes_dir.clone(),
+        exists,
+        has_current_theme,
+    })
+}
+
+/// Whether `apply` would look for a theme for `scheme` in `item`, going by the
+/// system prefix of the scheme id.
+fn supports_scheme(item: &ConfigItem, scheme: &str) -> bool {
+    let system = scheme.split('-').next().unwrap_or(DEFAULT_SCHEME_SYSTEM);
+
+    SchemeSystem::from_str(system).is_ok_and(|system| {
+        item.supported_systems
+            .as_ref()
+            .is_some_and(|systems| systems.contains(&system))
+    })
+}
+
+fn describe(status: &Status, data_path: &Path) -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "Current scheme: {}",
+        status.current_scheme.as_deref().unwrap_or("none applied")
+    );
+
+    for repo in &status.repos {
+        let _ = writeln!(out, "\n{}", repo.name);
+        let kind = if repo::is_archive(&repo.source) {
+            "archive"
+        } else if repo.is_local_path {
+            "local path"
+        } else {
+            "git"
+        };
+        let _ = writeln!(out, "  source: {} ({kind})", repo.source);
+
+        if !repo.installed {
+            let _ = writeln!(out, "  installed: no (run `{REPO_NAME} install`)");
+        }
+        match (&repo.revision, &repo.checked_out) {
+            (Some(revision), Some(commit)) => {
+                let _ = writeln!(out, "  revision: {revision} (checked out {commit})");
+            }
+            (Some(revision), None) => {
+                let _ = writeln!(out, "  revision: {revision}");
+            }
+            (None, _) => {}
+        }
+        if let Some(dirty) = repo.dirty {
+            let changes = if dirty {
+                "uncommitted changes"
+            } else {
+                "clean"
+            };
+            let _ = writeln!(out, "  working tree: {changes}");
+        }
+        let _ = writeln!(out, "  allow-dirty-update: {}", repo.allow_dirty_update);
+
+        if let Some(themes_dir) = &repo.themes_dir {
+            let state = match (themes_dir.exists, themes_dir.has_current_theme) {
+                (false, _) => "missing",
+                (true, Some(true)) => "has a theme for the current scheme",
+                (true, Some(false)) => "no theme for the current scheme",
+                (true, None) => "exists",
+            };
+            let _ = writeln!(out, "  themes-dir: {} ({state})", themes_dir.path);
+        }
+    }
+
+    if !status.stale_repos.is_empty() {
+        let _ = writeln!(
+            out,
+            "\nNot in config (in {}):",
+            paths::repos_dir(data_path).display()
+        );
+        for name in &status.stale_repos {
+            let _ = writeln!(out, "  {name}");
+        }
+    }
+
+    out
+}
@@ -1,13 +1,71 @@
+use crate::config::Config;
+use crate::lockfile;
 use crate::operations::{install, update};
+use crate::repo;
 use anyhow::Result;
+use std::num::NonZeroUsize;
 use std::path::Path;
 
 /// Syncs all dependencies
 ///
-/// Syncs dependencies by doing an `operation::install` and then `operation::update`
-pub fn sync(config_path: &Path, data_path: &Path, is_quiet: bool) -> Result<()> {
-    install::install(config_path, data_path, is_quiet)?;
-    update::update(config_path, data_path, is_quiet)?;
+/// Syncs dependencies by doing an `operation::install` and then `operation::update`. With
+/// `is_locked`, every repository is checked out at the commit pinned in `tinty.lock` instead of its
+/// configured revision; the lockfile is checked before anything is installed. With `is_offline`,
+/// every source is checked for needing the network before anything is installed. Afterwards, the
+/// current scheme is re-applied if its theme changed and `reapply` (or the config's `reapply`
+/// default) asks for it.
+#[allow(clippy::too_many_arguments)]
+pub fn sync(
+    config_path: &Path,
+    data_path: &Path,
+    profile: Option<&str>,
+    is_quiet: bool,
+    is_locked: bool,
+    max_jobs: Option<NonZeroUsize>,
+    is_offline: bool,
+    reapply: Option<bool>,
+) -> Result<()> {
+    let config = Config::read(config_path)?;
 
-    Ok(())
+    if is_offline {
+        repo::ensure_offline(
+            &config.repo_sources(),
+            config.repo_cache(is_offline)?.as_ref(),
+        )?;
+    }
+
+    let changelogs = if is_locked {
+        let commits = lockfile::locked_commits(config_path, &config)?;
+
+        install::install(config_path, data_path, is_quiet, max_jobs, is_offline)?;
+        update::update_to_commits(
+            config_path,
+            data_path,
+            &commits,
+            is_quiet,
+            max_jobs,
+            is_offline,
+        )?
+    } else {
+        install::install(config_path, data_path, is_quiet, max_jobs, is_offline)?;
+        update::update(
+            config_path,
+            data_path,
+            is_quiet,
+            false,
+            false,
+            max_jobs,
+            is_offline,
+        )?
+    };
+
+    update::reapply_if_changed(
+        config_path,
+        data_path,
+        profile,
+        &changelogs,
+        reapply,
+        "sync",
+        is_quiet,
+    )
 }
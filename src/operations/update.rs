@@ -1,151 +1,297 @@
+use crate::changelog::{self, RepoChangelog};
 use crate::config::{ensure_schemes_path_not_circular, Config};
-use crate::constants::{DEFAULT_REVISION, REPO_NAME, SCHEMES_REPO_NAME};
+use crate::constants::{
+    DEFAULT_REVISION, LOCKFILE_NAME, REPO_NAME, SCHEMES_REPO_NAME, SCHEME_SOURCE_SEPARATOR,
+};
+use crate::jobs::{self, Job};
+use crate::lockfile;
+use crate::operations::apply::apply;
+use crate::operations::current::get_current_scheme_slug;
+use crate::operations::derive::derive_schemes;
 use crate::paths;
-use crate::repo::{self, UpdateStatus};
+use crate::repo::{self, CloneOptions, UpdateStatus};
+use crate::scheme_index::SchemeIndex;
 use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::num::NonZeroUsize;
 use std::path::Path;
-use url::Url;
 
+/// Updates an `[[items]]` (or `[schemes]`) source through its repository
+/// backend, returning the message to print. An archive source is unpacked
+/// again, like a Git source is pulled; a Git source's sparse set and clone
+/// depth are also brought in line with `options`.
+///
+/// A local-directory source is a live symlink into a directory the user manages
+/// themselves, so there is no remote to fetch and no revision to check out.
+/// Its backend only re-validates the symlink and never runs git against the
+/// user's tree (which would reset their HEAD and rewrite their `origin`), so
+/// the uncommitted-changes check below does not apply to it.
 fn update_item(
     item_name: &str,
     item_url: &str,
     item_path: &Path,
     revision: Option<&str>,
     allow_dirty: bool,
-    is_quiet: bool,
-) -> Result<()> {
-    if item_path.is_dir() {
-        let rev = revision.unwrap_or(DEFAULT_REVISION);
-        let is_clean = repo::is_clean(item_path)?;
+    options: &CloneOptions,
+) -> Result<String> {
+    if !item_path.is_dir() {
+        return Ok(format!(
+            "{item_name} not installed (run `{REPO_NAME} install`)"
+        ));
+    }
 
-        if is_clean {
-            repo::update(item_path, item_url, revision, false)
-                .with_context(|| format!("Error updating {item_name} to {item_url}@{rev}"))?;
+    if repo::is_local_dir(item_url) {
+        repo::update(item_path, item_url, revision, allow_dirty, options)
+            .with_context(|| format!("Error updating {item_name} from {item_url}"))?;
 
-            if !is_quiet {
-                println!("{item_name} up to date");
+        return Ok(format!(
+            "{item_name} — left as-is (local path, no remote to update)"
+        ));
+    }
+
+    let rev = revision.unwrap_or(DEFAULT_REVISION);
+    let is_clean = repo::is_clean(item_path, item_url)?;
+
+    if is_clean {
+        let status = repo::update(item_path, item_url, revision, false, options)
+            .with_context(|| format!("Error updating {item_name} to {item_url}@{rev}"))?;
+
+        if let UpdateStatus::SignatureRejected { commit, stderr } = status {
+            return Err(repo::signature_error(&commit, &stderr))
+                .with_context(|| format!("Error updating {item_name} to {item_url}@{rev}"));
+        }
+
+        Ok(format!("{item_name} up to date"))
+    } else if allow_dirty {
+        let status = repo::update(item_path, item_url, revision, true, options)
+            .with_context(|| format!("Error updating {item_name} to {item_url}@{rev}"))?;
+
+        match status {
+            UpdateStatus::Updated => {
+                Ok(format!("{item_name} up to date (local changes preserved)"))
             }
-        } else if allow_dirty {
-            let status = repo::update(item_path, item_url, revision, true)
-                .with_context(|| format!("Error updating {item_name} to {item_url}@{rev}"))?;
-
-            if !is_quiet {
-                match status {
-                    UpdateStatus::Updated => {
-                        println!("{item_name} up to date (local changes preserved)");
-                    }
-                    UpdateStatus::ConflictPreserved { stderr } => {
-                        print_conflict_message(item_name, &stderr);
-                    }
-                }
+            UpdateStatus::ConflictPreserved { stderr } => Ok(conflict_message(item_name, &stderr)),
+            UpdateStatus::SignatureRejected { commit, stderr } => {
+                Err(repo::signature_error(&commit, &stderr))
+                    .with_context(|| format!("Error updating {item_name} to {item_url}@{rev}"))
             }
-        } else if !is_quiet {
-            println!("{item_name} contains uncommitted changes, please commit or remove and then run `{REPO_NAME} update` again.");
         }
-    } else if !is_quiet {
-        println!("{item_name} not installed (run `{REPO_NAME} install`)");
+    } else {
+        Ok(format!("{item_name} contains uncommitted changes, please commit or remove and then run `{REPO_NAME} update` again."))
     }
-
-    Ok(())
 }
 
-/// Prints a human-facing explanation when an update was refused because it
-/// would have overwritten the user's uncommitted work. The working tree is
-/// left untouched, so we echo git's own message verbatim — it already names
-/// the offending files and how to proceed.
-fn print_conflict_message(item_name: &str, git_stderr: &str) {
-    println!("{item_name}: could not update — your local changes are preserved:");
+/// Explains that an update was refused because it would have overwritten the
+/// user's uncommitted work. The working tree is left untouched, so we echo
+/// git's own message verbatim — it already names the offending files and how
+/// to proceed.
+fn conflict_message(item_name: &str, git_stderr: &str) -> String {
+    let mut message = format!("{item_name}: could not update — your local changes are preserved:");
     for line in git_stderr.lines() {
-        println!("{line}");
+        message.push('\n');
+        message.push_str(line);
     }
+    message
 }
 
-/// Reports the state of a local-path dependency without touching it. A local
-/// path is a live symlink into a directory the user manages themselves, so
-/// there is no remote to fetch and no revision to check out — `update` must
-/// leave it entirely alone (running git against it would reset the user's HEAD
-/// and rewrite their `origin`). We only confirm the symlink is still in place,
-/// exactly what `install` guarantees.
-fn report_local_dir(name: &str, repo_path: &Path, is_quiet: bool) {
-    if is_quiet {
-        return;
+/// Updates local files
+///
+/// Updates the provided repositories in config file by doing a git pull, up to `max_jobs` at a
+/// time, then prints what changed in each repository that moved (see `changelog`), or only that
+/// as JSON with `is_json`. With `write_lock`, `tinty.lock` is rewritten to pin the commits that
+/// are now checked out. With `is_offline`, fails before updating anything if a repository would
+/// be fetched over the network. Returns the changelog, for `reapply_if_changed`.
+#[allow(clippy::fn_params_excessive_bools)]
+pub fn update(
+    config_path: &Path,
+    data_path: &Path,
+    is_quiet: bool,
+    is_json: bool,
+    write_lock: bool,
+    max_jobs: Option<NonZeroUsize>,
+    is_offline: bool,
+) -> Result<Vec<RepoChangelog>> {
+    let config = Config::read(config_path)?;
+
+    let changelogs = update_repos(
+        &config,
+        data_path,
+        &BTreeMap::new(),
+        is_quiet || is_json,
+        max_jobs,
+        is_offline,
+    )?;
+
+    if write_lock {
+        lockfile::write_checked_out(config_path, &config, data_path)?;
+
+        if !is_quiet && !is_json {
+            println!("{LOCKFILE_NAME} updated");
+        }
     }
-    if repo_path.exists() {
-        println!("{name} — left as-is (local path, no remote to update)");
-    } else {
-        println!("{name} not installed (run `{REPO_NAME} install`)");
+
+    if is_json {
+        println!("{}", serde_json::to_string(&changelogs)?);
+    } else if !is_quiet {
+        print!("{}", changelog::describe(&changelogs));
     }
+
+    Ok(changelogs)
 }
 
-/// Updates the built-in schemes repository from its configured source.
-///
-/// A Git URL source is pulled to its revision exactly like an item. A local-path
-/// source is a live symlink to the user's directory, so there is nothing to
-/// fetch and `revision` is irrelevant — we only confirm the symlink is in place.
-fn update_schemes_repo(
-    schemes_repo_path: &Path,
-    source: &str,
-    revision: Option<&str>,
-    allow_dirty: bool,
+/// Checks out the commits pinned in `tinty.lock` (keyed by repository name) instead of the
+/// configured revisions, and fails if any repository could not be moved to its pinned commit.
+pub fn update_to_commits(
+    config_path: &Path,
+    data_path: &Path,
+    commits: &BTreeMap<String, String>,
+    is_quiet: bool,
+    max_jobs: Option<NonZeroUsize>,
+    is_offline: bool,
+) -> Result<Vec<RepoChangelog>> {
+    let config = Config::read(config_path)?;
+
+    let changelogs = update_repos(&config, data_path, commits, is_quiet, max_jobs, is_offline)?;
+    if !is_quiet {
+        print!("{}", changelog::describe(&changelogs));
+    }
+    lockfile::ensure_checked_out(&config, data_path, commits)?;
+
+    Ok(changelogs)
+}
+
+/// Re-applies the current scheme when `changelogs` changed one of the item
+/// theme files it uses or its scheme file, so `artifacts/` does not stay stale
+/// until the next `apply`. Hooks run with `operation` as their `%o`.
+/// `reapply` (from `--reapply` or `--no-reapply`) overrides the config's
+/// `reapply` default.
+pub fn reapply_if_changed(
+    config_path: &Path,
+    data_path: &Path,
+    profile: Option<&str>,
+    changelogs: &[RepoChangelog],
+    reapply: Option<bool>,
+    operation: &str,
     is_quiet: bool,
 ) -> Result<()> {
-    if Url::parse(source).is_ok() {
-        update_item(
-            SCHEMES_REPO_NAME,
-            source,
-            schemes_repo_path,
-            revision,
-            allow_dirty,
-            is_quiet,
-        )
-    } else {
-        report_local_dir(SCHEMES_REPO_NAME, schemes_repo_path, is_quiet);
-        Ok(())
+    let config = Config::read_with_profile(config_path, profile)?;
+    let current_scheme = get_current_scheme_slug(data_path, profile);
+    if !reapply.unwrap_or(config.reapply) || current_scheme.is_empty() {
+        return Ok(());
+    }
+
+    let scheme_index = SchemeIndex::for_config(&config, data_path);
+    let scheme_id = current_scheme
+        .split_once(SCHEME_SOURCE_SEPARATOR)
+        .map_or(current_scheme.as_str(), |(_, id)| id);
+    let scheme_source = scheme_index.source_of(&current_scheme);
+    if !changelog::touches_scheme(changelogs, &config, scheme_id, scheme_source) {
+        return Ok(());
     }
+
+    if !is_quiet {
+        println!("Re-applying {current_scheme}, its theme changed");
+    }
+
+    apply(
+        config_path,
+        data_path,
+        profile,
+        &current_scheme,
+        is_quiet,
+        Some(operation),
+    )
 }
 
-/// Updates local files
-///
-/// Updates the provided repositories in config file by doing a git pull
-pub fn update(config_path: &Path, data_path: &Path, is_quiet: bool) -> Result<()> {
-    let config = Config::read(config_path)?;
+/// Updates every configured repository, up to `max_jobs` at a time, using the commit in `commits`
+/// instead of the configured revision for the repositories it names. Returns what changed in each
+/// repository that moved.
+fn update_repos(
+    config: &Config,
+    data_path: &Path,
+    commits: &BTreeMap<String, String>,
+    is_quiet: bool,
+    max_jobs: Option<NonZeroUsize>,
+    is_offline: bool,
+) -> Result<Vec<RepoChangelog>> {
     // The built-in schemes repo has no `[[items]]` entry, so its leniency is
     // configured separately under `[schemes]`.
     let schemes_allow_dirty = config.schemes.allow_dirty_update;
     let (schemes_source, schemes_revision) = config.schemes_source();
-    let items = config.items.unwrap_or_default();
+    let schemes_repo_path = paths::schemes_repo_path(data_path);
+    let schemes_revision = commits
+        .get(SCHEMES_REPO_NAME)
+        .map(String::as_str)
+        .or(schemes_revision.as_deref());
 
-    for item in items {
-        let item_path = paths::item_repo_path(data_path, &item.name);
+    ensure_schemes_path_not_circular(&schemes_source, &schemes_repo_path)?;
 
-        // A local-path item is a symlink into a directory the user owns and
-        // edits directly (its own branches, its own `origin`). There is nothing
-        // to fetch or check out, so mirror `install`'s URL-vs-path dispatch and
-        // leave it untouched rather than running git against the user's tree.
-        if Url::parse(item.path.as_str()).is_ok() {
-            update_item(
-                item.name.as_str(),
-                item.path.as_str(),
-                &item_path,
-                item.revision.as_deref(),
-                item.allow_dirty_update,
-                is_quiet,
-            )?;
-        } else {
-            report_local_dir(item.name.as_str(), &item_path, is_quiet);
-        }
+    let defaults = config.default_clone_options(is_offline)?;
+    if is_offline {
+        repo::ensure_offline(&config.repo_sources(), defaults.cache.as_ref())?;
     }
 
-    let schemes_repo_path = paths::schemes_repo_path(data_path);
+    let before = changelog::heads(config, data_path);
 
-    ensure_schemes_path_not_circular(&schemes_source, &schemes_repo_path)?;
-    update_schemes_repo(
-        &schemes_repo_path,
-        &schemes_source,
-        schemes_revision.as_deref(),
-        schemes_allow_dirty,
-        is_quiet,
-    )?;
+    let mut jobs: Vec<Job> = config
+        .items
+        .iter()
+        .flatten()
+        .map(|item| {
+            Job::new(&item.name, || {
+                let revision = commits
+                    .get(&item.name)
+                    .map(String::as_str)
+                    .or(item.revision.as_deref());
+
+                update_item(
+                    item.name.as_str(),
+                    item.path.as_str(),
+                    &paths::item_repo_path(data_path, &item.name),
+                    revision,
+                    item.allow_dirty_update,
+                    &item.clone_options(&defaults),
+                )
+            })
+        })
+        .collect();
+    jobs.extend(config.scheme_sources.iter().flatten().map(|source| {
+        Job::new(&source.name, || {
+            let revision = commits
+                .get(&source.name)
+                .map(String::as_str)
+                .or(source.revision.as_deref());
+
+            update_item(
+                source.name.as_str(),
+                source.path.as_str(),
+                &paths::item_repo_path(data_path, &source.name),
+                revision,
+                false,
+                &defaults,
+            )
+        })
+    }));
+    // Exactly like an item: a Git URL source is pulled to its revision, and
+    // for a local-path source only the symlink is re-validated.
+    jobs.push(Job::new(SCHEMES_REPO_NAME, || {
+        update_item(
+            SCHEMES_REPO_NAME,
+            &schemes_source,
+            &schemes_repo_path,
+            schemes_revision,
+            schemes_allow_dirty,
+            &defaults,
+        )
+    }));
+
+    jobs::run_jobs("update", &jobs, max_jobs, is_quiet)?;
+
+    // Re-derive after the schemes repo moved so derived schemes pick up
+    // upstream changes to their base schemes.
+    if let Some(derived_schemes) = config.derived_schemes.as_ref() {
+        derive_schemes(derived_schemes, data_path, is_quiet)?;
+    }
 
-    Ok(())
+    Ok(changelog::changelog(config, data_path, &before))
 }
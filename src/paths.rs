@@ -9,7 +9,8 @@
 //! directory happens once at startup in `main`); they do no `~` expansion of
 //! their own.
 
-use crate::constants::{REPO_DIR, SCHEMES_REPO_NAME};
+use crate::constants::{ARTIFACTS_DIR, PROFILES_DIR, REPO_DIR, SCHEMES_REPO_NAME};
+use std::fs;
 use std::path::{Path, PathBuf};
 
 /// The directory holding every installed repository: `<data_dir>/repos`.
@@ -27,3 +28,35 @@ pub fn item_repo_path(data_path: &Path, item_name: &str) -> PathBuf {
 pub fn schemes_repo_path(data_path: &Path) -> PathBuf {
     repos_dir(data_path).join(SCHEMES_REPO_NAME)
 }
+
+/// The directory `apply` writes themes and the current scheme to:
+/// `<data_dir>/artifacts`, or `<data_dir>/profiles/<name>/artifacts` for
+/// `profile`, so every profile keeps its own current scheme.
+pub fn artifacts_dir(data_path: &Path, profile: Option<&str>) -> PathBuf {
+    profile.map_or_else(
+        || data_path.join(ARTIFACTS_DIR),
+        |profile| {
+            data_path
+                .join(PROFILES_DIR)
+                .join(profile)
+                .join(ARTIFACTS_DIR)
+        },
+    )
+}
+
+/// The artifacts directory without a profile, then that of every profile that
+/// has one, whether or not the profile is still configured.
+pub fn all_artifacts_dirs(data_path: &Path) -> Vec<PathBuf> {
+    let mut profile_dirs: Vec<PathBuf> = fs::read_dir(data_path.join(PROFILES_DIR))
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .map(|entry| entry.path().join(ARTIFACTS_DIR))
+        .filter(|dir| dir.is_dir())
+        .collect();
+    profile_dirs.sort();
+
+    std::iter::once(artifacts_dir(data_path, None))
+        .chain(profile_dirs)
+        .collect()
+}
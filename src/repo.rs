@@ -1,7 +1,13 @@
-use anyhow::Result;
-use std::path::Path;
+use anyhow::{anyhow, Result};
+use std::fmt::{self, Write as _};
+use std::num::NonZeroU32;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use url::Url;
 
+pub mod archive;
 pub mod git_shell;
+pub mod local_path;
 
 /// Outcome of an `update` that was allowed to run against a dirty working tree.
 #[derive(Debug, PartialEq, Eq)]
@@ -13,63 +19,383 @@ pub enum UpdateStatus {
     /// The working tree was left exactly as it was; `stderr` is git's own
     /// explanation, which names the offending files and how to proceed.
     ConflictPreserved { stderr: String },
+    /// The update was refused because `commit` has no good signature from an
+    /// allowed key (see [`CloneOptions::verify_signatures`]). Nothing was
+    /// checked out; `stderr` is git's explanation, empty for an unsigned
+    /// commit.
+    SignatureRejected { commit: String, stderr: String },
 }
 
+/// How the local copy of a repository compares to its configured revision, as
+/// reported by [`RepositoryBackend::check`].
+#[derive(Debug, PartialEq, Eq)]
+pub struct RevisionStatus {
+    /// The commit checked out locally, if the backend pins commits.
+    pub current: Option<String>,
+    /// The commit the configured revision resolves to upstream.
+    pub target: Option<String>,
+    /// How many commits `current` is behind `target`.
+    pub behind: u64,
+}
+
+/// How a file differs between two commits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FileChange {
+    Added,
+    Removed,
+    Modified,
+}
+
+/// What changed between two commits of a repository, as reported by
+/// [`RepositoryBackend::changes`].
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct RepoChanges {
+    /// The subjects of the commits reachable from the newer commit but not the
+    /// older one, newest first.
+    pub subjects: Vec<String>,
+    /// Every file that differs, relative to the repository root. A rename is
+    /// a removal plus an addition.
+    pub files: Vec<(FileChange, String)>,
+}
+
+/// How much of a Git source to fetch and check out, and which commits may be
+/// checked out. Backends without history or a working tree of their own
+/// ignore it.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CloneOptions {
+    /// Fetch only this many commits of history, keeping the clone shallow on
+    /// every update. `None` fetches full history.
+    pub depth: Option<NonZeroU32>,
+    /// Check out only these directories (plus files at the repository root)
+    /// in a sparse, blob-filtered clone. `None` checks out everything.
+    pub sparse_paths: Option<Vec<String>>,
+    /// Refuse to check out a commit without a good signature from an allowed
+    /// key.
+    pub verify_signatures: bool,
+    /// An SSH allowed signers file or a GPG home directory with the allowed
+    /// keys. `None` leaves it to the user's git and GPG configuration.
+    pub allowed_signers: Option<PathBuf>,
+    pub network: NetworkOptions,
+    /// Clone and update a Git URL through a mirror in this cache, instead of
+    /// fetching it from the URL directly. `None` uses no cache.
+    pub cache: Option<RepoCache>,
+}
+
+/// A directory of bare mirrors, one per Git URL, shared by every data
+/// directory configured to use it. A clone made from a mirror is a local
+/// clone, so installing a repository another data directory already fetched
+/// needs no network.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepoCache {
+    pub dir: PathBuf,
+    /// Fetch into a mirror before cloning or updating from it. `false` for
+    /// `--offline`, which uses the mirrors as they are.
+    pub refresh: bool,
+}
+
+impl RepoCache {
+    /// The mirror of `url` in the cache: `url` with every character other
+    /// than an ASCII letter, digit, `.` or `-` escaped as `_XX`, so distinct
+    /// URLs never share a mirror.
+    pub fn mirror_path(&self, url: &str) -> PathBuf {
+        let mut name = String::with_capacity(url.len());
+        for byte in url.bytes() {
+            if byte.is_ascii_alphanumeric() || byte == b'.' || byte == b'-' {
+                name.push(char::from(byte));
+            } else {
+                let _ = write!(name, "_{byte:02X}");
+            }
+        }
+
+        self.dir.join(format!("{name}.git"))
+    }
+
+    /// Whether `source` is a Git URL that can go through the cache. Bundles
+    /// are files already, so they never do.
+    pub fn can_cache(source: &str) -> bool {
+        !is_local_path(source) && !source.ends_with(git_shell::BUNDLE_EXTENSION)
+    }
+
+    /// Whether `source` is mirrored in the cache already, so it can be
+    /// installed or updated without the network.
+    pub fn has_mirror(&self, source: &str) -> bool {
+        Self::can_cache(source) && self.mirror_path(source).is_dir()
+    }
+}
+
+/// How long one clone or fetch may run, and how often one that failed for a
+/// transient reason (see [`GitErrorKind::is_transient`]) is tried again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NetworkOptions {
+    /// `None` waits however long git takes.
+    pub timeout: Option<Duration>,
+    pub retries: u32,
+}
+
+impl Default for NetworkOptions {
+    fn default() -> Self {
+        Self {
+            timeout: Some(Duration::from_secs(DEFAULT_GIT_TIMEOUT_SECS)),
+            retries: DEFAULT_GIT_RETRIES,
+        }
+    }
+}
+
+/// Default for `git-timeout`, in seconds.
+pub const DEFAULT_GIT_TIMEOUT_SECS: u64 = 300;
+/// Default for `git-retries`.
+pub const DEFAULT_GIT_RETRIES: u32 = 2;
+
+/// Why a clone or fetch failed, as far as git's output tells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GitErrorKind {
+    /// The remote refused the credentials, or asked for some.
+    Auth,
+    /// There is no repository at the URL.
+    NotFound,
+    /// The remote could not be reached, or the connection dropped.
+    Network,
+    /// The repository has no such branch, tag or commit.
+    RevisionMissing,
+    /// git ran longer than the configured `git-timeout`.
+    Timeout,
+    Other,
+}
+
+impl GitErrorKind {
+    /// Whether trying again later might succeed.
+    pub const fn is_transient(self) -> bool {
+        matches!(self, Self::Network | Self::Timeout)
+    }
+
+    const fn hint(self) -> &'static str {
+        match self {
+            Self::Auth => "Check the credentials or SSH key git uses for this host, or that the repository is public",
+            Self::NotFound => "Check the `path` in config.toml, and that you have access to the repository",
+            Self::Network => "Check the network connection, or use --offline with local sources",
+            Self::RevisionMissing => "Check the `revision` in config.toml",
+            Self::Timeout => "Raise `git-timeout` in config.toml, or set it to 0 to never time out",
+            Self::Other => "",
+        }
+    }
+}
+
+impl fmt::Display for GitErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            Self::Auth => "authentication failed",
+            Self::NotFound => "repository not found",
+            Self::Network => "network error",
+            Self::RevisionMissing => "revision not found",
+            Self::Timeout => "timed out",
+            Self::Other => "git failed",
+        };
+        write!(f, "{text}")
+    }
+}
+
+/// A failed clone or fetch. `action` says what tinty was doing, e.g.
+/// `clone https://example.com/repo`, and `stderr` is git's own explanation.
+#[derive(Debug)]
+pub struct GitError {
+    pub kind: GitErrorKind,
+    pub action: String,
+    pub stderr: String,
+}
+
+impl fmt::Display for GitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Failed to {}: {}", self.action, self.kind)?;
+        let hint = self.kind.hint();
+        if !hint.is_empty() {
+            write!(f, ". {hint}")?;
+        }
+        if !self.stderr.is_empty() {
+            write!(f, "\n{}", self.stderr)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for GitError {}
+
 /// High-level repository operations tinty performs against a `[[items]]` entry:
 /// fetching it onto disk for the first time (`install`), bringing it up to a
 /// configured revision (`update`), and checking whether the local copy has
 /// uncommitted changes (`is_clean`).
 ///
 /// Implementations may shell out to the `git` binary, use a Rust-native git
-/// library, or eventually back the operations with something other than git
-/// (a local-path symlink backend, for example). Callers should not depend on
+/// library, or back the operations with something other than git (the
+/// local-path symlink backend, for example). Callers should not depend on
 /// which implementation runs.
 pub trait RepositoryBackend {
-    fn install(&self, url: &str, target: &Path, revision: Option<&str>) -> Result<()>;
+    fn install(
+        &self,
+        url: &str,
+        target: &Path,
+        revision: Option<&str>,
+        options: &CloneOptions,
+    ) -> Result<()>;
     /// Bring the local copy at `target` to `revision`. When `allow_dirty` is
     /// `false` the caller guarantees a clean working tree. When `true` the
     /// update may run against uncommitted changes: non-overlapping edits are
     /// carried forward and a would-be-overwrite is reported via
-    /// [`UpdateStatus::ConflictPreserved`] rather than an error.
+    /// [`UpdateStatus::ConflictPreserved`] rather than an error. The local
+    /// copy is brought in line with `options` too, e.g. a changed sparse set.
     fn update(
         &self,
         target: &Path,
         url: &str,
         revision: Option<&str>,
         allow_dirty: bool,
+        options: &CloneOptions,
     ) -> Result<UpdateStatus>;
     fn is_clean(&self, target: &Path) -> Result<bool>;
     /// Returns the URL of the `origin` remote for the repository at `target`,
     /// or `None` when `target` is not a git repository or has no such remote.
     fn origin_url(&self, target: &Path) -> Result<Option<String>>;
+    /// Returns the commit SHA checked out at `target`, or `None` when the
+    /// backend has no notion of a pinned commit (a local directory).
+    fn head_commit(&self, target: &Path) -> Result<Option<String>>;
+    /// Compares the local copy at `target` with `revision` on `url` without
+    /// touching the working tree, HEAD or the configured remotes. The fetch
+    /// this needs is limited and retried as `network` says.
+    fn check(
+        &self,
+        target: &Path,
+        url: &str,
+        revision: Option<&str>,
+        network: &NetworkOptions,
+    ) -> Result<RevisionStatus>;
+    /// Files in the working tree at `target` that the repository does not
+    /// track and does not ignore, e.g. themes built for custom schemes.
+    fn untracked_files(&self, target: &Path) -> Result<Vec<PathBuf>>;
+    /// What changed at `target` between commits `from` and `to`, both as
+    /// returned by `head_commit`.
+    fn changes(&self, target: &Path, from: &str, to: &str) -> Result<RepoChanges>;
 }
 
 /// Returns the active repository backend for this invocation.
 ///
-/// Phase 1 always returns the git-shell-out backend. Phase 2 introduces the
-/// gix backend and runtime dispatch via `TINTY_USE_GIX`.
+/// This is always the backend that shells out to `git`. A gix backend
+/// selected with `TINTY_USE_GIX` is not implemented.
 #[must_use]
 pub fn backend() -> Box<dyn RepositoryBackend> {
     Box::new(git_shell::GitShellBackend)
 }
 
-pub fn install(url: &str, target: &Path, revision: Option<&str>) -> Result<()> {
-    backend().install(url, target, revision)
+/// Whether an `[[items]]` or `[schemes]` `path` is on the local filesystem (a
+/// directory or an archive) rather than a Git URL.
+pub fn is_local_path(source: &str) -> bool {
+    Url::parse(source).is_err()
+}
+
+/// Whether an `[[items]]` or `[schemes]` `path` is a local `.tar.gz`, `.tgz`
+/// or `.zip` archive.
+pub fn is_archive(source: &str) -> bool {
+    is_local_path(source) && archive::is_archive_path(source)
+}
+
+/// Whether a source is a local directory, which is symlinked into place.
+pub fn is_local_dir(source: &str) -> bool {
+    is_local_path(source) && !is_archive(source)
+}
+
+/// Whether using a source means talking to another machine: every Git URL
+/// except `file://` ones, which includes `file://` Git bundles.
+pub fn needs_network(source: &str) -> bool {
+    Url::parse(source).is_ok_and(|url| url.scheme() != "file")
+}
+
+/// Fails with every `(name, source)` in `sources` that needs the network, for
+/// `--offline`. A source already mirrored in `cache` does not.
+pub fn ensure_offline(sources: &[(String, String)], cache: Option<&RepoCache>) -> Result<()> {
+    let online: Vec<String> = sources
+        .iter()
+        .filter(|(_, source)| {
+            needs_network(source) && !cache.is_some_and(|cache| cache.has_mirror(source))
+        })
+        .map(|(name, source)| format!("{name} ({source})"))
+        .collect();
+
+    if online.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "--offline was given, but these sources need the network: {}. Point them at a local directory, a .tar.gz or .zip archive, or a file:// Git repository or bundle, or fetch them into the shared cache first",
+            online.join(", ")
+        ))
+    }
+}
+
+/// The error for a commit `git verify-commit` rejected, with git's own
+/// explanation when it gave one.
+pub fn signature_error(commit: &str, stderr: &str) -> anyhow::Error {
+    if stderr.is_empty() {
+        anyhow!("Refusing to check out {commit}: it is not signed")
+    } else {
+        anyhow!("Refusing to check out {commit}: it is not signed by an allowed key:\n{stderr}")
+    }
+}
+
+/// Returns the backend for a configured source: a local directory is handled
+/// by [`local_path::LocalPathBackend`], a local archive by
+/// [`archive::ArchiveBackend`], and a Git URL by [`backend`].
+#[must_use]
+pub fn backend_for(source: &str) -> Box<dyn RepositoryBackend> {
+    if is_archive(source) {
+        Box::new(archive::ArchiveBackend)
+    } else if is_local_path(source) {
+        Box::new(local_path::LocalPathBackend)
+    } else {
+        backend()
+    }
+}
+
+pub fn install(
+    source: &str,
+    target: &Path,
+    revision: Option<&str>,
+    options: &CloneOptions,
+) -> Result<()> {
+    backend_for(source).install(source, target, revision, options)
 }
 
 pub fn update(
     target: &Path,
-    url: &str,
+    source: &str,
     revision: Option<&str>,
     allow_dirty: bool,
+    options: &CloneOptions,
 ) -> Result<UpdateStatus> {
-    backend().update(target, url, revision, allow_dirty)
+    backend_for(source).update(target, source, revision, allow_dirty, options)
+}
+
+pub fn is_clean(target: &Path, source: &str) -> Result<bool> {
+    backend_for(source).is_clean(target)
+}
+
+pub fn origin_url(target: &Path, source: &str) -> Result<Option<String>> {
+    backend_for(source).origin_url(target)
+}
+
+pub fn head_commit(target: &Path, source: &str) -> Result<Option<String>> {
+    backend_for(source).head_commit(target)
+}
+
+pub fn check(
+    target: &Path,
+    source: &str,
+    revision: Option<&str>,
+    network: &NetworkOptions,
+) -> Result<RevisionStatus> {
+    backend_for(source).check(target, source, revision, network)
 }
 
-pub fn is_clean(target: &Path) -> Result<bool> {
-    backend().is_clean(target)
+pub fn untracked_files(target: &Path, source: &str) -> Result<Vec<PathBuf>> {
+    backend_for(source).untracked_files(target)
 }
 
-pub fn origin_url(target: &Path) -> Result<Option<String>> {
-    backend().origin_url(target)
+pub fn changes(target: &Path, source: &str, from: &str, to: &str) -> Result<RepoChanges> {
+    backend_for(source).changes(target, from, to)
 }
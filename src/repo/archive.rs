@@ -0,0 +1,187 @@
+#![allow(clippy::module_name_repetitions)]
+
+use crate::repo::{
+    CloneOptions, NetworkOptions, RepoChanges, RepositoryBackend, RevisionStatus, UpdateStatus,
+};
+use anyhow::{anyhow, Context, Result};
+use flate2::read::GzDecoder;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+/// File written into every unpacked archive, holding the path of the archive
+/// it came from.
+const ARCHIVE_MARKER: &str = ".tinty-archive";
+
+/// Extensions of the archives [`ArchiveBackend`] can unpack.
+const TAR_GZ_EXTENSIONS: [&str; 2] = [".tar.gz", ".tgz"];
+const ZIP_EXTENSION: &str = ".zip";
+
+/// Whether `source` names an archive on disk rather than a directory.
+pub fn is_archive_path(source: &str) -> bool {
+    let source = source.to_ascii_lowercase();
+
+    TAR_GZ_EXTENSIONS
+        .iter()
+        .chain(std::iter::once(&ZIP_EXTENSION))
+        .any(|extension| source.ends_with(extension))
+}
+
+/// Repository backend for sources that are a local `.tar.gz`, `.tgz` or `.zip`
+/// archive. The archive is unpacked into place, and unpacked again on
+/// `update`; there is no history, so nothing is pinned or compared. An archive
+/// holding a single top-level directory (like GitHub's source downloads) is
+/// unpacked from inside that directory.
+pub struct ArchiveBackend;
+
+impl RepositoryBackend for ArchiveBackend {
+    /// Unpacks the archive at `path` into `target`, replacing what is there.
+    /// `revision` and `options` are ignored.
+    fn install(
+        &self,
+        path: &str,
+        target: &Path,
+        _revision: Option<&str>,
+        _options: &CloneOptions,
+    ) -> Result<()> {
+        unpack_into(Path::new(path), target)
+    }
+
+    /// Unpacks the archive at `path` again, so a replaced archive takes effect.
+    /// The unpacked files are tinty's own, so `allow_dirty` does not apply.
+    fn update(
+        &self,
+        target: &Path,
+        path: &str,
+        _revision: Option<&str>,
+        _allow_dirty: bool,
+        _options: &CloneOptions,
+    ) -> Result<UpdateStatus> {
+        unpack_into(Path::new(path), target)?;
+
+        Ok(UpdateStatus::Updated)
+    }
+
+    /// An unpacked archive has no notion of uncommitted changes.
+    fn is_clean(&self, _target: &Path) -> Result<bool> {
+        Ok(true)
+    }
+
+    /// Returns the archive `target` was unpacked from.
+    fn origin_url(&self, target: &Path) -> Result<Option<String>> {
+        Ok(fs::read_to_string(target.join(ARCHIVE_MARKER))
+            .ok()
+            .map(|source| source.trim().to_string()))
+    }
+
+    /// An archive has no commits to pin.
+    fn head_commit(&self, _target: &Path) -> Result<Option<String>> {
+        Ok(None)
+    }
+
+    /// An archive is always current: there is nothing upstream to be behind.
+    fn check(
+        &self,
+        _target: &Path,
+        _path: &str,
+        _revision: Option<&str>,
+        _network: &NetworkOptions,
+    ) -> Result<RevisionStatus> {
+        Ok(RevisionStatus {
+            current: None,
+            target: None,
+            behind: 0,
+        })
+    }
+
+    /// Nothing in an unpacked archive is tracked, so nothing is reported.
+    fn untracked_files(&self, _target: &Path) -> Result<Vec<PathBuf>> {
+        Ok(Vec::new())
+    }
+
+    /// An unpacked archive has no commits to compare.
+    fn changes(&self, _target: &Path, _from: &str, _to: &str) -> Result<RepoChanges> {
+        Ok(RepoChanges::default())
+    }
+}
+
+/// Unpacks `archive` next to `target` first and only then swaps it into place,
+/// so a corrupt archive leaves the previous contents alone.
+fn unpack_into(archive: &Path, target: &Path) -> Result<()> {
+    if !archive.is_file() {
+        return Err(anyhow!(
+            "{} is not a file. Check the path in your config and try again",
+            archive.display()
+        ));
+    }
+
+    let parent = target
+        .parent()
+        .ok_or_else(|| anyhow!("{} has no parent directory", target.display()))?;
+    fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+    let staging = tempfile::Builder::new()
+        .prefix(".tinty-unpack-")
+        .tempdir_in(parent)?;
+
+    let source = archive.to_string_lossy();
+    if source.to_ascii_lowercase().ends_with(ZIP_EXTENSION) {
+        zip::ZipArchive::new(File::open(archive)?)
+            .and_then(|mut zip| zip.extract(staging.path()))
+            .with_context(|| format!("Failed to unpack {}", archive.display()))?;
+    } else {
+        tar::Archive::new(GzDecoder::new(File::open(archive)?))
+            .unpack(staging.path())
+            .with_context(|| format!("Failed to unpack {}", archive.display()))?;
+    }
+
+    let root =
+        single_top_level_dir(staging.path())?.unwrap_or_else(|| staging.path().to_path_buf());
+    fs::write(root.join(ARCHIVE_MARKER), source.as_bytes())?;
+
+    remove_existing(target)?;
+    fs::rename(&root, target).with_context(|| {
+        format!(
+            "Failed to move the unpacked {} to {}",
+            archive.display(),
+            target.display()
+        )
+    })
+}
+
+/// The only entry in `dir` when that entry is a directory.
+fn single_top_level_dir(dir: &Path) -> Result<Option<PathBuf>> {
+    let mut entries = fs::read_dir(dir)?.filter_map(Result::ok);
+
+    match (entries.next(), entries.next()) {
+        (Some(entry), None) if entry.file_type()?.is_dir() => Ok(Some(entry.path())),
+        _ => Ok(None),
+    }
+}
+
+/// Removes whatever occupies `target`. A symlink left by a local-directory
+/// source is unlinked without touching the directory it points at.
+fn remove_existing(target: &Path) -> Result<()> {
+    let Ok(metadata) = fs::symlink_metadata(target) else {
+        return Ok(());
+    };
+
+    if metadata.is_dir() {
+        fs::remove_dir_all(target)
+    } else {
+        fs::remove_file(target)
+    }
+    .with_context(|| format!("Failed to remove {}", target.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_archive_path;
+
+    #[test]
+    fn recognises_archive_extensions() {
+        assert!(is_archive_path("/srv/themes/tinted-shell.tar.gz"));
+        assert!(is_archive_path("schemes.TGZ"));
+        assert!(is_archive_path("~/offline/tinted-vim.zip"));
+        assert!(!is_archive_path("/srv/themes/tinted-shell"));
+        assert!(!is_archive_path("/srv/themes/tinted-shell.bundle"));
+    }
+}
@@ -1,22 +1,59 @@
 #![allow(clippy::module_name_repetitions)]
 
 use crate::constants::DEFAULT_REVISION;
-use crate::repo::{RepositoryBackend, UpdateStatus};
+use crate::repo::{
+    self, CloneOptions, FileChange, GitError, GitErrorKind, NetworkOptions, RepoCache, RepoChanges,
+    RepositoryBackend, RevisionStatus, UpdateStatus,
+};
 use anyhow::{anyhow, Context, Error, Result};
+use fs2::FileExt;
 use rand::Rng;
 use regex::bytes::Regex;
 use std::collections::HashSet;
 use std::fs;
-use std::io::{BufRead, BufReader};
-use std::path::Path;
+use std::io::{BufRead, BufReader, Read};
+use std::num::NonZeroU32;
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+use url::Url;
+
+pub const BUNDLE_EXTENSION: &str = ".bundle";
+/// How often a clone or fetch is checked for having finished or timed out.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// How long to wait before the first retry of a failed clone or fetch. Each
+/// further retry waits twice as long as the one before.
+const RETRY_BACKOFF: Duration = Duration::from_secs(1);
 
 /// Repository backend implemented by shelling out to the user's `git` binary.
 pub struct GitShellBackend;
 
 impl RepositoryBackend for GitShellBackend {
-    fn install(&self, url: &str, target: &Path, revision: Option<&str>) -> Result<()> {
-        git_clone(url, target, revision)
+    fn install(
+        &self,
+        url: &str,
+        target: &Path,
+        revision: Option<&str>,
+        options: &CloneOptions,
+    ) -> Result<()> {
+        if let Some(cache) = options.cache.as_ref().filter(|_| RepoCache::can_cache(url)) {
+            let (mirror, _lock) = git_mirror(cache, url, &options.network)?;
+            git_clone(&mirror.to_string_lossy(), target, revision, options, false)?;
+            return git_run(target, &format!("git remote set-url origin \"{url}\""));
+        }
+
+        // Local transports ignore `--filter` (with a warning), and there is
+        // nothing to save by filtering them anyway.
+        let is_filtered = repo::needs_network(url);
+
+        git_clone(
+            &remote_location(url),
+            target,
+            revision,
+            options,
+            is_filtered,
+        )
     }
 
     fn update(
@@ -25,8 +62,28 @@ impl RepositoryBackend for GitShellBackend {
         url: &str,
         revision: Option<&str>,
         allow_dirty: bool,
+        options: &CloneOptions,
     ) -> Result<UpdateStatus> {
-        git_update(target, url, revision, allow_dirty)
+        if let Some(cache) = options.cache.as_ref().filter(|_| RepoCache::can_cache(url)) {
+            let (mirror, _lock) = git_mirror(cache, url, &options.network)?;
+            let status = git_update(
+                target,
+                &mirror.to_string_lossy(),
+                revision,
+                allow_dirty,
+                options,
+            )?;
+            git_run(target, &format!("git remote set-url origin \"{url}\""))?;
+            return Ok(status);
+        }
+
+        git_update(
+            target,
+            &remote_location(url),
+            revision,
+            allow_dirty,
+            options,
+        )
     }
 
     fn is_clean(&self, target: &Path) -> Result<bool> {
@@ -34,8 +91,138 @@ impl RepositoryBackend for GitShellBackend {
     }
 
     fn origin_url(&self, target: &Path) -> Result<Option<String>> {
-        git_origin_url(target)
+        Ok(git_origin_url(target)?.map(|origin| bundle_url(&origin)))
     }
+
+    fn head_commit(&self, target: &Path) -> Result<Option<String>> {
+        git_head_commit(target)
+    }
+
+    fn check(
+        &self,
+        target: &Path,
+        url: &str,
+        revision: Option<&str>,
+        network: &NetworkOptions,
+    ) -> Result<RevisionStatus> {
+        git_check(target, &remote_location(url), revision, network)
+    }
+
+    fn untracked_files(&self, target: &Path) -> Result<Vec<PathBuf>> {
+        git_untracked_files(target)
+    }
+
+    fn changes(&self, target: &Path, from: &str, to: &str) -> Result<RepoChanges> {
+        git_changes(target, from, to)
+    }
+}
+
+/// What to hand git for `url`. Git reads a bundle from a plain path but not
+/// from a `file://` URL, so a `file://` URL to a `.bundle` becomes its path.
+fn remote_location(url: &str) -> String {
+    Url::parse(url)
+        .ok()
+        .filter(|url| url.scheme() == "file" && url.path().ends_with(BUNDLE_EXTENSION))
+        .and_then(|url| url.to_file_path().ok())
+        .map_or_else(
+            || url.to_string(),
+            |path| path.to_string_lossy().into_owned(),
+        )
+}
+
+/// The reverse of [`remote_location`]: an `origin` that is a bundle path is
+/// reported as the `file://` URL it was configured as.
+fn bundle_url(origin: &str) -> String {
+    if Url::parse(origin).is_err() && origin.ends_with(BUNDLE_EXTENSION) {
+        if let Ok(url) = Url::from_file_path(origin) {
+            return url.to_string();
+        }
+    }
+
+    origin.to_string()
+}
+
+/// Brings the mirror of `url` in `cache` up to date, cloning it on first use,
+/// and returns its path with a lock that keeps other tinty processes from
+/// changing the mirror until it is dropped. A mirror that exists but can't be
+/// refreshed because of the network is used as it is: it still has everything
+/// earlier fetches brought in.
+fn git_mirror(
+    cache: &RepoCache,
+    url: &str,
+    network: &NetworkOptions,
+) -> Result<(PathBuf, fs::File)> {
+    let mirror = cache.mirror_path(url);
+    fs::create_dir_all(&cache.dir)
+        .with_context(|| format!("Failed to create cache directory {}", cache.dir.display()))?;
+
+    let lock_path = mirror.with_extension("lock");
+    let lock = fs::File::create(&lock_path)
+        .with_context(|| format!("Failed to create lock file {}", lock_path.display()))?;
+    lock.lock_exclusive().with_context(|| {
+        format!(
+            "Failed to acquire exclusive lock on {}",
+            lock_path.display()
+        )
+    })?;
+
+    if !mirror.is_dir() {
+        let command = format!(
+            "git clone --mirror --quiet \"{}\" \"{}\"",
+            remote_location(url),
+            mirror.display()
+        );
+        with_retries(network, || {
+            let result = git_network(
+                &cache.dir,
+                &command,
+                &format!("mirror {url}"),
+                network.timeout,
+            );
+            if result.is_err() && mirror.exists() {
+                fs::remove_dir_all(&mirror)
+                    .with_context(|| format!("Failed to remove directory {}", mirror.display()))?;
+            }
+            result
+        })?;
+    } else if cache.refresh {
+        let result = with_retries(network, || {
+            git_network(
+                &mirror,
+                "git fetch --quiet --prune origin",
+                &format!("fetch {url} into the shared cache"),
+                network.timeout,
+            )
+        });
+        match result {
+            Err(err) if !error_kind(&err).is_some_and(GitErrorKind::is_transient) => {
+                return Err(err);
+            }
+            _ => {}
+        }
+    }
+
+    Ok((mirror, lock))
+}
+
+/// Reads the SHA of the commit checked out at `target`. Returns `None` when
+/// `target` is not a git repository or has no commits yet.
+fn git_head_commit(target: &Path) -> Result<Option<String>> {
+    if !target.is_dir() {
+        return Ok(None);
+    }
+
+    let output = safe_command("git rev-parse --verify --quiet HEAD", target)?
+        .stderr(Stdio::null())
+        .output()
+        .with_context(|| format!("Failed to read HEAD in {}", target.display()))?;
+
+    if !output.status.success() {
+        return Ok(None);
+    }
+
+    let sha = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    Ok((!sha.is_empty()).then_some(sha))
 }
 
 /// Reads the `origin` remote URL of the repository at `target`. Returns `None`
@@ -59,7 +246,16 @@ fn git_origin_url(target: &Path) -> Result<Option<String>> {
     Ok((!url.is_empty()).then_some(url))
 }
 
-fn git_clone(repo_url: &str, target_dir: &Path, revision: Option<&str>) -> Result<()> {
+/// Clones `repo_url` into `target_dir`. A `depth` in `options` makes a shallow
+/// clone, and `sparse_paths` a sparse one, fetching blobs only for the checked
+/// out paths when `is_filtered`.
+fn git_clone(
+    repo_url: &str,
+    target_dir: &Path,
+    revision: Option<&str>,
+    options: &CloneOptions,
+    is_filtered: bool,
+) -> Result<()> {
     if target_dir.exists() {
         return Err(anyhow!(
             "Error cloning {}. Target directory '{}' already exists",
@@ -68,32 +264,55 @@ fn git_clone(repo_url: &str, target_dir: &Path, revision: Option<&str>) -> Resul
         ));
     }
 
-    let git_command = format!("git clone \"{repo_url}\" \"{}\"", target_dir.display());
-    let command_vec = shell_words::split(git_command.as_str()).map_err(anyhow::Error::new)?;
-
-    let Some(command) = command_vec.first() else {
-        return Err(anyhow!("Unable to extract cli command"));
-    };
-    let Some(args) = command_vec.get(1..) else {
-        return Err(anyhow!("Unable to extract cli args"));
-    };
+    let mut clone_flags = depth_flag(options.depth);
+    if options.sparse_paths.is_some() {
+        clone_flags.push_str(" --sparse");
+        if is_filtered {
+            clone_flags.push_str(" --filter=blob:none");
+        }
+    }
+    let git_command = format!(
+        "git clone --quiet{clone_flags} \"{repo_url}\" \"{}\"",
+        target_dir.display()
+    );
 
-    Command::new(command)
-        .args(args)
-        .stdout(Stdio::null())
-        .status()
-        .with_context(|| format!("Failed to clone repository from {repo_url}"))?;
-
-    if let Some(revision_str) = revision {
-        // A freshly cloned tree is clean, so a conflict can never arise here;
-        // `allow_dirty` is irrelevant and the returned status is ignored.
-        let result = git_to_revision(target_dir, "origin", revision_str, false);
-        if let Err(e) = result {
-            // Cleanup! If we cannot checkout the revision, remove the directory.
+    with_retries(&options.network, || {
+        let result = git_network(
+            Path::new("."),
+            &git_command,
+            &format!("clone {repo_url}"),
+            options.network.timeout,
+        );
+        // A clone that timed out leaves a partial checkout behind, which would
+        // make the next attempt fail.
+        if result.is_err() && target_dir.exists() {
             fs::remove_dir_all(target_dir)
                 .with_context(|| format!("Failed to remove directory {}", target_dir.display()))?;
-            return Err(e);
         }
+        result
+    })?;
+
+    let result = git_sparse_checkout(target_dir, options.sparse_paths.as_deref())
+        .and_then(|()| {
+            // A freshly cloned tree is clean, so a conflict can never arise
+            // here; `allow_dirty` is irrelevant.
+            revision.map_or_else(
+                || git_verify_head(target_dir, options),
+                |revision_str| git_to_revision(target_dir, "origin", revision_str, false, options),
+            )
+        })
+        .and_then(|status| match status {
+            UpdateStatus::SignatureRejected { commit, stderr } => {
+                Err(repo::signature_error(&commit, &stderr))
+            }
+            UpdateStatus::Updated | UpdateStatus::ConflictPreserved { .. } => Ok(()),
+        });
+    if let Err(e) = result {
+        // Cleanup! If we cannot checkout the revision, or the checked out
+        // commit is not signed by an allowed key, remove the directory.
+        fs::remove_dir_all(target_dir)
+            .with_context(|| format!("Failed to remove directory {}", target_dir.display()))?;
+        return Err(e);
     }
 
     Ok(())
@@ -104,6 +323,7 @@ fn git_update(
     repo_url: &str,
     revision: Option<&str>,
     allow_dirty: bool,
+    options: &CloneOptions,
 ) -> Result<UpdateStatus> {
     if !repo_path.is_dir() {
         return Err(anyhow!(
@@ -114,12 +334,13 @@ fn git_update(
 
     // To make this operation atomic, we'll satisfy the remote & revision in this sequence:
     // 1.) add the remote URL as a new temporary remote.
-    // 2.) check if the revision exists in the temporary remote.
-    // 3.) checkout the revision from temporary remote
-    // 4.) On success:
-    //      4.1) replace the origin remote URL
-    //      4.2) remove the temporary remote
-    // 5.) On error, remove temporary remote
+    // 2.) bring the sparse set and clone depth in line with the config.
+    // 3.) check if the revision exists in the temporary remote.
+    // 4.) checkout the revision from temporary remote
+    // 5.) On success:
+    //      5.1) replace the origin remote URL
+    //      5.2) remove the temporary remote
+    // 6.) On error, remove temporary remote
     //
     // Note that this sequence works even if the directory is already on that remote & revision.
     //
@@ -143,7 +364,17 @@ fn git_update(
     })?;
 
     let revision_str = revision.unwrap_or(DEFAULT_REVISION);
-    let res = git_to_revision(repo_path, &tmp_remote_name, revision_str, allow_dirty);
+    let res = git_sparse_checkout(repo_path, options.sparse_paths.as_deref())
+        .and_then(|()| git_unshallow_unless(repo_path, &tmp_remote_name, options))
+        .and_then(|()| {
+            git_to_revision(
+                repo_path,
+                &tmp_remote_name,
+                revision_str,
+                allow_dirty,
+                options,
+            )
+        });
 
     let status = match res {
         Ok(status) => status,
@@ -194,6 +425,111 @@ fn git_update(
     Ok(status)
 }
 
+/// Fetches `revision` from `repo_url` into a temporary remote, like
+/// `git_update`, and compares it with HEAD. Only objects are downloaded: the
+/// working tree, HEAD and `origin` are left alone, and the temporary remote is
+/// removed again whether or not the comparison succeeds.
+fn git_check(
+    repo_path: &Path,
+    repo_url: &str,
+    revision: Option<&str>,
+    network: &NetworkOptions,
+) -> Result<RevisionStatus> {
+    if !repo_path.is_dir() {
+        return Err(anyhow!(
+            "Error checking for updates. {} is not a directory",
+            repo_path.display()
+        ));
+    }
+
+    let tmp_remote_name = random_remote_name();
+    safe_command(
+        format!("git remote add \"{tmp_remote_name}\" \"{repo_url}\"").as_str(),
+        repo_path,
+    )?
+    .stdout(Stdio::null())
+    .status()
+    .with_context(|| {
+        format!(
+            "Error with adding {} as a remote named {} in {}",
+            repo_url,
+            tmp_remote_name,
+            repo_path.display()
+        )
+    })?;
+
+    let revision_str = revision.unwrap_or(DEFAULT_REVISION);
+    let res = git_compare_to_remote(repo_path, &tmp_remote_name, revision_str, network);
+
+    safe_command(
+        format!("git remote rm \"{tmp_remote_name}\"").as_str(),
+        repo_path,
+    )?
+    .stdout(Stdio::null())
+    .status()
+    .with_context(|| {
+        format!(
+            "Failed to remove temporary remote {tmp_remote_name} in {}",
+            repo_path.display()
+        )
+    })?;
+
+    res
+}
+
+fn git_compare_to_remote(
+    repo_path: &Path,
+    remote_name: &str,
+    revision: &str,
+    network: &NetworkOptions,
+) -> Result<RevisionStatus> {
+    git_fetch_revision(repo_path, remote_name, revision, "", network)?;
+
+    let resolved = git_resolve_revision(repo_path, remote_name, revision, None, network)?;
+    // An annotated tag resolves to the tag object; compare the commit it
+    // points at.
+    let target = git_stdout(
+        repo_path,
+        &format!(
+            "git rev-parse --verify --quiet \"{}^{{commit}}\"",
+            resolved.sha
+        ),
+    )?
+    .ok_or_else(|| anyhow!("cannot resolve {revision} into a commit"))?;
+    let current = git_head_commit(repo_path)?;
+    let behind = match &current {
+        Some(current) => git_stdout(
+            repo_path,
+            &format!("git rev-list --count \"{current}..{target}\""),
+        )?
+        .and_then(|count| count.parse().ok())
+        .unwrap_or_default(),
+        None => 0,
+    };
+
+    Ok(RevisionStatus {
+        current,
+        target: Some(target),
+        behind,
+    })
+}
+
+/// Runs a git command and returns its trimmed stdout, or `None` when it fails
+/// or prints nothing.
+fn git_stdout(repo_path: &Path, command: &str) -> Result<Option<String>> {
+    let output = safe_command(command, repo_path)?
+        .stderr(Stdio::null())
+        .output()
+        .with_context(|| format!("Failed to run `{command}` in {}", repo_path.display()))?;
+
+    if !output.status.success() {
+        return Ok(None);
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    Ok((!stdout.is_empty()).then_some(stdout))
+}
+
 fn random_remote_name() -> String {
     let mut rng = rand::thread_rng();
     let random_number: u32 = rng.gen();
@@ -219,94 +555,37 @@ fn git_resolve_revision(
     repo_path: &Path,
     remote_name: &str,
     revision: &str,
+    depth: Option<NonZeroU32>,
+    network: &NetworkOptions,
 ) -> Result<ResolvedRevision> {
     // 1.) Check if its a tag.
-    let expected_tag_ref = format!("refs/tags/{revision}");
-    let mut command = safe_command(
-        format!("git ls-remote --quiet --tags \"{remote_name}\" \"{expected_tag_ref}\"").as_str(),
+    if let Some(sha) = git_ls_remote(
         repo_path,
-    )?;
-    let mut child = command
-        .stderr(Stdio::null())
-        .stdout(Stdio::piped())
-        .spawn()
-        .with_context(|| "Failed to spawn".to_string())?;
-
-    let Some(stdout) = child.stdout.take() else {
-        return Err(anyhow!("failed to capture stdout"));
-    };
-    let reader = BufReader::new(stdout);
-
-    if let Some(parts) = reader
-        .lines()
-        .map_while(Result::ok)
-        .map(|line| line.split('\t').map(String::from).collect::<Vec<String>>())
-        .filter(|parts| parts.len() == 2)
-        .find(|parts| {
-            parts
-                .get(1)
-                .map_or_else(|| false, |second_part| *second_part == expected_tag_ref)
-        })
-    {
-        if let Some(first_part) = parts.first() {
-            // we found a tag that matches
-            child.kill()?; // Abort the child process.
-            child.wait()?; // Cleanup
-
-            return Ok(ResolvedRevision {
-                sha: first_part.clone(),
-                kind: RevisionType::Tag,
-            });
-        }
+        remote_name,
+        " --tags",
+        &format!("refs/tags/{revision}"),
+        network,
+    )? {
+        return Ok(ResolvedRevision {
+            sha,
+            kind: RevisionType::Tag,
+        });
     }
 
-    child
-        .wait()
-        .with_context(|| format!("Failed to list remote tags from {remote_name}"))?;
-
     // 2.) Check if its a branch
-    let expected_branch_ref = format!("refs/heads/{revision}");
-    let mut command = safe_command(
-        format!("git ls-remote --quiet \"{remote_name}\" \"{expected_branch_ref}\"").as_str(),
+    if let Some(sha) = git_ls_remote(
         repo_path,
-    )?;
-    let mut child = command
-        .stdout(Stdio::piped())
-        .spawn()
-        .with_context(|| "Failed to spawn".to_string())?;
-
-    let Some(stdout) = child.stdout.take() else {
-        return Err(anyhow!("failed to capture stdout"));
-    };
-    let reader = BufReader::new(stdout);
-
-    if let Some(parts) = reader
-        .lines()
-        .map_while(Result::ok)
-        .map(|line| line.split('\t').map(String::from).collect::<Vec<String>>())
-        .filter(|parts| parts.len() == 2)
-        .find(|parts| {
-            parts
-                .get(1)
-                .map_or_else(|| false, |second_part| *second_part == expected_branch_ref)
-        })
-    {
-        if let Some(first_part) = parts.first() {
-            // we found a branch that matches.
-            child.kill()?; // Abort the child process.
-            child.wait()?; // Cleanup
-
-            return Ok(ResolvedRevision {
-                sha: first_part.clone(),
-                kind: RevisionType::Branch,
-            });
-        }
+        remote_name,
+        "",
+        &format!("refs/heads/{revision}"),
+        network,
+    )? {
+        return Ok(ResolvedRevision {
+            sha,
+            kind: RevisionType::Branch,
+        });
     }
 
-    child
-        .wait()
-        .with_context(|| format!("Failed to list branches tags from {remote_name}"))?;
-
     // We are here because revision isn't a tag or a branch.
     // First, we'll check if revision itself *could* be a SHA1.
     // If it doesn't look like one, we'll return early.
@@ -315,16 +594,24 @@ fn git_resolve_revision(
         return Err(anyhow!("Invalid regex"));
     };
     if !re.is_match(revision.as_bytes()) {
-        return Err(anyhow!("cannot resolve {revision} into a Git SHA1"));
+        return Err(revision_missing(
+            revision,
+            format!("cannot resolve {revision} into a Git SHA1"),
+        ));
     }
 
-    safe_command(
-        format!("git fetch --quiet \"{remote_name}\"").as_str(),
-        repo_path,
-    )?
-    .stdout(Stdio::null())
-    .status()
-    .with_context(|| format!("unable to fetch objects from remote {remote_name}"))?;
+    // In a shallow clone only commits within `depth` of a branch tip are
+    // found.
+    let depth_flag = depth_flag(depth);
+    let fetch_command = format!("git fetch --quiet{depth_flag} \"{remote_name}\"");
+    with_retries(network, || {
+        git_network(
+            repo_path,
+            &fetch_command,
+            &format!("fetch {}", remote_url(repo_path, remote_name)),
+            network.timeout,
+        )
+    })?;
 
     // 3.) Check if any branch in remote contains the SHA1:
     // It seems that the only way to do this is to list the branches that contain the SHA1
@@ -361,11 +648,401 @@ fn git_resolve_revision(
         format!("Failed to list branches from {remote_name} containing SHA1 {revision}")
     })?;
 
-    Err(anyhow!(
-        "cannot find revision {revision} in remote {remote_name}",
+    Err(revision_missing(
+        revision,
+        format!(
+            "cannot find revision {revision} in remote {}",
+            remote_url(repo_path, remote_name)
+        ),
     ))
 }
 
+fn revision_missing(revision: &str, detail: String) -> Error {
+    GitError {
+        kind: GitErrorKind::RevisionMissing,
+        action: format!("resolve {revision}"),
+        stderr: detail,
+    }
+    .into()
+}
+
+/// Verifies the commit a fresh clone checked out when no revision is
+/// configured.
+fn git_verify_head(repo_path: &Path, options: &CloneOptions) -> Result<UpdateStatus> {
+    if !options.verify_signatures {
+        return Ok(UpdateStatus::Updated);
+    }
+
+    Ok(git_verify_commit(repo_path, "HEAD", options)?.unwrap_or(UpdateStatus::Updated))
+}
+
+/// Checks that `revision` (a commit, or a tag pointing at one) has a good
+/// signature from a key in `options.allowed_signers`, or from one git and
+/// GPG trust by default. Returns [`UpdateStatus::SignatureRejected`] when it
+/// does not, and `None` when it does.
+fn git_verify_commit(
+    repo_path: &Path,
+    revision: &str,
+    options: &CloneOptions,
+) -> Result<Option<UpdateStatus>> {
+    let commit = git_stdout(
+        repo_path,
+        &format!("git rev-parse --verify --quiet \"{revision}^{{commit}}\""),
+    )?
+    .ok_or_else(|| anyhow!("cannot resolve {revision} into a commit"))?;
+
+    // An allowed signers file only affects SSH signatures and a GPG home
+    // only GPG ones, so whichever is given, the other kind is checked against
+    // an empty one: otherwise the user's own keyring or signers file would
+    // let through keys the item doesn't allow.
+    let empty_gnupg_home;
+    let empty_signers_file;
+    let (signers_file, gnupg_home) = match options.allowed_signers.as_deref() {
+        Some(path) if path.is_dir() => {
+            empty_signers_file = tempfile::NamedTempFile::new()?;
+            (Some(empty_signers_file.path()), Some(path))
+        }
+        Some(path) => {
+            empty_gnupg_home = tempfile::tempdir()?;
+            (Some(path), Some(empty_gnupg_home.path()))
+        }
+        None => (None, None),
+    };
+    let signers_config = signers_file.map_or_else(String::new, |path| {
+        format!(" -c gpg.ssh.allowedSignersFile=\"{}\"", path.display())
+    });
+    let mut command = safe_command(
+        &format!("git{signers_config} verify-commit \"{commit}\""),
+        repo_path,
+    )?;
+    if let Some(gnupg_home) = gnupg_home {
+        command.env("GNUPGHOME", gnupg_home);
+    }
+    let output = command
+        .stdout(Stdio::null())
+        .output()
+        .with_context(|| format!("Failed to verify {commit} in {}", repo_path.display()))?;
+
+    if output.status.success() {
+        return Ok(None);
+    }
+
+    Ok(Some(UpdateStatus::SignatureRejected {
+        commit,
+        stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+    }))
+}
+
+/// The `git clone`/`git fetch` flag for `depth`, with a leading space, or
+/// nothing for full history.
+fn depth_flag(depth: Option<NonZeroU32>) -> String {
+    depth.map_or_else(String::new, |depth| format!(" --depth {depth}"))
+}
+
+/// Limits the working tree at `repo_path` to `sparse_paths` (in cone mode, so
+/// files at the repository root stay checked out), or turns a sparse checkout
+/// back into a full one when `sparse_paths` is `None`.
+fn git_sparse_checkout(repo_path: &Path, sparse_paths: Option<&[String]>) -> Result<()> {
+    let command = match sparse_paths {
+        Some(paths) => {
+            let paths: Vec<String> = paths.iter().map(|path| format!("\"{path}\"")).collect();
+            format!("git sparse-checkout set --cone -- {}", paths.join(" "))
+        }
+        None if git_stdout(repo_path, "git config --bool core.sparseCheckout")?.as_deref()
+            == Some("true") =>
+        {
+            "git sparse-checkout disable".to_string()
+        }
+        None => return Ok(()),
+    };
+
+    git_run(repo_path, &command)
+}
+
+/// Fetches the full history of a shallow clone from `remote_name` when no
+/// `depth` is configured anymore.
+fn git_unshallow_unless(repo_path: &Path, remote_name: &str, options: &CloneOptions) -> Result<()> {
+    let is_shallow =
+        git_stdout(repo_path, "git rev-parse --is-shallow-repository")?.as_deref() == Some("true");
+
+    if options.depth.is_some() || !is_shallow {
+        return Ok(());
+    }
+
+    let command = format!("git fetch --quiet --unshallow \"{remote_name}\"");
+    with_retries(&options.network, || {
+        git_network(
+            repo_path,
+            &command,
+            &format!(
+                "fetch the full history of {}",
+                remote_url(repo_path, remote_name)
+            ),
+            options.network.timeout,
+        )
+    })
+}
+
+/// Runs a git command, failing with its stderr when it exits unsuccessfully.
+fn git_run(repo_path: &Path, command: &str) -> Result<()> {
+    let output = safe_command(command, repo_path)?
+        .stdout(Stdio::null())
+        .output()
+        .with_context(|| format!("Failed to run `{command}` in {}", repo_path.display()))?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "`{command}` failed in {}:\n{}",
+            repo_path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+/// Fetches `revision` from `remote_name`. A revision the remote does not
+/// advertise is not an error yet: a commit SHA is only found once
+/// `git_resolve_revision` fetches whole branches, which reports it if the
+/// revision is missing after all.
+fn git_fetch_revision(
+    repo_path: &Path,
+    remote_name: &str,
+    revision: &str,
+    depth_flag: &str,
+    network: &NetworkOptions,
+) -> Result<()> {
+    let command = format!("git fetch --quiet{depth_flag} \"{remote_name}\" \"{revision}\"");
+    let result = with_retries(network, || {
+        git_network(
+            repo_path,
+            &command,
+            &format!(
+                "fetch {revision} from {}",
+                remote_url(repo_path, remote_name)
+            ),
+            network.timeout,
+        )
+    });
+
+    match result {
+        Err(err) if error_kind(&err) == Some(GitErrorKind::RevisionMissing) => Ok(()),
+        result => result,
+    }
+}
+
+/// The SHA1 `expected_ref` points at in `remote_name`, or `None` when the
+/// remote has no such ref.
+fn git_ls_remote(
+    repo_path: &Path,
+    remote_name: &str,
+    flags: &str,
+    expected_ref: &str,
+    network: &NetworkOptions,
+) -> Result<Option<String>> {
+    let command = format!("git ls-remote --quiet{flags} \"{remote_name}\" \"{expected_ref}\"");
+    let stdout = with_retries(network, || {
+        git_network_output(
+            repo_path,
+            &command,
+            &format!(
+                "list {expected_ref} in {}",
+                remote_url(repo_path, remote_name)
+            ),
+            network.timeout,
+        )
+    })?;
+
+    Ok(stdout
+        .lines()
+        .filter_map(|line| line.split_once('\t'))
+        .find(|(_, reference)| *reference == expected_ref)
+        .map(|(sha, _)| sha.to_string()))
+}
+
+/// The URL of `remote_name`, for error messages, or the name itself when it
+/// can't be read.
+fn remote_url(repo_path: &Path, remote_name: &str) -> String {
+    git_stdout(repo_path, &format!("git remote get-url \"{remote_name}\""))
+        .ok()
+        .flatten()
+        .unwrap_or_else(|| remote_name.to_string())
+}
+
+/// Runs `attempt` until it succeeds, fails for a reason that is not transient,
+/// or has been retried `network.retries` times, waiting longer before each
+/// retry.
+fn with_retries<T>(network: &NetworkOptions, mut attempt: impl FnMut() -> Result<T>) -> Result<T> {
+    let mut retries: u32 = 0;
+    let mut backoff = RETRY_BACKOFF;
+
+    loop {
+        let err = match attempt() {
+            Ok(value) => return Ok(value),
+            Err(err) => err,
+        };
+        if !error_kind(&err).is_some_and(GitErrorKind::is_transient) {
+            return Err(err);
+        }
+        if retries >= network.retries {
+            return Err(if retries == 0 {
+                err
+            } else {
+                err.context(format!(
+                    "Gave up after {} attempts",
+                    retries.saturating_add(1)
+                ))
+            });
+        }
+
+        thread::sleep(backoff);
+        backoff = backoff.saturating_mul(2);
+        retries = retries.saturating_add(1);
+    }
+}
+
+fn error_kind(err: &Error) -> Option<GitErrorKind> {
+    err.downcast_ref::<GitError>().map(|err| err.kind)
+}
+
+/// Runs a git command that talks to a remote, killing it after `timeout`.
+/// Fails with a [`GitError`] classifying git's stderr. git never prompts for
+/// credentials, which would hang a job nobody is watching.
+fn git_network(cwd: &Path, command: &str, action: &str, timeout: Option<Duration>) -> Result<()> {
+    git_network_output(cwd, command, action, timeout).map(drop)
+}
+
+/// Like `git_network`, returning the command's stdout.
+fn git_network_output(
+    cwd: &Path,
+    command: &str,
+    action: &str,
+    timeout: Option<Duration>,
+) -> Result<String> {
+    let mut child = safe_command(command, cwd)?
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to run `{command}` in {}", cwd.display()))?;
+
+    // Read stdout and stderr on other threads so a chatty git can't fill a
+    // pipe and block while we wait for it.
+    let mut stdout_pipe = child
+        .stdout
+        .take()
+        .ok_or_else(|| anyhow!("failed to capture stdout"))?;
+    let stdout_reader = thread::spawn(move || {
+        let mut stdout = String::new();
+        let _ = stdout_pipe.read_to_string(&mut stdout);
+        stdout
+    });
+    let mut stderr_pipe = child
+        .stderr
+        .take()
+        .ok_or_else(|| anyhow!("failed to capture stderr"))?;
+    let stderr_reader = thread::spawn(move || {
+        let mut stderr = String::new();
+        let _ = stderr_pipe.read_to_string(&mut stderr);
+        stderr
+    });
+
+    let deadline = timeout.and_then(|timeout| Instant::now().checked_add(timeout));
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            child.kill()?;
+            child.wait()?;
+            // Not joining the readers: a helper git started, such as
+            // `git-remote-https`, may outlive git and hold the pipe open.
+            return Err(GitError {
+                kind: GitErrorKind::Timeout,
+                action: format!("{action} within {}s", timeout.unwrap_or_default().as_secs()),
+                stderr: String::new(),
+            }
+            .into());
+        }
+        thread::sleep(POLL_INTERVAL);
+    };
+
+    let stderr = stderr_reader
+        .join()
+        .map_err(|_| anyhow!("failed to read the output of `{command}`"))?;
+    if status.success() {
+        return stdout_reader
+            .join()
+            .map_err(|_| anyhow!("failed to read the output of `{command}`"));
+    }
+
+    Err(GitError {
+        kind: classify(&stderr),
+        action: action.to_string(),
+        stderr: stderr.trim().to_string(),
+    }
+    .into())
+}
+
+/// Tells from git's (English) stderr why a clone or fetch failed.
+fn classify(stderr: &str) -> GitErrorKind {
+    const AUTH: &[&str] = &[
+        "authentication failed",
+        "permission denied",
+        "could not read username",
+        "could not read password",
+        "terminal prompts disabled",
+        "host key verification failed",
+        "invalid username or password",
+        "returned error: 401",
+        "returned error: 403",
+    ];
+    const NOT_FOUND: &[&str] = &[
+        "repository not found",
+        "does not appear to be a git repository",
+        "returned error: 404",
+        "' not found",
+    ];
+    const REVISION_MISSING: &[&str] = &[
+        "couldn't find remote ref",
+        "not our ref",
+        "unknown revision",
+    ];
+    const NETWORK: &[&str] = &[
+        "could not resolve host",
+        "could not resolve proxy",
+        "temporary failure in name resolution",
+        "connection refused",
+        "connection reset",
+        "connection timed out",
+        "operation timed out",
+        "network is unreachable",
+        "failed to connect",
+        "unable to access",
+        "early eof",
+        "the remote end hung up unexpectedly",
+        "rpc failed",
+        "gnutls",
+        "ssl",
+    ];
+
+    let stderr = stderr.to_lowercase();
+    let matches = |needles: &[&str]| needles.iter().any(|needle| stderr.contains(needle));
+
+    if matches(AUTH) {
+        GitErrorKind::Auth
+    } else if matches(NOT_FOUND) {
+        GitErrorKind::NotFound
+    } else if matches(REVISION_MISSING) {
+        GitErrorKind::RevisionMissing
+    } else if matches(NETWORK) {
+        GitErrorKind::Network
+    } else {
+        GitErrorKind::Other
+    }
+}
+
 fn safe_command(command_str: &str, cwd: &Path) -> Result<Command, Error> {
     let command_vec = shell_words::split(command_str).map_err(anyhow::Error::new)?;
     let Some(command) = command_vec.first() else {
@@ -385,22 +1062,33 @@ fn git_to_revision(
     remote_name: &str,
     revision: &str,
     allow_dirty: bool,
+    options: &CloneOptions,
 ) -> Result<UpdateStatus> {
     // Download the object from the remote
-    safe_command(
-        format!("git fetch --quiet \"{remote_name}\" \"{revision}\"").as_str(),
+    git_fetch_revision(
         repo_path,
-    )?
-    .status()
-    .with_context(|| {
-        format!(
-            "Error with fetching revision {revision} in {}",
-            repo_path.display()
-        )
-    })?;
+        remote_name,
+        revision,
+        &depth_flag(options.depth),
+        &options.network,
+    )?;
 
     // Normalize the revision into the SHA.
-    let resolved = git_resolve_revision(repo_path, remote_name, revision)?;
+    let resolved = git_resolve_revision(
+        repo_path,
+        remote_name,
+        revision,
+        options.depth,
+        &options.network,
+    )?;
+
+    // Check the signature before anything is checked out, so a rejected
+    // commit never reaches the working tree.
+    if options.verify_signatures {
+        if let Some(status) = git_verify_commit(repo_path, &resolved.sha, options)? {
+            return Ok(status);
+        }
+    }
 
     // Build the checkout. Branches are checked out by name (with `-B`) to keep
     // HEAD attached; tags and specific SHAs are expected to detach HEAD.
@@ -506,6 +1194,68 @@ fn git_nul_paths(repo_path: &Path, command: &str) -> Result<Vec<String>> {
         .collect())
 }
 
+/// Reads the commit subjects in `from..to` and the files that differ between
+/// the two commits. Only trees are compared, so this works in a blob-filtered
+/// clone without fetching anything.
+fn git_changes(repo_path: &Path, from: &str, to: &str) -> Result<RepoChanges> {
+    let output = safe_command(&format!("git log --format=%s \"{from}..{to}\""), repo_path)?
+        .output()
+        .with_context(|| format!("Failed to read the log in {}", repo_path.display()))?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to read the log from {from} to {to} in {}:\n{}",
+            repo_path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    let subjects = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::to_string)
+        .collect();
+
+    // With `-z`, each entry is a status letter and a path, NUL-separated.
+    let entries = git_nul_paths(
+        repo_path,
+        &format!("git diff --name-status --no-renames -z \"{from}\" \"{to}\""),
+    )?;
+    let files = entries
+        .chunks_exact(2)
+        .filter_map(|entry| match entry {
+            [status, path] => {
+                let change = match status.as_str() {
+                    "A" => FileChange::Added,
+                    "D" => FileChange::Removed,
+                    _ => FileChange::Modified,
+                };
+                Some((change, path.clone()))
+            }
+            _ => None,
+        })
+        .collect();
+
+    Ok(RepoChanges { subjects, files })
+}
+
+fn git_untracked_files(target_dir: &Path) -> Result<Vec<PathBuf>> {
+    let output = safe_command("git ls-files --others --exclude-standard -z", target_dir)?
+        .stderr(Stdio::null())
+        .output()
+        .with_context(|| format!("Failed to execute process in {}", target_dir.display()))?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to list untracked files in {}",
+            target_dir.display()
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .split('\0')
+        .filter(|path| !path.is_empty())
+        .map(|path| target_dir.join(path))
+        .collect())
+}
+
 fn git_is_working_dir_clean(target_dir: &Path) -> Result<bool> {
     // We use the Git plumbing `status --porcelain` command to tell us of files that has changed,
     // both staged and unstaged.
@@ -516,3 +1266,113 @@ fn git_is_working_dir_clean(target_dir: &Path) -> Result<bool> {
     // With the --quiet flag, it will return a 0 exit-code if no files has changed.
     Ok(output.stdout.is_empty())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{classify, error_kind, git_network, git_resolve_revision, with_retries};
+    use crate::repo::{GitError, GitErrorKind, NetworkOptions};
+    use std::path::Path;
+    use std::process::Command;
+    use std::time::Duration;
+
+    #[test]
+    fn classify_recognises_common_git_failures() {
+        let cases = [
+            (
+                "fatal: Authentication failed for 'https://example.com/repo/'",
+                GitErrorKind::Auth,
+            ),
+            (
+                "fatal: could not read Username for 'https://example.com': terminal prompts disabled",
+                GitErrorKind::Auth,
+            ),
+            (
+                "remote: Repository not found.\nfatal: repository 'https://example.com/repo/' not found",
+                GitErrorKind::NotFound,
+            ),
+            (
+                "fatal: '/tmp/missing' does not appear to be a git repository",
+                GitErrorKind::NotFound,
+            ),
+            (
+                "fatal: couldn't find remote ref refs/heads/missing",
+                GitErrorKind::RevisionMissing,
+            ),
+            (
+                "fatal: unable to access 'https://example.com/repo/': Could not resolve host: example.com",
+                GitErrorKind::Network,
+            ),
+            ("fatal: the remote end hung up unexpectedly", GitErrorKind::Network),
+            ("error: something else", GitErrorKind::Other),
+        ];
+
+        for (stderr, kind) in cases {
+            assert_eq!(classify(stderr), kind, "{stderr}");
+        }
+    }
+
+    #[test]
+    fn git_network_times_out() {
+        let err = git_network(
+            Path::new("."),
+            "sleep 5",
+            "wait",
+            Some(Duration::from_millis(100)),
+        )
+        .unwrap_err();
+
+        assert_eq!(error_kind(&err), Some(GitErrorKind::Timeout));
+    }
+
+    #[test]
+    fn git_resolve_revision_classifies_ls_remote_failures() {
+        let repo = tempfile::tempdir().unwrap();
+        for args in [
+            &["init", "-q"][..],
+            &["remote", "add", "origin", "http://127.0.0.1:9/repo.git"],
+        ] {
+            let status = Command::new("git")
+                .args(args)
+                .current_dir(repo.path())
+                .status()
+                .unwrap();
+            assert!(status.success());
+        }
+        let network = NetworkOptions {
+            timeout: Some(Duration::from_secs(30)),
+            retries: 0,
+        };
+
+        let Err(err) = git_resolve_revision(repo.path(), "origin", "main", None, &network) else {
+            panic!("expected resolving against an unreachable remote to fail");
+        };
+
+        assert_eq!(error_kind(&err), Some(GitErrorKind::Network), "{err:#}");
+    }
+
+    #[test]
+    fn with_retries_only_retries_transient_failures() {
+        let network = NetworkOptions {
+            timeout: None,
+            retries: 1,
+        };
+        let failing = |kind| {
+            let mut attempts = 0;
+            let result: anyhow::Result<()> = with_retries(&network, || {
+                attempts += 1;
+                Err(GitError {
+                    kind,
+                    action: "fetch".to_string(),
+                    stderr: String::new(),
+                }
+                .into())
+            });
+            (attempts, result.unwrap_err())
+        };
+
+        let (attempts, err) = failing(GitErrorKind::Network);
+        assert_eq!(attempts, 2);
+        assert!(format!("{err:#}").starts_with("Gave up after 2 attempts"));
+        assert_eq!(failing(GitErrorKind::NotFound).0, 1);
+    }
+}
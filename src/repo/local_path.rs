@@ -0,0 +1,162 @@
+#![allow(clippy::module_name_repetitions)]
+
+use crate::constants::REPO_NAME;
+use crate::repo::git_shell::GitShellBackend;
+use crate::repo::{
+    CloneOptions, NetworkOptions, RepoChanges, RepositoryBackend, RevisionStatus, UpdateStatus,
+};
+use anyhow::{anyhow, Context, Result};
+use std::fs;
+use std::os::unix::fs::symlink;
+use std::path::{Path, PathBuf};
+
+/// Repository backend for sources that are a local directory rather than a Git
+/// URL. The directory belongs to the user, so it is never fetched, checked out
+/// or otherwise written to: `install` symlinks it into place and `update` only
+/// re-validates that symlink.
+pub struct LocalPathBackend;
+
+impl RepositoryBackend for LocalPathBackend {
+    /// Symlinks `target` to the directory at `path`. An existing symlink is
+    /// re-pointed, so a changed `path` in the config takes effect. `revision`
+    /// and `options` are ignored.
+    fn install(
+        &self,
+        path: &str,
+        target: &Path,
+        _revision: Option<&str>,
+        _options: &CloneOptions,
+    ) -> Result<()> {
+        let source = Path::new(path);
+        ensure_is_dir(source)?;
+
+        match fs::symlink_metadata(target) {
+            Ok(metadata) if metadata.file_type().is_symlink() => {
+                remove_symlink(target)?;
+            }
+            Ok(_) => {
+                return Err(anyhow!("\"{}\" is not a symlink, but according to your config it should be. Please remove this directory and try again", target.display()));
+            }
+            Err(_) => {
+                if let Some(parent) = target.parent() {
+                    fs::create_dir_all(parent)
+                        .with_context(|| format!("Failed to create {}", parent.display()))?;
+                }
+            }
+        }
+
+        symlink(source, target).with_context(|| {
+            format!(
+                "Failed to symlink {} to {}",
+                target.display(),
+                source.display()
+            )
+        })
+    }
+
+    /// Confirms `target` is still a symlink to the directory at `path`,
+    /// re-pointing it if the configured `path` changed. The directory itself is
+    /// left untouched whatever `allow_dirty` is.
+    fn update(
+        &self,
+        target: &Path,
+        path: &str,
+        _revision: Option<&str>,
+        _allow_dirty: bool,
+        _options: &CloneOptions,
+    ) -> Result<UpdateStatus> {
+        let source = Path::new(path);
+        ensure_is_dir(source)?;
+
+        let Ok(link) = fs::read_link(target) else {
+            return Err(anyhow!(
+                "\"{}\" is not a symlink to {}. Run `{REPO_NAME} install` and try again",
+                target.display(),
+                source.display()
+            ));
+        };
+
+        if link != source {
+            remove_symlink(target)?;
+            symlink(source, target).with_context(|| {
+                format!(
+                    "Failed to symlink {} to {}",
+                    target.display(),
+                    source.display()
+                )
+            })?;
+        }
+
+        Ok(UpdateStatus::Updated)
+    }
+
+    /// Reports the git status of the linked directory when it is a git
+    /// repository. A plain directory has no notion of uncommitted changes and
+    /// is always clean.
+    fn is_clean(&self, target: &Path) -> Result<bool> {
+        if target.join(".git").exists() {
+            GitShellBackend.is_clean(target)
+        } else {
+            Ok(true)
+        }
+    }
+
+    /// Returns the directory `target` links to.
+    fn origin_url(&self, target: &Path) -> Result<Option<String>> {
+        Ok(fs::read_link(target)
+            .ok()
+            .map(|link| link.to_string_lossy().into_owned()))
+    }
+
+    /// A local directory is used as-is, so there is no commit to pin.
+    fn head_commit(&self, _target: &Path) -> Result<Option<String>> {
+        Ok(None)
+    }
+
+    /// A local directory is always current: there is nothing upstream to be
+    /// behind.
+    fn check(
+        &self,
+        _target: &Path,
+        _path: &str,
+        _revision: Option<&str>,
+        _network: &NetworkOptions,
+    ) -> Result<RevisionStatus> {
+        Ok(RevisionStatus {
+            current: None,
+            target: None,
+            behind: 0,
+        })
+    }
+
+    /// The directory belongs to the user, so none of its files are reported
+    /// as something tinty may clean up.
+    fn untracked_files(&self, _target: &Path) -> Result<Vec<PathBuf>> {
+        Ok(Vec::new())
+    }
+
+    /// A local directory has no commits to compare.
+    fn changes(&self, _target: &Path, _from: &str, _to: &str) -> Result<RepoChanges> {
+        Ok(RepoChanges::default())
+    }
+}
+
+fn ensure_is_dir(source: &Path) -> Result<()> {
+    if source.is_dir() {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "{} is not a directory. Check the path in your config and try again",
+            source.display()
+        ))
+    }
+}
+
+fn remove_symlink(target: &Path) -> Result<()> {
+    fs::remove_file(target).map_err(|_| {
+        anyhow!(
+            "Error trying to remove symlink at \"{}\". Remove it manually and try again",
+            target.display()
+        )
+    })
+}
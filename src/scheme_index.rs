@@ -0,0 +1,489 @@
+//! Resolution of user-typed scheme names to scheme IDs.
+//!
+//! A scheme ID is `<system>-<slug>` (e.g. `base16-mocha`), which is how schemes
+//! are keyed on disk. Users also type bare slugs (`mocha`) or display names
+//! (`Catppuccin Mocha`); `SchemeIndex::resolve` accepts all three as long as
+//! they identify a single scheme, and otherwise fails with the closest IDs by
+//! edit distance. Names from the config's `[aliases]` table are resolved to
+//! their target first. `apply`, `info`, `cycle` and the gallery API all resolve
+//! names through here so they accept the same spellings.
+//!
+//! The official schemes repository and each `[[scheme-sources]]` entry are
+//! merged into one namespace: a scheme ID several of them have resolves to the
+//! one with the highest priority, and any of them can be named explicitly as
+//! `<source>:<id>` (or `<source>:<slug>`, `<source>:<name>`).
+
+use crate::config::Config;
+use crate::constants::{
+    CUSTOM_SCHEMES_DIR_NAME, REPO_NAME, SCHEMES_REPO_NAME, SCHEME_SOURCE_SEPARATOR,
+};
+use crate::paths;
+use crate::utils::get_all_scheme_file_paths;
+use anyhow::{anyhow, Context, Result};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use tinted_builder::SchemeSystem;
+use tinted_builder_rust::operation_build::utils::SchemeFile;
+
+/// How many "did you mean" suggestions to offer at most.
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Every scheme found in a set of scheme directories, keyed by scheme ID.
+pub struct SchemeIndex {
+    schemes: BTreeMap<String, SchemeFile>,
+    /// The named sources `schemes` was merged from, highest precedence first.
+    sources: Vec<(String, HashMap<String, SchemeFile>)>,
+    /// The source each scheme in `schemes` comes from. Custom schemes have
+    /// none.
+    origins: BTreeMap<String, String>,
+    aliases: BTreeMap<String, String>,
+}
+
+impl SchemeIndex {
+    /// Indexes the official schemes repo, every `[[scheme-sources]]` entry and
+    /// the custom schemes directory. Custom schemes keep shadowing the others,
+    /// as with `for_data_path`.
+    pub fn for_config(config: &Config, data_path: &Path) -> Self {
+        let mut index = Self::for_scheme_sources(config, data_path);
+        if let Ok(custom) =
+            get_all_scheme_file_paths(&data_path.join(CUSTOM_SCHEMES_DIR_NAME), None)
+        {
+            for id in custom.keys() {
+                index.origins.remove(id);
+            }
+            index.schemes.extend(custom);
+        }
+
+        index
+    }
+
+    /// Indexes the official schemes repo and every `[[scheme-sources]]` entry,
+    /// leaving out custom schemes. The official repo has priority 0, and ties
+    /// go to whichever comes first, the official repo before any source.
+    pub fn for_scheme_sources(config: &Config, data_path: &Path) -> Self {
+        let mut source_dirs = vec![(
+            SCHEMES_REPO_NAME.to_string(),
+            paths::schemes_repo_path(data_path),
+            0,
+        )];
+        source_dirs.extend(config.scheme_sources.iter().flatten().map(|source| {
+            (
+                source.name.clone(),
+                paths::item_repo_path(data_path, &source.name),
+                source.priority,
+            )
+        }));
+        // A stable sort, so equal priorities keep their config order.
+        source_dirs.sort_by_key(|(_, _, priority)| Reverse(*priority));
+
+        let sources: Vec<(String, HashMap<String, SchemeFile>)> = source_dirs
+            .into_iter()
+            .map(|(name, dir, _)| {
+                let schemes = get_all_scheme_file_paths(&dir, None).unwrap_or_default();
+                (name, schemes)
+            })
+            .collect();
+
+        let mut schemes = BTreeMap::new();
+        let mut origins = BTreeMap::new();
+        for (name, source_schemes) in sources.iter().rev() {
+            for (id, file) in source_schemes {
+                schemes.insert(id.clone(), file.clone());
+                origins.insert(id.clone(), name.clone());
+            }
+        }
+
+        Self {
+            schemes,
+            sources,
+            origins,
+            aliases: BTreeMap::new(),
+        }
+    }
+
+    /// Indexes the built-in schemes repo and the custom schemes directory.
+    pub fn for_data_path(data_path: &Path) -> Self {
+        Self::from_dirs(&[
+            paths::schemes_repo_path(data_path),
+            data_path.join(CUSTOM_SCHEMES_DIR_NAME),
+        ])
+    }
+
+    /// Indexes the given scheme directories; directories that do not exist are
+    /// skipped.
+    pub fn from_dirs(scheme_dirs: &[PathBuf]) -> Self {
+        let schemes = scheme_dirs
+            .iter()
+            .filter_map(|dir| get_all_scheme_file_paths(dir, None).ok())
+            .flatten()
+            .collect();
+
+        Self {
+            schemes,
+            sources: Vec::new(),
+            origins: BTreeMap::new(),
+            aliases: BTreeMap::new(),
+        }
+    }
+
+    /// Also resolves the names in `aliases` (the config's `[aliases]` table) to
+    /// the scheme each one points at.
+    pub fn with_aliases(mut self, aliases: &BTreeMap<String, String>) -> Self {
+        self.aliases.clone_from(aliases);
+        self
+    }
+
+    /// Whether `scheme_id` is a scheme in the index.
+    pub fn contains(&self, scheme_id: &str) -> bool {
+        self.schemes.contains_key(scheme_id)
+    }
+
+    /// The scheme file for a name returned by `resolve`: a scheme ID or
+    /// `<source>:<id>`.
+    pub fn get(&self, scheme_name: &str) -> Option<&SchemeFile> {
+        match scheme_name.split_once(SCHEME_SOURCE_SEPARATOR) {
+            Some((source, id)) => self.source_schemes(source)?.get(id),
+            None => self.schemes.get(scheme_name),
+        }
+    }
+
+    /// The source a name returned by `resolve` comes from, or `None` for a
+    /// custom scheme.
+    pub fn source_of<'a>(&'a self, scheme_name: &'a str) -> Option<&'a str> {
+        match scheme_name.split_once(SCHEME_SOURCE_SEPARATOR) {
+            Some((source, _)) => Some(source),
+            None => self.origins.get(scheme_name).map(String::as_str),
+        }
+    }
+
+    /// Every scheme's name, sorted: the ID of each scheme that wins its ID,
+    /// then `<source>:<id>` for each one another source shadows.
+    pub fn names(&self) -> Vec<String> {
+        let mut shadowed: Vec<String> = self
+            .sources
+            .iter()
+            .flat_map(|(source, schemes)| {
+                schemes
+                    .keys()
+                    .filter(|id| self.origins.get(*id) != Some(source))
+                    .map(move |id| format!("{source}{SCHEME_SOURCE_SEPARATOR}{id}"))
+            })
+            .collect();
+        shadowed.sort();
+
+        self.schemes.keys().cloned().chain(shadowed).collect()
+    }
+
+    fn source_schemes(&self, source: &str) -> Option<&HashMap<String, SchemeFile>> {
+        self.sources
+            .iter()
+            .find(|(name, _)| name == source)
+            .map(|(_, schemes)| schemes)
+    }
+
+    /// The aliases whose target resolves to `scheme_id`, in name order.
+    pub fn aliases_for(&self, scheme_id: &str) -> Vec<&str> {
+        self.aliases
+            .iter()
+            .filter(|(_, target)| self.resolve_scheme(target).is_ok_and(|id| id == scheme_id))
+            .map(|(alias, _)| alias.as_str())
+            .collect()
+    }
+
+    /// Resolves `input` to a scheme ID. In order, `input` may be:
+    ///
+    /// 1. a scheme ID (`base16-mocha`),
+    /// 2. a slug without the system prefix (`mocha`), if only one system has it,
+    /// 3. a scheme display name, matched case-insensitively, if only one scheme
+    ///    has it.
+    ///
+    /// An alias is checked before all of these, and its target may use any of
+    /// the spellings above, but not another alias.
+    pub fn resolve(&self, input: &str) -> Result<String> {
+        let input = input.trim();
+
+        if let Some(target) = self.aliases.get(input) {
+            return self.resolve_scheme(target).with_context(|| {
+                format!("Alias \"{input}\" points at \"{target}\", which is not a scheme")
+            });
+        }
+
+        self.resolve_scheme(input)
+    }
+
+    fn resolve_scheme(&self, input: &str) -> Result<String> {
+        let input = input.trim();
+
+        if let Some((source, name)) = input.split_once(SCHEME_SOURCE_SEPARATOR) {
+            if let Some(source_schemes) = self.source_schemes(source) {
+                let id = match_in(source_schemes, name.trim())?.ok_or_else(|| {
+                    anyhow!("Scheme does not exist: {input}\nRun `{REPO_NAME} list` to see the available schemes")
+                })?;
+
+                // The winning scheme keeps its plain ID, so applying it either
+                // way records the same name.
+                return Ok(
+                    if self.origins.get(&id).map(String::as_str) == Some(source) {
+                        id
+                    } else {
+                        format!("{source}{SCHEME_SOURCE_SEPARATOR}{id}")
+                    },
+                );
+            }
+        }
+
+        match_in(&self.schemes, input)?.ok_or_else(|| self.not_found_error(input))
+    }
+
+    fn not_found_error(&self, input: &str) -> anyhow::Error {
+        let suggestions = self.suggestions(input);
+
+        if suggestions.is_empty() {
+            anyhow!("Scheme does not exist: {input}\nRun `{REPO_NAME} list` to see the available schemes")
+        } else {
+            anyhow!(
+                "Scheme does not exist: {input}\nDid you mean: {}?",
+                suggestions.join(", ")
+            )
+        }
+    }
+
+    /// The scheme IDs closest to `input` by edit distance between slugs. A
+    /// system prefix on `input` that differs from the scheme's costs one edit,
+    /// and a missing one costs nothing.
+    fn suggestions(&self, input: &str) -> Vec<String> {
+        let input = input.to_lowercase();
+        let input_slug = slug_of(&input).unwrap_or(&input);
+        let input_system = input.strip_suffix(input_slug);
+        let max_distance = (input_slug.chars().count() / 3).max(2);
+        let mut scored: Vec<(usize, &String)> = self
+            .schemes
+            .keys()
+            .filter_map(|id| {
+                let slug = slug_of(id)?;
+                let distance = strsim::levenshtein(input_slug, &slug.to_lowercase());
+                let system_penalty = usize::from(
+                    input_system
+                        .is_some_and(|system| !system.is_empty() && !id.starts_with(system)),
+                );
+                Some((distance.saturating_add(system_penalty), id))
+            })
+            .filter(|(distance, _)| *distance <= max_distance)
+            .collect();
+
+        scored.sort();
+        scored
+            .into_iter()
+            .take(MAX_SUGGESTIONS)
+            .map(|(_, id)| id.clone())
+            .collect()
+    }
+}
+
+/// Matches `input` against `schemes` as a scheme ID, then as a slug, then as a
+/// display name, as described on `SchemeIndex::resolve`.
+fn match_in<'a, S>(schemes: S, input: &str) -> Result<Option<String>>
+where
+    S: IntoIterator<Item = (&'a String, &'a SchemeFile)> + Copy,
+{
+    if let Some((id, _)) = schemes.into_iter().find(|(id, _)| *id == input) {
+        return Ok(Some(id.clone()));
+    }
+
+    let mut slug_matches: Vec<&String> = schemes
+        .into_iter()
+        .map(|(id, _)| id)
+        .filter(|id| slug_of(id) == Some(input))
+        .collect();
+    slug_matches.sort();
+    if let Some(id) = single_match(input, &slug_matches)? {
+        return Ok(Some(id));
+    }
+
+    let mut name_matches: Vec<&String> = schemes
+        .into_iter()
+        .filter(|(_, file)| {
+            file.get_scheme()
+                .is_ok_and(|scheme| scheme.get_scheme_name().eq_ignore_ascii_case(input))
+        })
+        .map(|(id, _)| id)
+        .collect();
+    name_matches.sort();
+    single_match(input, &name_matches)
+}
+
+/// The slug part of a scheme ID, i.e. the ID without its `<system>-` prefix.
+fn slug_of(id: &str) -> Option<&str> {
+    SchemeSystem::variants()
+        .iter()
+        .find_map(|system| id.strip_prefix(system.as_str())?.strip_prefix('-'))
+}
+
+/// The only ID in `matches`, `None` if there are none, or an error listing
+/// them if `input` is ambiguous.
+fn single_match(input: &str, matches: &[&String]) -> Result<Option<String>> {
+    match matches {
+        [] => Ok(None),
+        [id] => Ok(Some((*id).clone())),
+        _ => Err(anyhow!(
+            "Scheme name \"{input}\" is ambiguous, it matches: {}\nUse the full scheme name, e.g. `{REPO_NAME} apply {}`",
+            matches
+                .iter()
+                .map(|id| id.as_str())
+                .collect::<Vec<_>>()
+                .join(", "),
+            matches.first().map_or("", |id| id.as_str()),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SchemeIndex;
+    use crate::config::Config;
+    use crate::utils::write_to_file;
+    use std::collections::BTreeMap;
+    use std::fmt::Write as _;
+    use std::path::Path;
+
+    fn write_scheme(dir: &Path, system: &str, slug: &str, name: &str) {
+        let mut palette = String::new();
+        for index in 0..16 {
+            writeln!(palette, "  base0{index:X}: '#808080'").unwrap();
+        }
+        write_to_file(
+            dir.join(system).join(format!("{slug}.yaml")),
+            &format!(
+                "system: {system}\nname: {name}\nslug: {slug}\nauthor: Test\nvariant: dark\npalette:\n{palette}"
+            ),
+        )
+        .unwrap();
+    }
+
+    fn index() -> (SchemeIndex, tempfile::TempDir) {
+        let temp = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(temp.path().join("base16")).unwrap();
+        std::fs::create_dir_all(temp.path().join("base24")).unwrap();
+        write_scheme(temp.path(), "base16", "mocha", "Mocha");
+        write_scheme(
+            temp.path(),
+            "base16",
+            "catppuccin-mocha",
+            "Catppuccin Mocha",
+        );
+        write_scheme(temp.path(), "base16", "nord", "Nord");
+        write_scheme(temp.path(), "base24", "nord", "Nord");
+
+        (SchemeIndex::from_dirs(&[temp.path().to_path_buf()]), temp)
+    }
+
+    #[test]
+    fn resolves_ids_slugs_and_names() {
+        let (index, _temp) = index();
+
+        assert_eq!(index.resolve("base24-nord").unwrap(), "base24-nord");
+        assert_eq!(index.resolve("mocha").unwrap(), "base16-mocha");
+        assert_eq!(
+            index.resolve("catppuccin mocha").unwrap(),
+            "base16-catppuccin-mocha"
+        );
+    }
+
+    #[test]
+    fn ambiguous_slug_lists_every_match() {
+        let (index, _temp) = index();
+        let err = index.resolve("nord").unwrap_err().to_string();
+
+        assert!(err.contains("ambiguous"), "{err}");
+        assert!(err.contains("base16-nord, base24-nord"), "{err}");
+    }
+
+    #[test]
+    fn unknown_name_suggests_closest_matches() {
+        let (index, _temp) = index();
+        let err = index.resolve("base16-moca").unwrap_err().to_string();
+
+        assert!(
+            err.starts_with("Scheme does not exist: base16-moca"),
+            "{err}"
+        );
+        assert!(err.contains("Did you mean: base16-mocha?"), "{err}");
+    }
+
+    #[test]
+    fn aliases_resolve_to_their_target() {
+        let (index, _temp) = index();
+        let aliases = BTreeMap::from([
+            ("night".to_string(), "base24-nord".to_string()),
+            ("cosy".to_string(), "Catppuccin Mocha".to_string()),
+            ("broken".to_string(), "base16-nope".to_string()),
+        ]);
+        let index = index.with_aliases(&aliases);
+
+        assert_eq!(index.resolve("night").unwrap(), "base24-nord");
+        assert_eq!(index.resolve("cosy").unwrap(), "base16-catppuccin-mocha");
+        assert_eq!(index.aliases_for("base16-catppuccin-mocha"), vec!["cosy"]);
+
+        let err = format!("{:#}", index.resolve("broken").unwrap_err());
+        assert!(
+            err.starts_with("Alias \"broken\" points at \"base16-nope\""),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn scheme_sources_merge_by_priority() {
+        let temp = tempfile::tempdir().unwrap();
+        let repos = temp.path().join("repos");
+        for source in ["schemes", "company", "fork"] {
+            std::fs::create_dir_all(repos.join(source).join("base16")).unwrap();
+        }
+        write_scheme(&repos.join("schemes"), "base16", "mocha", "Mocha");
+        write_scheme(&repos.join("schemes"), "base16", "nord", "Nord");
+        write_scheme(&repos.join("company"), "base16", "mocha", "Company Mocha");
+        write_scheme(&repos.join("company"), "base16", "brand", "Brand");
+        write_scheme(&repos.join("fork"), "base16", "mocha", "Fork Mocha");
+        write_scheme(&repos.join("fork"), "base16", "nord", "Fork Nord");
+        let config: Config = toml::from_str(
+            "[[scheme-sources]]\nname = \"fork\"\npath = \"/fork\"\n\n[[scheme-sources]]\nname = \"company\"\npath = \"/company\"\npriority = 10\n",
+        )
+        .unwrap();
+        let index = SchemeIndex::for_scheme_sources(&config, temp.path());
+
+        assert_eq!(index.resolve("mocha").unwrap(), "base16-mocha");
+        assert_eq!(index.source_of("base16-mocha"), Some("company"));
+        // The official repo wins a tie with a source.
+        assert_eq!(index.source_of("base16-nord"), Some("schemes"));
+        assert_eq!(
+            index.resolve("company:base16-mocha").unwrap(),
+            "base16-mocha"
+        );
+        assert_eq!(index.resolve("fork:nord").unwrap(), "fork:base16-nord");
+        assert_eq!(
+            index.resolve("schemes:Mocha").unwrap(),
+            "schemes:base16-mocha"
+        );
+        assert_eq!(
+            index
+                .get("fork:base16-nord")
+                .unwrap()
+                .get_scheme()
+                .unwrap()
+                .get_scheme_name(),
+            "Fork Nord"
+        );
+        assert!(index.resolve("fork:brand").is_err());
+        assert_eq!(
+            index.names(),
+            vec![
+                "base16-brand",
+                "base16-mocha",
+                "base16-nord",
+                "fork:base16-mocha",
+                "fork:base16-nord",
+                "schemes:base16-mocha",
+            ]
+        );
+    }
+}
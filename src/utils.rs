@@ -1,12 +1,14 @@
 #![allow(clippy::arithmetic_side_effects)]
 use crate::config::{Config, ConfigItem, ConfigRing, DEFAULT_CONFIG_SHELL};
-use crate::constants::REPO_NAME;
+use crate::constants::{HOSTNAME_ENV_VAR, REPO_NAME};
 use anyhow::{anyhow, Context, Result};
 use home::home_dir;
+use regex::Regex;
 use std::collections::HashMap;
 use std::fs::{self, File};
 use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::process::Command;
 use tinted_builder::SchemeSystem;
 use tinted_builder_rust::operation_build::utils::{get_scheme_files_by_name, SchemeFile};
 
@@ -32,10 +34,10 @@ pub fn write_to_file(path: impl AsRef<Path>, contents: &str) -> Result<()> {
     Ok(())
 }
 
-pub fn get_shell_command_from_string(config_path: &Path, command: &str) -> Result<Vec<String>> {
-    let config = Config::read(config_path)?;
+pub fn get_shell_command_from_string(config: &Config, command: &str) -> Result<Vec<String>> {
     let shell = config
         .shell
+        .clone()
         .unwrap_or_else(|| DEFAULT_CONFIG_SHELL.to_string());
     let full_command = shell.replace("{}", command);
 
@@ -195,3 +197,178 @@ pub fn cycle_scheme_list(config: &Config, requested_ring: Option<&str>) -> Resul
 
     Ok(ring.schemes.clone())
 }
+
+/// A regex matching the whole of a string against `pattern`, in which `*`
+/// matches any run of characters and `?` any single character.
+pub fn wildcard_regex(pattern: &str) -> Result<Regex> {
+    Ok(Regex::new(&format!(
+        "^{}$",
+        regex::escape(pattern)
+            .replace(r"\*", ".*")
+            .replace(r"\?", ".")
+    ))?)
+}
+
+/// `TINTY_HOSTNAME`, or the machine's host name up to the first `.`.
+pub fn hostname() -> Result<String> {
+    if let Some(hostname) = std::env::var(HOSTNAME_ENV_VAR)
+        .ok()
+        .filter(|hostname| !hostname.is_empty())
+    {
+        return Ok(hostname);
+    }
+
+    let output = fs::read_to_string("/proc/sys/kernel/hostname")
+        .ok()
+        .or_else(|| {
+            Command::new("hostname")
+                .output()
+                .ok()
+                .filter(|output| output.status.success())
+                .map(|output| String::from_utf8_lossy(&output.stdout).into_owned())
+        })
+        .unwrap_or_default();
+    let hostname = output.trim().split('.').next().unwrap_or_default();
+    if hostname.is_empty() {
+        return Err(anyhow!(
+            "Unable to determine the host name. Set {HOSTNAME_ENV_VAR} instead"
+        ));
+    }
+
+    Ok(hostname.to_string())
+}
+
+/// Whether `command` is an executable file, when it is a path, or is found in
+/// one of the `PATH` directories.
+pub fn command_exists(command: &str) -> bool {
+    if command.contains(['/', '\\']) {
+        return is_executable(Path::new(command));
+    }
+
+    std::env::var_os("PATH").is_some_and(|paths| {
+        std::env::split_paths(&paths).any(|dir| is_executable(&dir.join(command)))
+    })
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::metadata(path)
+        .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file() || path.with_extension("exe").is_file()
+}
+
+/// Expands `$NAME`, `${NAME}` and `${NAME:-default}` in `value` from the
+/// environment, where `default` is used when `NAME` is unset or empty and is
+/// expanded itself. `$$` is a literal `$`. An unset variable or another
+/// `${...}` form is an error.
+pub fn interpolate_env_vars(value: &str) -> Result<String> {
+    let not_set = |name: &str| {
+        anyhow!("${name} is not set. Set it, give a default with ${{{name}:-default}}, or write $$ for a literal $")
+    };
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '$' {
+            out.push(c);
+            continue;
+        }
+
+        match chars.peek().copied() {
+            Some('$') => {
+                chars.next();
+                out.push('$');
+            }
+            Some('{') => {
+                chars.next();
+                let mut inner = String::new();
+                let mut depth = 0;
+                loop {
+                    match chars.next() {
+                        None => return Err(anyhow!("${{{inner} is missing its closing }}")),
+                        Some('}') if depth == 0 => break,
+                        Some(c) => {
+                            if c == '{' {
+                                depth += 1;
+                            } else if c == '}' {
+                                depth -= 1;
+                            }
+                            inner.push(c);
+                        }
+                    }
+                }
+
+                let name_len = inner
+                    .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                    .unwrap_or(inner.len());
+                let (name, rest) = inner.split_at(name_len);
+                let is_supported =
+                    is_variable_name(name) && (rest.is_empty() || rest.starts_with(":-"));
+                if !is_supported {
+                    return Err(anyhow!(
+                        "${{{inner}}} is not supported. Use ${{NAME}} or ${{NAME:-default}}"
+                    ));
+                }
+
+                let value = std::env::var(name).ok();
+                match (value, rest.strip_prefix(":-")) {
+                    (Some(value), Some(_)) if !value.is_empty() => out.push_str(&value),
+                    (_, Some(default)) => out.push_str(&interpolate_env_vars(default)?),
+                    (Some(value), None) => out.push_str(&value),
+                    (None, None) => return Err(not_set(name)),
+                }
+            }
+            Some(next) if next.is_ascii_alphabetic() || next == '_' => {
+                let mut name = String::new();
+                while let Some(c) = chars.next_if(|c| c.is_ascii_alphanumeric() || *c == '_') {
+                    name.push(c);
+                }
+                out.push_str(&std::env::var(&name).map_err(|_| not_set(&name))?);
+            }
+            _ => out.push('$'),
+        }
+    }
+
+    Ok(out)
+}
+
+fn is_variable_name(name: &str) -> bool {
+    name.chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::interpolate_env_vars;
+
+    #[test]
+    fn interpolate_env_vars_expands_and_defaults() {
+        let home = std::env::var("HOME").unwrap();
+
+        assert_eq!(
+            interpolate_env_vars("$HOME/a ${HOME}/b").unwrap(),
+            format!("{home}/a {home}/b")
+        );
+        assert_eq!(
+            interpolate_env_vars("${TINTY_TEST_UNSET:-$HOME/.config}/tinty").unwrap(),
+            format!("{home}/.config/tinty")
+        );
+        assert_eq!(
+            interpolate_env_vars("cost: $$5, $1 and $(date)").unwrap(),
+            "cost: $5, $1 and $(date)"
+        );
+        assert!(interpolate_env_vars("$TINTY_TEST_UNSET")
+            .unwrap_err()
+            .to_string()
+            .starts_with("$TINTY_TEST_UNSET is not set"));
+        assert!(interpolate_env_vars("${HOME").is_err());
+        assert!(interpolate_env_vars("${HOME%/}").is_err());
+    }
+}
@@ -0,0 +1,652 @@
+//! `tinty config validate`: every problem in the config files at once, each
+//! with the file, line and column it is at.
+//!
+//! Each file `include` reads is checked on its own. It is deserialized as a
+//! config to find the keys `Config::read` would ignore, and parsed with
+//! `toml_edit`, which keeps the position of every key and value, to place
+//! them and to find invalid `supported-systems`, `write-to-file` arrays of the
+//! wrong length, duplicate names and `shell` values without the `{}`
+//! placeholder. The merged config is then read as every other command
+//! reads it, and the scheme names used by `default-scheme` and rings are
+//! looked up among the installed schemes.
+
+use crate::config::{
+    self, Config, ConfigDerivedScheme, ConfigItem, ConfigProfile, ConfigRing, ConfigSchemeSource,
+    DeriveOperation, ItemCondition, SchemesConfig,
+};
+use crate::includes;
+use crate::paths;
+use crate::scheme_index::SchemeIndex;
+use std::fmt;
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use tinted_builder::SchemeSystem;
+use toml_edit::{ImDocument, Item, TableLike, Value};
+
+/// How far off an unknown key may be from a known one to be suggested.
+const MAX_SUGGESTION_DISTANCE: usize = 2;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// One problem, and where it is: a 1-based line and column, when known.
+#[derive(Debug)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub file: PathBuf,
+    pub position: Option<(usize, usize)>,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+
+        match self.position {
+            Some((line, column)) => write!(
+                f,
+                "{}:{line}:{column}: {severity}: {}",
+                self.file.display(),
+                self.message
+            ),
+            None => write!(f, "{}: {severity}: {}", self.file.display(), self.message),
+        }
+    }
+}
+
+/// A scheme name the config uses, to look up once the schemes are known.
+struct SchemeReference {
+    key: String,
+    name: String,
+    file: PathBuf,
+    position: Option<(usize, usize)>,
+}
+
+/// Checks one file, collecting what it finds.
+struct FileChecker<'a> {
+    file: &'a Path,
+    contents: &'a str,
+    diagnostics: Vec<Diagnostic>,
+    scheme_references: Vec<SchemeReference>,
+    /// Whether a problem was found that also makes `Config::read` fail.
+    is_unreadable: bool,
+}
+
+/// Every problem in the config at `config_path` and the files it includes.
+pub fn validate(config_path: &Path, data_path: &Path) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut scheme_references = Vec::new();
+    let mut is_unreadable = false;
+
+    let (files, include_error) = match includes::load(config_path) {
+        Ok(merged) => (merged.files, None),
+        Err(err) => (vec![config_path.to_path_buf()], Some(err)),
+    };
+    for file in &files {
+        let Ok(contents) = fs::read_to_string(file) else {
+            continue;
+        };
+        let mut checker = FileChecker {
+            file,
+            contents: &contents,
+            diagnostics: Vec::new(),
+            scheme_references: Vec::new(),
+            is_unreadable: false,
+        };
+        checker.check();
+        diagnostics.append(&mut checker.diagnostics);
+        scheme_references.append(&mut checker.scheme_references);
+        is_unreadable |= checker.is_unreadable;
+    }
+
+    let config = match (include_error, Config::read(config_path)) {
+        (None, Ok(config)) => Some(config),
+        (Some(err), _) | (None, Err(err)) => {
+            if !is_unreadable {
+                diagnostics.push(Diagnostic {
+                    severity: Severity::Error,
+                    file: config_path.to_path_buf(),
+                    position: None,
+                    message: format!("{err:#}"),
+                });
+            }
+            None
+        }
+    };
+
+    if let Some(config) = config {
+        diagnostics.extend(check_scheme_references(
+            &config,
+            data_path,
+            config_path,
+            &scheme_references,
+        ));
+    }
+
+    diagnostics
+}
+
+/// Looks up every scheme name the config uses among the installed schemes
+/// and the derived schemes.
+fn check_scheme_references(
+    config: &Config,
+    data_path: &Path,
+    config_path: &Path,
+    references: &[SchemeReference],
+) -> Vec<Diagnostic> {
+    if references.is_empty() {
+        return Vec::new();
+    }
+    if !paths::schemes_repo_path(data_path).is_dir() {
+        return vec![Diagnostic {
+            severity: Severity::Warning,
+            file: config_path.to_path_buf(),
+            position: None,
+            message: "Scheme names were not checked, since the schemes are not installed. Run `tinty install` first".to_string(),
+        }];
+    }
+
+    let index = SchemeIndex::for_config(config, data_path).with_aliases(&config.aliases);
+    // Derived schemes are only written by `install`, so they are matched by
+    // their slug or `<system>-<slug>` id rather than looked up.
+    let derived_names: Vec<String> = config
+        .derived_schemes
+        .iter()
+        .flatten()
+        .flat_map(|derived| {
+            SchemeSystem::variants()
+                .iter()
+                .map(|system| format!("{system}-{}", derived.slug))
+                .chain([derived.slug.clone()])
+        })
+        .collect();
+
+    references
+        .iter()
+        .filter(|reference| !derived_names.contains(&reference.name))
+        .filter_map(|reference| {
+            let err = index.resolve(&reference.name).err()?;
+            Some(Diagnostic {
+                severity: Severity::Error,
+                file: reference.file.clone(),
+                position: reference.position,
+                message: format!(
+                    "{} uses \"{}\", which is not installed. {}",
+                    reference.key,
+                    reference.name,
+                    err.to_string().replace('\n', ". ")
+                ),
+            })
+        })
+        .collect()
+}
+
+impl FileChecker<'_> {
+    fn check(&mut self) {
+        let document = match ImDocument::parse(self.contents) {
+            Ok(document) => document,
+            Err(err) => {
+                self.is_unreadable = true;
+                self.error(err.span(), err.message().trim().to_string());
+                return;
+            }
+        };
+        let root = document.as_table();
+
+        self.check_unknown_keys(root);
+        self.check_shell(root, "shell");
+        self.reference_scheme(root.get("default-scheme"), "default-scheme");
+
+        if let Some(items) = root.get("items") {
+            self.check_items(items);
+        }
+        if let Some(rings) = root.get("rings") {
+            let rings = entries(rings);
+            self.check_unique_names(&rings, "name", "[[rings]]", false);
+            for ring in rings {
+                let name = ring.get("name").and_then(Item::as_str).unwrap_or_default();
+                let schemes = ring.get("schemes").and_then(Item::as_array);
+                for scheme in schemes.into_iter().flatten() {
+                    self.reference_scheme_value(scheme, &format!("Ring \"{name}\""));
+                }
+            }
+        }
+        if let Some(sources) = root.get("scheme-sources") {
+            let sources = entries(sources);
+            self.check_unique_names(&sources, "name", "[[scheme-sources]]", true);
+        }
+        if let Some(profiles) = root.get("profiles").and_then(Item::as_table_like) {
+            for (name, profile) in profiles.iter() {
+                let Some(profile) = profile.as_table_like() else {
+                    continue;
+                };
+                let context = format!("[profiles.{name}]");
+                self.check_shell(profile, &format!("{context} shell"));
+                self.reference_scheme(
+                    profile.get("default-scheme"),
+                    &format!("{context} default-scheme"),
+                );
+            }
+        }
+    }
+
+    fn check_items(&mut self, items: &Item) {
+        let items = entries(items);
+        self.check_unique_names(&items, "name", "[[items]]", true);
+
+        for item in items {
+            let systems = item.get("supported-systems").and_then(Item::as_array);
+            for system in systems.into_iter().flatten() {
+                let is_valid = system
+                    .as_str()
+                    .is_some_and(|system| SchemeSystem::from_str(system).is_ok());
+                if !is_valid {
+                    self.is_unreadable = true;
+                    let valid: Vec<String> = SchemeSystem::variants()
+                        .iter()
+                        .map(|system| format!("\"{system}\""))
+                        .collect();
+                    self.error(
+                        system.span(),
+                        format!(
+                            "supported-systems has {}, which is not a scheme system. Use {}",
+                            system.to_string().trim(),
+                            valid.join(", ")
+                        ),
+                    );
+                }
+            }
+
+            if let Some(write_to_file) = item.get("write-to-file") {
+                let len = write_to_file.as_array().map_or(0, toml_edit::Array::len);
+                if !(1..=3).contains(&len) {
+                    self.error(
+                        write_to_file.span(),
+                        "write-to-file should be [\"<file>\"], [\"<file>\", \"<start marker>\"] or [\"<file>\", \"<start marker>\", \"<end marker>\"]".to_string(),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Reports the keys deserializing the file as a config ignores, with the
+    /// closest known key when there is one.
+    fn check_unknown_keys(&mut self, root: &toml_edit::Table) {
+        let Ok(mut table) = toml::from_str::<toml::Table>(self.contents) else {
+            return;
+        };
+        table.remove(includes::INCLUDE_KEY);
+
+        // One value at a time, so that a value that doesn't deserialize, or an
+        // `[[items]]` entry an included file only adds a `hook` to, doesn't
+        // hide the keys next to it. Errors are left to `Config::read`, as the
+        // merged config may well deserialize.
+        let mut unknown: Vec<Vec<String>> = Vec::new();
+        for (path, probe) in probes(toml::Value::Table(table)) {
+            let toml::Value::Table(probe) = probe else {
+                continue;
+            };
+            let _ = config::deserialize(probe, |ignored| {
+                let ignored = path.get(..ignored.len()).unwrap_or_default().to_vec();
+                if !unknown.contains(&ignored) {
+                    unknown.push(ignored);
+                }
+            });
+        }
+        unknown.sort_by_key(|path| key_span(root, path).map(|span| span.start));
+
+        for path in unknown {
+            let Some((key, parent)) = path.split_last() else {
+                continue;
+            };
+            let (context, known, is_fatal) = table_kind(parent);
+            let suggestion = known
+                .iter()
+                .map(|known| (strsim::levenshtein(key, known), known))
+                .filter(|(distance, _)| *distance <= MAX_SUGGESTION_DISTANCE)
+                .min_by_key(|(distance, _)| *distance)
+                .map(|(_, known)| format!(". Did you mean `{known}`?"))
+                .unwrap_or_default();
+            self.is_unreadable |= is_fatal;
+            self.error(
+                key_span(root, &path),
+                format!("Unknown key `{key}` in {context}{suggestion}"),
+            );
+        }
+    }
+
+    /// Reports entries that reuse the `name_key` of an earlier entry.
+    fn check_unique_names(
+        &mut self,
+        entries: &[&dyn TableLike],
+        name_key: &str,
+        context: &str,
+        is_fatal: bool,
+    ) {
+        let mut seen: Vec<(&str, Option<(usize, usize)>)> = Vec::new();
+
+        for entry in entries {
+            let Some(name) = entry.get(name_key) else {
+                continue;
+            };
+            let Some(value) = name.as_str() else {
+                continue;
+            };
+            let position = self.position(name.span());
+
+            if let Some((_, first)) = seen.iter().find(|(seen, _)| *seen == value) {
+                let first = first.map_or_else(String::new, |(line, _)| format!(" on line {line}"));
+                self.is_unreadable |= is_fatal;
+                self.error(
+                    name.span(),
+                    format!("{context} {name_key} \"{value}\" is already used{first}"),
+                );
+            } else {
+                seen.push((value, position));
+            }
+        }
+    }
+
+    fn check_shell(&mut self, table: &dyn TableLike, key: &str) {
+        let Some(shell) = table.get("shell") else {
+            return;
+        };
+        if shell.as_str().is_some_and(|shell| !shell.contains("{}")) {
+            self.is_unreadable = true;
+            self.error(
+                shell.span(),
+                format!("{key} needs a {{}} placeholder for the command, e.g. \"sh -c '{{}}'\""),
+            );
+        }
+    }
+
+    fn reference_scheme(&mut self, item: Option<&Item>, key: &str) {
+        if let Some(value) = item.and_then(Item::as_value) {
+            self.reference_scheme_value(value, key);
+        }
+    }
+
+    fn reference_scheme_value(&mut self, value: &Value, key: &str) {
+        if let Some(name) = value.as_str() {
+            self.scheme_references.push(SchemeReference {
+                key: key.to_string(),
+                name: name.to_string(),
+                file: self.file.to_path_buf(),
+                position: self.position(value.span()),
+            });
+        }
+    }
+
+    fn error(&mut self, span: Option<Range<usize>>, message: String) {
+        self.diagnostics.push(Diagnostic {
+            severity: Severity::Error,
+            file: self.file.to_path_buf(),
+            position: self.position(span),
+            message,
+        });
+    }
+
+    /// The 1-based line and column where `span` starts.
+    fn position(&self, span: Option<Range<usize>>) -> Option<(usize, usize)> {
+        let before = self.contents.get(..span?.start)?;
+        let line = before.matches('\n').count().checked_add(1)?;
+        let column = before
+            .rsplit('\n')
+            .next()
+            .unwrap_or_default()
+            .chars()
+            .count()
+            .checked_add(1)?;
+
+        Some((line, column))
+    }
+}
+
+/// `value` cut down to each value in it that is not a table or an array of
+/// tables, with the path to that value.
+fn probes(value: toml::Value) -> Vec<(Vec<String>, toml::Value)> {
+    let nested: Vec<(String, toml::Value, bool)> = match value {
+        toml::Value::Table(table) if !table.is_empty() => table
+            .into_iter()
+            .map(|(key, value)| (key, value, false))
+            .collect(),
+        toml::Value::Array(entries)
+            if !entries.is_empty() && entries.iter().all(toml::Value::is_table) =>
+        {
+            entries
+                .into_iter()
+                .enumerate()
+                .map(|(index, entry)| (index.to_string(), entry, true))
+                .collect()
+        }
+        value => return vec![(Vec::new(), value)],
+    };
+
+    nested
+        .into_iter()
+        .flat_map(|(segment, value, is_index)| {
+            probes(value).into_iter().map(move |(mut path, probe)| {
+                let probe = if is_index {
+                    toml::Value::Array(vec![probe])
+                } else {
+                    toml::Value::Table(toml::Table::from_iter([(segment.clone(), probe)]))
+                };
+                path.insert(0, segment.clone());
+                (path, probe)
+            })
+        })
+        .collect()
+}
+
+/// How the table at `path` is named in messages, the keys it has, and
+/// whether an unknown key in it also makes `Config::read` fail.
+fn table_kind(path: &[String]) -> (String, Vec<&'static str>, bool) {
+    let path: Vec<&str> = path.iter().map(String::as_str).collect();
+    match path.as_slice() {
+        [] => (
+            "the top level".to_string(),
+            std::iter::once(includes::INCLUDE_KEY)
+                .chain(config::field_names::<Config>().iter().copied())
+                .collect(),
+            false,
+        ),
+        ["schemes"] => (
+            "[schemes]".to_string(),
+            config::field_names::<SchemesConfig>().to_vec(),
+            false,
+        ),
+        ["items", _] => (
+            "[[items]]".to_string(),
+            config::field_names::<ConfigItem>().to_vec(),
+            false,
+        ),
+        ["items", _, "when"] => (
+            "[items.when]".to_string(),
+            config::field_names::<ItemCondition>().to_vec(),
+            true,
+        ),
+        ["rings", _] => (
+            "[[rings]]".to_string(),
+            config::field_names::<ConfigRing>().to_vec(),
+            false,
+        ),
+        ["scheme-sources", _] => (
+            "[[scheme-sources]]".to_string(),
+            config::field_names::<ConfigSchemeSource>().to_vec(),
+            false,
+        ),
+        ["derived-schemes", _] => (
+            "[[derived-schemes]]".to_string(),
+            config::field_names::<ConfigDerivedScheme>().to_vec(),
+            false,
+        ),
+        ["derived-schemes", _, "operations", _] => (
+            "[[derived-schemes.operations]]".to_string(),
+            config::field_names::<DeriveOperation>().to_vec(),
+            false,
+        ),
+        ["profiles", name] => (
+            format!("[profiles.{name}]"),
+            config::field_names::<ConfigProfile>().to_vec(),
+            false,
+        ),
+        _ => (format!("[{}]", path.join(".")), Vec::new(), false),
+    }
+}
+
+/// The span of the key at `path`, going through arrays of tables by index.
+fn key_span(root: &toml_edit::Table, path: &[String]) -> Option<Range<usize>> {
+    let (key, parents) = path.split_last()?;
+    let mut table: &dyn TableLike = root;
+    let mut segments = parents.iter();
+
+    while let Some(segment) = segments.next() {
+        let item = table.get(segment)?;
+        table = match item.as_table_like() {
+            Some(table) => table,
+            None => *entries(item).get(segments.next()?.parse::<usize>().ok()?)?,
+        };
+    }
+
+    table.get_key_value(key).and_then(|(key, _)| key.span())
+}
+
+/// The tables of an array of tables or of an array of inline tables.
+fn entries(item: &Item) -> Vec<&dyn TableLike> {
+    match item {
+        Item::ArrayOfTables(tables) => tables
+            .iter()
+            .map(|table| -> &dyn TableLike { table })
+            .collect(),
+        Item::Value(Value::Array(array)) => array
+            .iter()
+            .filter_map(Value::as_inline_table)
+            .map(|table| -> &dyn TableLike { table })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{validate, Severity};
+    use crate::paths::schemes_repo_path;
+    use std::fs;
+
+    #[test]
+    fn validate_reports_every_problem_with_its_position() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = dir.path().join("config.toml");
+        fs::write(
+            &config,
+            "shell = \"bash\"\n\n[[items]]\nname = \"vim\"\npath = \"https://example.com/vim\"\ntheme-dir = \"colors\"\nsupported-systems = [\"base17\"]\nwrite-to-file = []\n\n[[items]]\nname = \"vim\"\npath = \"https://example.com/vim\"\nthemes-dir = \"colors\"\n",
+        )
+        .unwrap();
+
+        let diagnostics = validate(&config, dir.path());
+        let messages: Vec<String> = diagnostics
+            .iter()
+            .map(|diagnostic| {
+                let (line, column) = diagnostic.position.unwrap();
+                assert_eq!(diagnostic.severity, Severity::Error);
+                format!("{line}:{column} {}", diagnostic.message)
+            })
+            .collect();
+
+        assert_eq!(messages.len(), 5, "{messages:#?}");
+        assert!(messages
+            .iter()
+            .any(|message| message.starts_with("1:9 shell needs a {} placeholder")));
+        assert!(messages.iter().any(|message| message
+            == "6:1 Unknown key `theme-dir` in [[items]]. Did you mean `themes-dir`?"));
+        assert!(messages
+            .iter()
+            .any(|message| message.starts_with("7:22 supported-systems has \"base17\"")));
+        assert!(messages
+            .iter()
+            .any(|message| message.starts_with("8:17 write-to-file should be")));
+        assert!(messages
+            .iter()
+            .any(|message| message == "11:8 [[items]] name \"vim\" is already used on line 4"));
+    }
+
+    #[test]
+    fn validate_reports_unknown_keys_at_any_depth() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = dir.path().join("config.toml");
+        fs::write(
+            &config,
+            "include = [\"extra.toml\"]\n\n[[items]]\nname = \"vim\"\npath = \"https://example.com/vim\"\nthemes-dir = \"colors\"\nwhen.hostnme = \"work\"\n\n[[derived-schemes]]\nslug = \"dark\"\nbase = \"base16-mocha\"\n\n[[derived-schemes.operations]]\nop = \"darken\"\namount = 0.1\nslot = [\"base00\"]\n\n[profiles.work]\ndefault-schem = \"base16-mocha\"\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("extra.toml"),
+            "[[items]]\nname = \"vim\"\nhok = \"echo vim\"\n",
+        )
+        .unwrap();
+
+        let diagnostics = validate(&config, dir.path());
+        let messages: Vec<String> = diagnostics
+            .iter()
+            .map(|diagnostic| {
+                let (line, column) = diagnostic.position.unwrap();
+                format!(
+                    "{}:{line}:{column} {}",
+                    diagnostic.file.file_name().unwrap().to_string_lossy(),
+                    diagnostic.message
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            messages,
+            [
+                "config.toml:7:6 Unknown key `hostnme` in [items.when]. Did you mean `hostname`?",
+                "config.toml:16:1 Unknown key `slot` in [[derived-schemes.operations]]. Did you mean `slots`?",
+                "config.toml:19:1 Unknown key `default-schem` in [profiles.work]. Did you mean `default-scheme`?",
+                "extra.toml:3:1 Unknown key `hok` in [[items]]. Did you mean `hook`?",
+            ]
+        );
+    }
+
+    #[test]
+    fn validate_matches_derived_schemes_by_their_id() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = dir.path().join("config.toml");
+        fs::create_dir_all(schemes_repo_path(dir.path()).join("base16")).unwrap();
+        fs::write(
+            &config,
+            "default-scheme = \"base16-ayu-dark\"\n\n[[rings]]\nname = \"dark\"\nschemes = [\"base16-dark\"]\n\n[[derived-schemes]]\nslug = \"dark\"\nbase = \"base16-mocha\"\n",
+        )
+        .unwrap();
+
+        let diagnostics = validate(&config, dir.path());
+
+        assert_eq!(diagnostics.len(), 1, "{diagnostics:#?}");
+        assert!(diagnostics
+            .first()
+            .unwrap()
+            .message
+            .starts_with("default-scheme uses \"base16-ayu-dark\", which is not installed"));
+    }
+
+    #[test]
+    fn validate_reports_syntax_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = dir.path().join("config.toml");
+        fs::write(&config, "default-scheme = \"base16-mocha\"\nhooks = [\n").unwrap();
+
+        let diagnostics = validate(&config, dir.path());
+
+        assert_eq!(diagnostics.len(), 1, "{diagnostics:#?}");
+        assert!(diagnostics.first().unwrap().position.is_some());
+    }
+}
@@ -124,7 +124,7 @@ fn test_cli_apply_subcommand_invalid_scheme_system() -> Result<()> {
         format!("apply {scheme_name}").as_str(),
         true,
     )?;
-    let expected_output = format!("Invalid scheme name. Make sure your scheme is prefixed with a supprted system (\"base16\", \"base24\" or \"tinted8\"), eg: base16-{scheme_name}");
+    let expected_output = format!("Scheme does not exist: {scheme_name}");
 
     // ---
     // Act
@@ -147,13 +147,13 @@ fn test_cli_apply_subcommand_no_scheme_system() -> Result<()> {
     // -------
     // Arrange
     // -------
-    let scheme_name = "ocean";
-    let (_, _data_path, command_vec, _temp_dir) = setup(
+    let scheme_name = "oceanicnext";
+    let (_, data_path, command_vec, _temp_dir) = setup(
         "test_cli_apply_subcommand_no_scheme_system",
         format!("apply {scheme_name}").as_str(),
         true,
     )?;
-    let expected_output = "Invalid scheme name. Make sure the scheme system is prefixed <SCHEME_SYSTEM>-<SCHEME_NAME>, eg: `base16-ayu-dark`";
+    let current_scheme_path = data_path.join(ARTIFACTS_DIR).join(CURRENT_SCHEME_FILE_NAME);
 
     // ---
     // Act
@@ -163,9 +163,10 @@ fn test_cli_apply_subcommand_no_scheme_system() -> Result<()> {
     // ------
     // Assert
     // ------
+    ensure!(stderr.is_empty(), "Expected empty stderr, got: {stderr}");
     ensure!(
-        stderr.contains(expected_output),
-        "Expected stderr to contain: {expected_output}\nGot: {stderr}"
+        fs::read_to_string(&current_scheme_path)? == format!("base16-{scheme_name}"),
+        "Expected the unprefixed slug to resolve to its base16 scheme"
     );
 
     Ok(())
@@ -0,0 +1,183 @@
+//! Integration tests for the `clone-depth` and `sparse-checkout` item options.
+//!
+//! Fully offline: the item is a throwaway local git repository addressed with a
+//! `file://` URL, and the schemes repo is a plain local directory.
+
+mod utils;
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::{ensure, Context, Result};
+use utils::{build_command_vec, run_command, setup, write_to_file};
+
+const ITEM_NAME: &str = "large-item";
+
+fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .with_context(|| format!("failed to run git {args:?} in {}", dir.display()))?;
+    ensure!(
+        output.status.success(),
+        "git {args:?} failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn commit(dir: &Path, message: &str) -> Result<()> {
+    git(dir, &["add", "-A"])?;
+    git(
+        dir,
+        &[
+            "-c",
+            "user.email=tinty@test.local",
+            "-c",
+            "user.name=tinty test",
+            "-c",
+            "commit.gpgsign=false",
+            "commit",
+            "-q",
+            "-m",
+            message,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Creates a template repository with three commits touching `themes/`,
+/// `templates/` and an unrelated `assets/` directory.
+fn create_source(root: &Path) -> Result<()> {
+    let source = root.join(ITEM_NAME);
+    fs::create_dir_all(source.join("themes"))?;
+    fs::create_dir_all(source.join("templates"))?;
+    fs::create_dir_all(source.join("assets"))?;
+    git(&source, &["init", "-q", "-b", "main"])?;
+    for n in 1..=3 {
+        fs::write(source.join("themes").join(format!("theme-{n}.sh")), "theme")?;
+        fs::write(source.join("templates/config.yaml"), format!("# {n}"))?;
+        fs::write(
+            source.join("assets").join(format!("large-{n}.png")),
+            "asset",
+        )?;
+        fs::write(source.join("README.md"), format!("# {n}"))?;
+        commit(&source, &format!("commit {n}"))?;
+    }
+    Ok(())
+}
+
+fn write_config(config_path: &Path, root: &Path, options: &str) -> Result<()> {
+    fs::create_dir_all(root.join("schemes"))?;
+    write_to_file(
+        config_path,
+        &format!(
+            "[schemes]\npath = \"{}\"\n\n[[items]]\npath = \"file://{}\"\nname = \"{ITEM_NAME}\"\nthemes-dir = \"themes\"\nrevision = \"main\"\n{options}",
+            root.join("schemes").display(),
+            root.join(ITEM_NAME).display()
+        ),
+    )
+}
+
+#[test]
+fn test_cli_install_shallow_sparse_clone() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, command_vec, temp) =
+        setup("clone_options_install", "install", false)?;
+    create_source(temp.path())?;
+    write_config(
+        &config_path,
+        temp.path(),
+        "clone-depth = 1\nsparse-checkout = true\n",
+    )?;
+    let repo_path = data_path.join("repos").join(ITEM_NAME);
+
+    // ---
+    // Act
+    // ---
+    let (stdout, stderr) = run_command(&command_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(stderr.is_empty(), "Expected empty stderr, got: {stderr}");
+    ensure!(
+        stdout.contains(&format!("{ITEM_NAME} installed")),
+        "Expected the item to be installed.\nstdout: {stdout}"
+    );
+    ensure!(
+        repo_path.join("themes/theme-3.sh").exists()
+            && repo_path.join("templates/config.yaml").exists()
+            && repo_path.join("README.md").exists(),
+        "Expected themes-dir, templates/ and root files to be checked out"
+    );
+    ensure!(
+        !repo_path.join("assets").exists(),
+        "Expected other directories to be left out of the sparse checkout"
+    );
+    ensure!(
+        git(&repo_path, &["rev-list", "--count", "HEAD"])? == "1",
+        "Expected a clone with a single commit of history"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_update_keeps_clone_options_in_sync() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, install_vec, temp) =
+        setup("clone_options_update", "install", false)?;
+    create_source(temp.path())?;
+    write_config(
+        &config_path,
+        temp.path(),
+        "clone-depth = 1\nsparse-checkout = true\n",
+    )?;
+    run_command(&install_vec)?;
+    let repo_path = data_path.join("repos").join(ITEM_NAME);
+    let source = temp.path().join(ITEM_NAME);
+    fs::write(source.join("themes/theme-4.sh"), "theme")?;
+    commit(&source, "commit 4")?;
+    let update_vec = build_command_vec("update", &config_path, &data_path)?;
+
+    // ---
+    // Act
+    // ---
+    let (_, shallow_stderr) = run_command(&update_vec)?;
+    let shallow_count = git(&repo_path, &["rev-list", "--count", "HEAD"])?;
+    write_config(&config_path, temp.path(), "")?;
+    let (_, full_stderr) = run_command(&update_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(
+        shallow_stderr.is_empty() && full_stderr.is_empty(),
+        "Expected empty stderr, got: {shallow_stderr}{full_stderr}"
+    );
+    ensure!(
+        shallow_count == "1",
+        "Expected update to keep the clone at its depth, got {shallow_count} commits"
+    );
+    ensure!(
+        repo_path.join("themes/theme-4.sh").exists(),
+        "Expected the new commit to be checked out"
+    );
+    ensure!(
+        repo_path.join("assets/large-3.png").exists(),
+        "Expected the full tree once sparse-checkout is turned off"
+    );
+    ensure!(
+        git(&repo_path, &["rev-list", "--count", "HEAD"])? == "4",
+        "Expected full history once clone-depth is removed"
+    );
+
+    Ok(())
+}
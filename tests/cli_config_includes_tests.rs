@@ -0,0 +1,103 @@
+//! Integration tests for `include` in `config.toml`: shared files merged by
+//! item name and per-host overlays.
+//!
+//! Fully offline: `tinty config` only reads the config files.
+
+mod utils;
+
+use std::path::Path;
+
+use anyhow::{ensure, Result};
+use utils::{build_command_vec, run_command_with_env, setup, write_to_file};
+
+const HOSTNAME_ENV_VAR: &str = "TINTY_HOSTNAME";
+
+/// Writes a base config including every file in `items/` and the overlay for
+/// the host, an item file and a `laptop` overlay.
+fn write_configs(config_path: &Path) -> Result<()> {
+    let dir = config_path.parent().unwrap_or_else(|| Path::new("."));
+    write_to_file(
+        config_path,
+        "include = [\"items/*.toml\", \"hosts/{hostname}.toml\"]\ndefault-scheme = \"base16-mocha\"\n\n[[items]]\nname = \"tinted-vim\"\npath = \"https://github.com/tinted-theming/tinted-vim\"\nthemes-dir = \"colors\"\n",
+    )?;
+    write_to_file(
+        dir.join("items/shell.toml"),
+        "[[items]]\nname = \"tinted-shell\"\npath = \"https://github.com/tinted-theming/tinted-shell\"\nthemes-dir = \"scripts\"\nhook = \"source %f\"\n",
+    )?;
+    write_to_file(
+        dir.join("hosts/laptop.toml"),
+        "default-scheme = \"base16-github\"\n\n[[items]]\nname = \"tinted-vim\"\nrevision = \"v2\"\n",
+    )
+}
+
+#[test]
+fn test_cli_config_merges_includes_and_host_overlay() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, _, _temp) = setup("config_includes_laptop", "config", false)?;
+    write_configs(&config_path)?;
+    let config_vec = build_command_vec("config", &config_path, &data_path)?;
+    let overlay = config_path
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join("hosts/laptop.toml");
+
+    // ---
+    // Act
+    // ---
+    let (stdout, stderr) = run_command_with_env(&config_vec, &[(HOSTNAME_ENV_VAR, "laptop")])?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(stderr.is_empty(), "Expected empty stderr, got: {stderr}");
+    for expected in [
+        "default-scheme = \"base16-github\"",
+        "name = \"tinted-vim\"",
+        "revision = \"v2\"",
+        "name = \"tinted-shell\"",
+        &format!("# default-scheme = {}", overlay.display()),
+        &format!("# items.tinted-vim.revision = {}", overlay.display()),
+        &format!("# items.tinted-vim.path = {}", config_path.display()),
+    ] {
+        ensure!(
+            stdout.contains(expected),
+            "Expected {expected:?} in the merged config.\nstdout: {stdout}"
+        );
+    }
+    ensure!(
+        stdout.matches("name = \"tinted-vim\"").count() == 1,
+        "Expected the overlay to merge into the existing item.\nstdout: {stdout}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_config_skips_missing_host_overlay() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, _, _temp) = setup("config_includes_desktop", "config", false)?;
+    write_configs(&config_path)?;
+    let config_vec = build_command_vec("config", &config_path, &data_path)?;
+
+    // ---
+    // Act
+    // ---
+    let (stdout, stderr) = run_command_with_env(&config_vec, &[(HOSTNAME_ENV_VAR, "desktop")])?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(stderr.is_empty(), "Expected empty stderr, got: {stderr}");
+    ensure!(
+        stdout.contains("default-scheme = \"base16-mocha\"")
+            && stdout.contains("name = \"tinted-shell\"")
+            && !stdout.contains("revision = \"v2\""),
+        "Expected the base config and the item file only.\nstdout: {stdout}"
+    );
+
+    Ok(())
+}
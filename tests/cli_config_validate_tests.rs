@@ -0,0 +1,139 @@
+//! Integration tests for `tinty config validate`.
+//!
+//! Fully offline: the schemes repo and the item are plain local directories.
+
+mod utils;
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::{ensure, Result};
+use utils::{build_command_vec, run_command, setup, write_to_file};
+
+/// Writes a `base16-mocha` scheme and an item with a theme for it, and returns
+/// the config using them.
+fn write_sources(root: &Path) -> Result<String> {
+    let scheme = fs::read_to_string("./tests/fixtures/schemes/tinty-generated.yaml")?
+        .replace("slug: tinty-generated", "slug: mocha");
+    write_to_file(root.join("schemes/base16/mocha.yaml"), &scheme)?;
+    write_to_file(root.join("shell/themes/base16-mocha.sh"), "shell mocha\n")?;
+
+    Ok(format!(
+        "default-scheme = \"base16-mocha\"\n\n[schemes]\npath = \"{schemes}\"\n\n[[items]]\nname = \"shell\"\npath = \"{shell}\"\nthemes-dir = \"themes\"\n",
+        schemes = root.join("schemes").display(),
+        shell = root.join("shell").display(),
+    ))
+}
+
+#[test]
+fn test_cli_config_validate_accepts_valid_config() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, install_vec, temp) =
+        setup("config_validate_valid", "install", false)?;
+    write_to_file(&config_path, &write_sources(temp.path())?)?;
+    let validate_vec = build_command_vec("config validate", &config_path, &data_path)?;
+
+    // ---
+    // Act
+    // ---
+    run_command(&install_vec)?;
+    let (stdout, stderr) = run_command(&validate_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(stderr.is_empty(), "Expected empty stderr, got: {stderr}");
+    ensure!(
+        stdout.contains(&format!("{} is valid", config_path.display())),
+        "Expected the config to be valid.\nstdout: {stdout}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_config_validate_reports_every_problem() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, install_vec, temp) =
+        setup("config_validate_problems", "install", false)?;
+    let config = write_sources(temp.path())?;
+    write_to_file(&config_path, &config)?;
+    let validate_vec = build_command_vec("config validate", &config_path, &data_path)?;
+    let file = config_path.display();
+
+    // ---
+    // Act
+    // ---
+    run_command(&install_vec)?;
+    write_to_file(
+        &config_path,
+        &format!(
+            "shell = \"bash -c\"\n{config}theme-dir = \"themes\"\nsupported-systems = [\"base17\"]\nwrite-to-file = []\n\n[[items]]\nname = \"shell\"\npath = \"https://example.com/shell\"\nthemes-dir = \"themes\"\n"
+        ),
+    )?;
+    let (stdout, stderr) = run_command(&validate_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    for expected in [
+        format!("{file}:1:9: error: shell needs a {{}} placeholder"),
+        format!(
+            "{file}:11:1: error: Unknown key `theme-dir` in [[items]]. Did you mean `themes-dir`?"
+        ),
+        format!("{file}:12:22: error: supported-systems has \"base17\""),
+        format!("{file}:13:17: error: write-to-file should be"),
+        format!("{file}:16:8: error: [[items]] name \"shell\" is already used on line 8"),
+        "5 problems found".to_string(),
+    ] {
+        ensure!(
+            stderr.contains(&expected),
+            "Expected {expected:?} in stderr.\nstderr: {stderr}"
+        );
+    }
+    ensure!(stdout.is_empty(), "Expected empty stdout, got: {stdout}");
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_config_validate_reports_unknown_ring_schemes() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, install_vec, temp) =
+        setup("config_validate_rings", "install", false)?;
+    let config = write_sources(temp.path())?;
+    write_to_file(&config_path, &config)?;
+    let validate_vec = build_command_vec("config validate", &config_path, &data_path)?;
+
+    // ---
+    // Act
+    // ---
+    run_command(&install_vec)?;
+    write_to_file(
+        &config_path,
+        &format!(
+            "{config}\n[[rings]]\nname = \"dark\"\nschemes = [\"base16-mocha\", \"base16-missing\"]\n"
+        ),
+    )?;
+    let (_, stderr) = run_command(&validate_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(
+        stderr.contains(&format!(
+            "{}:13:28: error: Ring \"dark\" uses \"base16-missing\", which is not installed",
+            config_path.display()
+        )) && stderr.contains("1 problem found")
+            && !stderr.contains("\"base16-mocha\""),
+        "Expected the unknown ring scheme only.\nstderr: {stderr}"
+    );
+
+    Ok(())
+}
@@ -0,0 +1,153 @@
+//! Integration tests for the `derive` subcommand and `[[derived-schemes]]`.
+//!
+//! These tests are offline: the schemes repo is written straight into the data
+//! directory, so `derive` never needs `install` to clone anything.
+
+mod utils;
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::{ensure, Result};
+use utils::{run_command, setup, write_to_file};
+
+/// A minimal, valid base16 scheme YAML for the given slug.
+fn scheme_yaml(slug: &str, name: &str) -> String {
+    format!(
+        "system: base16\nname: {name}\nslug: {slug}\nauthor: Tinty Test\nvariant: dark\npalette:\n  base00: '#282628'\n  base01: '#403e3f'\n  base02: '#595757'\n  base03: '#71706e'\n  base04: '#8a8986'\n  base05: '#a2a29d'\n  base06: '#bbbbb5'\n  base07: '#d4d4cd'\n  base08: '#bf2546'\n  base09: '#f69622'\n  base0A: '#f99923'\n  base0B: '#19953f'\n  base0C: '#40dab9'\n  base0D: '#0666dc'\n  base0E: '#8554ac'\n  base0F: '#ac7424'\n"
+    )
+}
+
+/// Writes a schemes repo containing `base16/<slug>.yaml` into the data dir.
+fn write_schemes_repo(data_path: &Path, slug: &str, name: &str) -> Result<()> {
+    write_to_file(
+        data_path
+            .join("repos")
+            .join("schemes")
+            .join("base16")
+            .join(format!("{slug}.yaml")),
+        &scheme_yaml(slug, name),
+    )
+}
+
+#[test]
+fn test_cli_derive_subcommand_writes_custom_scheme() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, command_vec, _temp_dir) = setup(
+        "test_cli_derive_subcommand_writes_custom_scheme",
+        "derive",
+        false,
+    )?;
+    write_schemes_repo(&data_path, "test-dark", "Test Dark")?;
+    write_to_file(
+        &config_path,
+        r##"[[derived-schemes]]
+slug = "test-dimmed"
+base = "base16-test-dark"
+name = "Test Dimmed"
+operations = [
+  { op = "darken", amount = 5 },
+  { op = "desaturate", amount = 100, slots = ["base08"], space = "hsl" },
+]
+overrides = { base0D = "#123456" }
+"##,
+    )?;
+
+    // ---
+    // Act
+    // ---
+    let (stdout, stderr) = run_command(&command_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    let derived_path = data_path
+        .join("custom-schemes")
+        .join("base16")
+        .join("test-dimmed.yaml");
+    ensure!(
+        derived_path.exists(),
+        "expected derived scheme at {}; stderr: {stderr}",
+        derived_path.display()
+    );
+    ensure!(
+        stdout.contains("base16-test-dimmed derived from base16-test-dark"),
+        "unexpected stdout: {stdout}"
+    );
+
+    let contents = fs::read_to_string(&derived_path)?;
+    ensure!(contents.contains("name: Test Dimmed"), "{contents}");
+    ensure!(contents.contains("slug: test-dimmed"), "{contents}");
+    ensure!(contents.contains("'#123456'"), "{contents}");
+    // base00 was darkened, so it no longer matches the base scheme.
+    ensure!(!contents.contains("'#282628'"), "{contents}");
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_derive_subcommand_with_unknown_slug() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, command_vec, _temp_dir) = setup(
+        "test_cli_derive_subcommand_with_unknown_slug",
+        "derive does-not-exist",
+        false,
+    )?;
+    write_schemes_repo(&data_path, "test-dark", "Test Dark")?;
+    write_to_file(
+        &config_path,
+        "[[derived-schemes]]\nslug = \"test-dimmed\"\nbase = \"base16-test-dark\"\n",
+    )?;
+
+    // ---
+    // Act
+    // ---
+    let (_, stderr) = run_command(&command_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(
+        stderr.contains("does-not-exist") && stderr.contains("test-dimmed"),
+        "expected unknown slug error listing available slugs; stderr: {stderr}"
+    );
+    ensure!(!data_path.join("custom-schemes").exists());
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_derive_subcommand_with_unknown_palette_slot() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, command_vec, _temp_dir) = setup(
+        "test_cli_derive_subcommand_with_unknown_palette_slot",
+        "derive",
+        false,
+    )?;
+    write_schemes_repo(&data_path, "test-dark", "Test Dark")?;
+    write_to_file(
+        &config_path,
+        "[[derived-schemes]]\nslug = \"test-dimmed\"\nbase = \"base16-test-dark\"\noperations = [{ op = \"lighten\", amount = 5, slots = [\"base10\"] }]\n",
+    )?;
+
+    // ---
+    // Act
+    // ---
+    let (_, stderr) = run_command(&command_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(
+        stderr.contains("base10"),
+        "expected unknown slot error; stderr: {stderr}"
+    );
+
+    Ok(())
+}
@@ -0,0 +1,150 @@
+//! Integration tests for environment variables in config values: `path` and
+//! `write-to-file` are expanded by tinty, `hook` and `hooks` by the shell.
+//!
+//! Fully offline: the schemes repo and the item are plain local directories.
+
+mod utils;
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::{ensure, Result};
+use utils::{build_command_vec, run_command_with_env, setup, write_to_file};
+
+/// Writes a `base16-mocha` scheme, an item with a theme for it, and a config
+/// reaching both through `$SOURCES_DIR`.
+#[allow(clippy::literal_string_with_formatting_args)]
+fn write_sources(config_path: &Path, root: &Path) -> Result<()> {
+    let scheme = fs::read_to_string("./tests/fixtures/schemes/tinty-generated.yaml")?
+        .replace("slug: tinty-generated", "slug: mocha");
+    write_to_file(root.join("schemes/base16/mocha.yaml"), &scheme)?;
+    write_to_file(root.join("shell/themes/base16-mocha.sh"), "shell mocha\n")?;
+
+    write_to_file(
+        config_path,
+        "hooks = [\"echo global ${GREETING:-hello}\", \"for f in one.sh two.sh; do echo loop $f ${f%.sh}; done\"]\n\n[schemes]\npath = \"${SOURCES_DIR}/schemes\"\n\n[[items]]\npath = \"$SOURCES_DIR/shell\"\nname = \"shell\"\nthemes-dir = \"themes\"\nwrite-to-file = [\"${OUTPUT_DIR:-$SOURCES_DIR/out}/theme.sh\"]\nhook = \"echo item $USER_NAME $TINTY_THEME_OPERATION\"\n",
+    )
+}
+
+#[test]
+fn test_cli_apply_expands_env_vars() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, install_vec, temp) = setup("env_interpolation", "install", false)?;
+    write_sources(&config_path, temp.path())?;
+    write_to_file(temp.path().join("out/theme.sh"), "old\n")?;
+    let apply_vec = build_command_vec("apply base16-mocha", &config_path, &data_path)?;
+    let sources_dir = temp.path().display().to_string();
+    let env = [("SOURCES_DIR", sources_dir.as_str()), ("USER_NAME", "ada")];
+
+    // ---
+    // Act
+    // ---
+    let (_, install_stderr) = run_command_with_env(&install_vec, &env)?;
+    let (stdout, stderr) = run_command_with_env(&apply_vec, &env)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(
+        install_stderr.is_empty() && stderr.is_empty(),
+        "Expected empty stderr, got: {install_stderr}{stderr}"
+    );
+    ensure!(
+        stdout.contains("item ada apply") && stdout.contains("global hello"),
+        "Expected the shell to expand the hooks.\nstdout: {stdout}"
+    );
+    ensure!(
+        stdout.contains("loop one.sh one") && stdout.contains("loop two.sh two"),
+        "Expected shell variables in hooks to work.\nstdout: {stdout}"
+    );
+    ensure!(
+        fs::read_to_string(temp.path().join("out/theme.sh"))? == "shell mocha\n",
+        "Expected the theme to be written to the expanded write-to-file path"
+    );
+
+    Ok(())
+}
+
+#[test]
+#[allow(clippy::literal_string_with_formatting_args)]
+fn test_cli_hooks_keep_quotes_in_env_var_values() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, install_vec, temp) =
+        setup("env_interpolation_quotes", "install", false)?;
+    write_sources(&config_path, temp.path())?;
+    write_to_file(temp.path().join("out/theme.sh"), "old\n")?;
+    let apply_vec = build_command_vec("apply base16-mocha", &config_path, &data_path)?;
+    let config_vec = build_command_vec("config", &config_path, &data_path)?;
+    let sources_dir = temp.path().display().to_string();
+    let env = [
+        ("SOURCES_DIR", sources_dir.as_str()),
+        ("USER_NAME", "ada"),
+        ("GREETING", "it's \"quoted\" $HOME; echo injected"),
+    ];
+
+    // ---
+    // Act
+    // ---
+    let (_, install_stderr) = run_command_with_env(&install_vec, &env)?;
+    let (stdout, stderr) = run_command_with_env(&apply_vec, &env)?;
+    let (config_stdout, _) = run_command_with_env(&config_vec, &env)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(
+        install_stderr.is_empty() && stderr.is_empty(),
+        "Expected empty stderr, got: {install_stderr}{stderr}"
+    );
+    ensure!(
+        stdout
+            .lines()
+            .any(|line| line == "global it's \"quoted\" $HOME; echo injected"),
+        "Expected the hook to print the value as it is.\nstdout: {stdout}"
+    );
+    ensure!(
+        !stdout.lines().any(|line| line == "injected"),
+        "Expected the value not to run as part of the hook.\nstdout: {stdout}"
+    );
+    ensure!(
+        config_stdout.contains("echo global ${GREETING:-hello}"),
+        "Expected the config to keep the hook as written.\nstdout: {config_stdout}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_undefined_env_var_errors() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, install_vec, temp) =
+        setup("env_interpolation_undefined", "install", false)?;
+    write_sources(&config_path, temp.path())?;
+    let config_vec = build_command_vec("config", &config_path, &data_path)?;
+
+    // ---
+    // Act
+    // ---
+    let (_, stderr) = run_command_with_env(&install_vec, &[])?;
+    let (_, config_stderr) = run_command_with_env(&config_vec, &[])?;
+
+    // ------
+    // Assert
+    // ------
+    for stderr in [stderr, config_stderr] {
+        ensure!(
+            stderr
+                .contains("Unable to expand config.toml items.shell.path = \"$SOURCES_DIR/shell\"")
+                && stderr.contains("$SOURCES_DIR is not set"),
+            "Expected an undefined variable error.\nstderr: {stderr}"
+        );
+    }
+
+    Ok(())
+}
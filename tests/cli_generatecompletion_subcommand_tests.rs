@@ -102,6 +102,7 @@ fn test_cli_generatecompletion_subcommand_fish() -> Result<()> {
         stdout.contains(r#"
 complete -c tinty -n "__fish_tinty_needs_command" -s c -l config -d 'Optional path to the tinty config.toml file' -r
 complete -c tinty -n "__fish_tinty_needs_command" -s d -l data-dir -d 'Optional path to the tinty data directory' -r
+complete -c tinty -n "__fish_tinty_needs_command" -l profile -d 'Optional [profiles.<NAME>] from the config to use. Defaults to $TINTY_PROFILE' -r
 complete -c tinty -n "__fish_tinty_needs_command" -s h -l help -d 'Print help'
 "#),
         "stdout does not contain expected shell completion output"
@@ -1,7 +1,8 @@
 //! Integration tests for the `generate-scheme` subcommand.
 //!
 //! Covers: generating schemes from images with custom properties (author,
-//! name, slug, system, variant), default properties, and `--save` to disk.
+//! name, slug, system, variant), default properties, and `--save` to disk, as
+//! well as the `--from-color` and `--random [--seed]` palette modes.
 //!
 //! Uses a local fixture image — no network access required.
 
@@ -197,3 +198,137 @@ palette:
 
     Ok(())
 }
+
+#[test]
+fn test_cli_generatescheme_subcommand_random_with_seed_is_reproducible() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (_, _data_path, command_vec, _temp_dir) = setup(
+        "test_cli_generatescheme_subcommand_random_with_seed_is_reproducible",
+        "generate-scheme --random --seed 42 --system base24",
+        false,
+    )?;
+
+    // ---
+    // Act
+    // ---
+    let (first_stdout, stderr) = utils::run_command(&command_vec)?;
+    let (second_stdout, _) = utils::run_command(&command_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(stderr.is_empty(), "Expected empty stderr, got: {stderr}");
+    ensure!(
+        first_stdout == second_stdout,
+        "Expected the same seed to generate the same scheme.\nFirst:\n{first_stdout}\nSecond:\n{second_stdout}"
+    );
+    ensure!(
+        first_stdout.contains("description: \"Generated by tinty with --random --seed 42\""),
+        "Expected the seed in the description, got:\n{first_stdout}"
+    );
+    ensure!(
+        first_stdout.contains("base17:"),
+        "Expected a base24 palette, got:\n{first_stdout}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_generatescheme_subcommand_from_color_with_save() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let scheme_slug = "test-from-color";
+    let (_, data_path, command_vec, _temp_dir) = setup(
+        "test_cli_generatescheme_subcommand_from_color_with_save",
+        format!("generate-scheme --from-color \"#88c0d0\" --slug {scheme_slug} --save").as_str(),
+        false,
+    )?;
+    let out_scheme_path = data_path.join(format!(
+        "{CUSTOM_SCHEMES_DIR_NAME}/base16/{scheme_slug}.yaml"
+    ));
+
+    // ---
+    // Act
+    // ---
+    let (stdout, stderr) = utils::run_command(&command_vec)?;
+    let actual_output = fs::read_to_string(&out_scheme_path)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(stderr.is_empty(), "Expected empty stderr, got: {stderr}");
+    ensure!(
+        stdout == format!("Scheme created: {}\n", out_scheme_path.display()),
+        "Expected stdout to show scheme creation path.\nGot: {stdout}"
+    );
+    ensure!(
+        actual_output.contains("base0C: '#88c0d0'"),
+        "Expected the seed color in the palette, got:\n{actual_output}"
+    );
+    ensure!(
+        actual_output.contains("variant: dark"),
+        "Expected a dark scheme by default, got:\n{actual_output}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_generatescheme_subcommand_seed_without_random() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (_, _data_path, command_vec, _temp_dir) = setup(
+        "test_cli_generatescheme_subcommand_seed_without_random",
+        "generate-scheme --from-color \"#88c0d0\" --seed 1",
+        false,
+    )?;
+
+    // ---
+    // Act
+    // ---
+    let (_, stderr) = utils::run_command(&command_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(
+        stderr.contains("cannot be used with") && stderr.contains("--seed"),
+        "Expected a usage error, got: {stderr}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_generatescheme_subcommand_seed_requires_random() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (_, _data_path, command_vec, _temp_dir) = setup(
+        "test_cli_generatescheme_subcommand_seed_requires_random",
+        "generate-scheme --seed 1",
+        false,
+    )?;
+
+    // ---
+    // Act
+    // ---
+    let (stdout, stderr) = utils::run_command(&command_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(
+        stdout.is_empty()
+            && stderr.contains("required arguments were not provided")
+            && stderr.contains("--random"),
+        "Expected a usage error naming --random, got: {stderr}"
+    );
+
+    Ok(())
+}
@@ -0,0 +1,149 @@
+//! Integration tests for how `install` and `update` report failed clones and
+//! fetches: classified, with a hint, and without stopping the other
+//! repositories.
+//!
+//! Fully offline: every source is a throwaway local git repository addressed
+//! with a `file://` URL.
+
+mod utils;
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::{ensure, Context, Result};
+use utils::{build_command_vec, run_command, setup, write_to_file};
+
+fn git(dir: &Path, args: &[&str]) -> Result<()> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .with_context(|| format!("failed to run git {args:?} in {}", dir.display()))?;
+    ensure!(
+        output.status.success(),
+        "git {args:?} failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    Ok(())
+}
+
+/// Creates a git repository at `dir` with one commit on `main`.
+fn create_repo(dir: &Path) -> Result<()> {
+    write_to_file(dir.join("themes/base16-mocha.sh"), "mocha")?;
+    git(dir, &["init", "-q", "-b", "main"])?;
+    git(dir, &["add", "-A"])?;
+    git(
+        dir,
+        &[
+            "-c",
+            "user.email=tinty@test.local",
+            "-c",
+            "user.name=tinty test",
+            "-c",
+            "commit.gpgsign=false",
+            "commit",
+            "-q",
+            "-m",
+            "Initial commit",
+        ],
+    )
+}
+
+/// Writes a config with the schemes repo and two items, `first` and `second`,
+/// the latter at `second_revision`.
+fn write_config(config_path: &Path, root: &Path, second_revision: &str) -> Result<()> {
+    let item = |name: &str, revision: &str| {
+        format!(
+            "\n[[items]]\npath = \"file://{}\"\nname = \"{name}\"\nthemes-dir = \"themes\"\nrevision = \"{revision}\"\n",
+            root.join(name).display()
+        )
+    };
+
+    write_to_file(
+        config_path,
+        &format!(
+            "[schemes]\npath = \"file://{}\"\nrevision = \"main\"\n{}{}",
+            root.join("schemes").display(),
+            item("first", "main"),
+            item("second", second_revision)
+        ),
+    )
+}
+
+fn create_sources(config_path: &Path, root: &Path, second_revision: &str) -> Result<()> {
+    for name in ["schemes", "first", "second"] {
+        create_repo(&root.join(name))?;
+    }
+    write_config(config_path, root, second_revision)
+}
+
+#[test]
+fn test_cli_install_reports_missing_revision() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, install_vec, temp) =
+        setup("git_errors_missing_revision", "install", false)?;
+    create_sources(&config_path, temp.path(), "no-such-branch")?;
+
+    // ---
+    // Act
+    // ---
+    let (stdout, stderr) = run_command(&install_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(
+        stderr.contains("revision not found. Check the `revision` in config.toml"),
+        "Expected a classified revision error.\nstderr: {stderr}"
+    );
+    ensure!(
+        stdout.contains("first installed"),
+        "Expected the other item to be installed.\nstdout: {stdout}"
+    );
+    ensure!(
+        !data_path.join("repos/second").exists(),
+        "Expected the failed clone to be cleaned up"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_update_continues_past_missing_repository() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, install_vec, temp) =
+        setup("git_errors_missing_repo", "install", false)?;
+    create_sources(&config_path, temp.path(), "main")?;
+    run_command(&install_vec)?;
+    fs::remove_dir_all(temp.path().join("second"))?;
+    let update_vec = build_command_vec("update", &config_path, &data_path)?;
+
+    // ---
+    // Act
+    // ---
+    let (stdout, stderr) = run_command(&update_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(
+        stderr.contains("second failed to update")
+            && stderr.contains("repository not found. Check the `path` in config.toml"),
+        "Expected a classified not-found error for the missing repository.\nstderr: {stderr}"
+    );
+    ensure!(
+        stderr.contains("Failed to update 1 of 3 repositories"),
+        "Expected the failures to be summed up at the end.\nstderr: {stderr}"
+    );
+    ensure!(
+        stdout.contains("first up to date") && stdout.contains("schemes up to date"),
+        "Expected the other repositories to be updated.\nstdout: {stdout}"
+    );
+
+    Ok(())
+}
@@ -8,7 +8,7 @@
 
 mod utils;
 
-use crate::utils::{setup, write_to_file, ARTIFACTS_DIR, CURRENT_SCHEME_FILE_NAME, REPO_NAME};
+use crate::utils::{setup, write_to_file, ARTIFACTS_DIR, CURRENT_SCHEME_FILE_NAME};
 use anyhow::{ensure, Result};
 
 #[test]
@@ -228,7 +228,7 @@ fn test_cli_info_subcommand_with_setup_invalid_scheme_name() -> Result<()> {
     // -------
     // Arrange
     // -------
-    let scheme_name = "mocha";
+    let scheme_name = "not-a-real-scheme";
     let (_, _data_path, command_vec, _temp_dir) = setup(
         "test_cli_info_subcommand_with_setup_invalid_scheme_name",
         format!("info {scheme_name}").as_str(),
@@ -244,14 +244,7 @@ fn test_cli_info_subcommand_with_setup_invalid_scheme_name() -> Result<()> {
     // Assert
     // ------
     ensure!(
-        stderr.contains(
-            format!(
-                r#"Invalid scheme system: "{scheme_name}" from scheme name "{scheme_name}"
-Make sure to add the system prefix to the theme name. Eg: base16-oceanicnext
-Run `{REPO_NAME} list` to get a list of scheme names"#,
-            )
-            .as_str()
-        ),
+        stderr.contains(format!("Scheme does not exist: {scheme_name}").as_str()),
         "Expected stderr to contain expected error message.\nGot: {stderr}"
     );
 
@@ -0,0 +1,114 @@
+//! Integration tests for an item's `when` table, which leaves the item out on
+//! machines it doesn't hold on.
+//!
+//! Fully offline: the schemes repo and the items are plain local directories.
+
+mod utils;
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::{ensure, Result};
+use utils::{build_command_vec, run_command_with_env, setup, write_to_file, ARTIFACTS_DIR};
+
+const HOSTNAME_ENV_VAR: &str = "TINTY_HOSTNAME";
+
+/// Writes a `base16-mocha` scheme, a `shell` item used everywhere, and a
+/// `work-terminal` item limited to `work-*` hosts whose directory only exists
+/// on those.
+fn write_sources(config_path: &Path, root: &Path) -> Result<()> {
+    let scheme = fs::read_to_string("./tests/fixtures/schemes/tinty-generated.yaml")?
+        .replace("slug: tinty-generated", "slug: mocha");
+    write_to_file(root.join("schemes/base16/mocha.yaml"), &scheme)?;
+    write_to_file(root.join("shell/themes/base16-mocha.sh"), "shell mocha\n")?;
+
+    write_to_file(
+        config_path,
+        &format!(
+            "[schemes]\npath = \"{schemes}\"\n\n[[items]]\npath = \"{shell}\"\nname = \"shell\"\nthemes-dir = \"themes\"\n\n[[items]]\npath = \"{work}\"\nname = \"work-terminal\"\nthemes-dir = \"themes\"\nhook = \"exit 1\"\nwhen.hostname = [\"work-*\"]\nwhen.command-exists = \"sh\"\n",
+            schemes = root.join("schemes").display(),
+            shell = root.join("shell").display(),
+            work = root.join("work-terminal").display(),
+        ),
+    )
+}
+
+#[test]
+fn test_cli_item_is_left_out_when_condition_does_not_hold() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, install_vec, temp) =
+        setup("item_conditions_skipped", "install", false)?;
+    write_sources(&config_path, temp.path())?;
+    let apply_vec = build_command_vec("apply base16-mocha", &config_path, &data_path)?;
+    let config_vec = build_command_vec("config", &config_path, &data_path)?;
+    let env = [(HOSTNAME_ENV_VAR, "home-laptop")];
+
+    // ---
+    // Act
+    // ---
+    let (install_stdout, install_stderr) = run_command_with_env(&install_vec, &env)?;
+    let (_, apply_stderr) = run_command_with_env(&apply_vec, &env)?;
+    let (config_stdout, _) = run_command_with_env(&config_vec, &env)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(
+        install_stderr.is_empty() && !install_stdout.contains("work-terminal"),
+        "Expected only the unconditional item to be installed.\nstdout: {install_stdout}\nstderr: {install_stderr}"
+    );
+    ensure!(
+        apply_stderr.is_empty(),
+        "Expected apply to ignore the left out item, got: {apply_stderr}"
+    );
+    ensure!(
+        data_path
+            .join(ARTIFACTS_DIR)
+            .join("shell-themes-file.sh")
+            .is_file(),
+        "Expected the unconditional item to be applied"
+    );
+    ensure!(
+        config_stdout.contains(
+            "# Not used on this machine: the host name is \"home-laptop\"\n[[items]]\nname = \"work-terminal\""
+        ) && config_stdout.contains("when = { hostname = [\"work-*\"], command-exists = [\"sh\"] }"),
+        "Expected the left out item and why in the config.\nstdout: {config_stdout}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_item_is_used_when_condition_holds() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, install_vec, temp) =
+        setup("item_conditions_used", "install", false)?;
+    write_sources(&config_path, temp.path())?;
+    write_to_file(
+        temp.path().join("work-terminal/themes/base16-mocha.conf"),
+        "work mocha\n",
+    )?;
+
+    // ---
+    // Act
+    // ---
+    let (stdout, stderr) = run_command_with_env(&install_vec, &[(HOSTNAME_ENV_VAR, "work-42")])?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(
+        stderr.is_empty() && stdout.contains("work-terminal"),
+        "Expected the conditional item to be installed on a work host.\nstdout: {stdout}\nstderr: {stderr}"
+    );
+    ensure!(
+        data_path.join("repos/work-terminal").exists(),
+        "Expected the conditional item in repos/"
+    );
+
+    Ok(())
+}
@@ -0,0 +1,97 @@
+//! Integration tests for local-directory `[[items]]`, which `install` and
+//! `update` route through the local-path repository backend.
+//!
+//! Fully offline: items and the schemes repo are plain local directories, so
+//! nothing is cloned.
+
+mod utils;
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::{ensure, Result};
+use utils::{build_command_vec, run_command, setup, write_to_file};
+
+const ITEM_NAME: &str = "local-item";
+
+fn config(schemes: &Path, item: &Path) -> String {
+    format!(
+        "[schemes]\npath = \"{}\"\n\n[[items]]\npath = \"{}\"\nname = \"{ITEM_NAME}\"\nthemes-dir = \".\"\n",
+        schemes.display(),
+        item.display()
+    )
+}
+
+#[test]
+fn test_cli_update_repoints_local_item_when_path_changes() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, command_vec, temp) =
+        setup("local_item_update_repoints", "install", false)?;
+    let schemes = temp.path().join("schemes");
+    let first = temp.path().join("first");
+    let second = temp.path().join("second");
+    for dir in [&schemes, &first, &second] {
+        fs::create_dir_all(dir)?;
+    }
+    write_to_file(&config_path, &config(&schemes, &first))?;
+    let (stdout, stderr) = run_command(&command_vec)?;
+    ensure!(
+        stdout.contains(&format!("{ITEM_NAME} installed")),
+        "Expected the local item to be installed.\nstdout: {stdout}\nstderr: {stderr}"
+    );
+
+    // ---
+    // Act
+    // ---
+    write_to_file(&config_path, &config(&schemes, &second))?;
+    let update_vec = build_command_vec("update", &config_path, &data_path)?;
+    let (stdout, stderr) = run_command(&update_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(
+        stdout.contains(&format!(
+            "{ITEM_NAME} — left as-is (local path, no remote to update)"
+        )),
+        "Expected a local-directory no-op message.\nstdout: {stdout}\nstderr: {stderr}"
+    );
+    ensure!(
+        fs::read_link(data_path.join("repos").join(ITEM_NAME))? == second,
+        "The item symlink should follow the configured path."
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_install_refuses_directory_in_local_item_slot() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, command_vec, temp) =
+        setup("local_item_slot_is_directory", "install", false)?;
+    let schemes = temp.path().join("schemes");
+    let item = temp.path().join("item");
+    fs::create_dir_all(&schemes)?;
+    fs::create_dir_all(&item)?;
+    fs::create_dir_all(data_path.join("repos").join(ITEM_NAME))?;
+    write_to_file(&config_path, &config(&schemes, &item))?;
+
+    // ---
+    // Act
+    // ---
+    let (_, stderr) = run_command(&command_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(
+        stderr.contains("is not a symlink, but according to your config it should be"),
+        "Expected the install to refuse a real directory.\nstderr: {stderr}"
+    );
+
+    Ok(())
+}
@@ -0,0 +1,188 @@
+//! Integration tests for `tinty.lock`: pinning on `install`, `update
+//! --write-lock` and `sync --locked`.
+//!
+//! Fully offline: the item is a throwaway local git repository addressed with a
+//! `file://` URL (so it is cloned like a remote), and the schemes repo is a
+//! plain local directory.
+
+mod utils;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{ensure, Context, Result};
+use utils::{build_command_vec, run_command, setup, write_to_file};
+
+const ITEM_NAME: &str = "locked-item";
+
+/// What `utils::setup` returns: config path, data path, command and temp dir.
+type Setup = (PathBuf, PathBuf, Vec<String>, tempfile::TempDir);
+
+fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .with_context(|| format!("failed to run git {args:?} in {}", dir.display()))?;
+    ensure!(
+        output.status.success(),
+        "git {args:?} failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    Ok(String::from_utf8(output.stdout)?.trim().to_string())
+}
+
+/// Commits `contents` to `theme.txt` in `dir` and returns the new commit SHA.
+fn commit(dir: &Path, contents: &str) -> Result<String> {
+    fs::write(dir.join("theme.txt"), contents)?;
+    git(dir, &["add", "-A"])?;
+    git(dir, &["commit", "-q", "-m", contents])?;
+    git(dir, &["rev-parse", "HEAD"])
+}
+
+/// Sets up a config with a `file://` item on `main` and a local schemes
+/// directory, and returns the item's source directory and first commit.
+fn arrange(name: &str, command: &str) -> Result<(Setup, PathBuf, String)> {
+    let setup = setup(name, command, false)?;
+    let (config_path, _, _, temp) = &setup;
+    let source = temp.path().join("item-source");
+    let schemes = temp.path().join("schemes");
+    fs::create_dir_all(&source)?;
+    fs::create_dir_all(&schemes)?;
+    git(&source, &["init", "-q", "-b", "main"])?;
+    git(&source, &["config", "user.email", "tinty@test.local"])?;
+    git(&source, &["config", "user.name", "tinty test"])?;
+    git(&source, &["config", "commit.gpgsign", "false"])?;
+    let first = commit(&source, "first")?;
+
+    write_to_file(
+        config_path,
+        &format!(
+            "[schemes]\npath = \"{}\"\n\n[[items]]\npath = \"file://{}\"\nname = \"{ITEM_NAME}\"\nthemes-dir = \".\"\nrevision = \"main\"\n",
+            schemes.display(),
+            source.display()
+        ),
+    )?;
+
+    Ok((setup, source, first))
+}
+
+fn lockfile(config_path: &Path) -> Result<String> {
+    Ok(fs::read_to_string(
+        config_path.with_file_name("tinty.lock"),
+    )?)
+}
+
+fn item_head(data_path: &Path) -> Result<String> {
+    git(
+        &data_path.join("repos").join(ITEM_NAME),
+        &["rev-parse", "HEAD"],
+    )
+}
+
+#[test]
+fn test_cli_install_pins_commits_and_sync_locked_checks_them_out() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let ((config_path, data_path, command_vec, _temp), source, first) =
+        arrange("lockfile_sync_locked", "install")?;
+    run_command(&command_vec)?;
+    ensure!(
+        lockfile(&config_path)?.contains(&format!("commit = \"{first}\"")),
+        "install should pin the installed commit.\nGot: {}",
+        lockfile(&config_path)?
+    );
+    let second = commit(&source, "second")?;
+    run_command(&build_command_vec("update", &config_path, &data_path)?)?;
+    ensure!(
+        item_head(&data_path)? == second,
+        "update should move the item to the latest commit"
+    );
+
+    // ---
+    // Act
+    // ---
+    let (_, stderr) = run_command(&build_command_vec(
+        "sync --locked",
+        &config_path,
+        &data_path,
+    )?)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(
+        lockfile(&config_path)?.contains(&format!("commit = \"{first}\"")),
+        "update without --write-lock must leave the lockfile alone"
+    );
+    ensure!(
+        item_head(&data_path)? == first,
+        "sync --locked should check out the pinned commit.\nstderr: {stderr}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_update_write_lock_bumps_pins() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let ((config_path, data_path, command_vec, _temp), source, _) =
+        arrange("lockfile_write_lock", "install")?;
+    run_command(&command_vec)?;
+    let second = commit(&source, "second")?;
+
+    // ---
+    // Act
+    // ---
+    let (stdout, _) = run_command(&build_command_vec(
+        "update --write-lock",
+        &config_path,
+        &data_path,
+    )?)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(
+        stdout.contains("tinty.lock updated"),
+        "Expected a lockfile message.\nGot: {stdout}"
+    );
+    ensure!(
+        lockfile(&config_path)?.contains(&format!("commit = \"{second}\"")),
+        "update --write-lock should pin the updated commit"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_sync_locked_requires_lockfile() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let ((_, data_path, command_vec, _temp), _, _) =
+        arrange("lockfile_sync_locked_missing", "sync --locked")?;
+
+    // ---
+    // Act
+    // ---
+    let (_, stderr) = run_command(&command_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(
+        stderr.contains("tinty.lock does not exist"),
+        "Expected a missing lockfile error.\nGot: {stderr}"
+    );
+    ensure!(
+        !data_path.join("repos").join(ITEM_NAME).exists(),
+        "Nothing should be installed when the lockfile is missing"
+    );
+
+    Ok(())
+}
@@ -0,0 +1,297 @@
+//! Integration tests for archive and Git bundle sources, `--offline` and
+//! `tinty bundle export`.
+//!
+//! Fully offline: sources are `.tar.gz` and `.zip` archives, Git bundles and
+//! local git repositories built in a temp dir, and the schemes repo is a plain
+//! local directory.
+
+mod utils;
+
+use std::fmt::Write as _;
+use std::fs::{self, File};
+use std::io::Write as _;
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::{ensure, Context, Result};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use utils::{build_command_vec, run_command, setup, write_to_file};
+
+fn git(dir: &Path, args: &[&str]) -> Result<()> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .with_context(|| format!("failed to run git {args:?} in {}", dir.display()))?;
+    ensure!(
+        output.status.success(),
+        "git {args:?} failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    Ok(())
+}
+
+/// Writes a `.tar.gz` at `archive` holding `theme.txt` with `contents`, inside
+/// a wrapping `name-main/` directory like GitHub's source downloads.
+fn write_tar_gz(archive: &Path, name: &str, contents: &str) -> Result<()> {
+    let mut builder = tar::Builder::new(GzEncoder::new(
+        File::create(archive)?,
+        Compression::default(),
+    ));
+    let mut header = tar::Header::new_gnu();
+    header.set_size(contents.len().try_into()?);
+    header.set_mode(0o644);
+    header.set_cksum();
+    builder.append_data(
+        &mut header,
+        format!("{name}-main/theme.txt"),
+        contents.as_bytes(),
+    )?;
+    builder.into_inner()?.finish()?;
+    Ok(())
+}
+
+/// Writes a config with a local schemes directory and one item per
+/// `(name, path)`.
+fn write_config(config_path: &Path, root: &Path, items: &[(&str, String)]) -> Result<()> {
+    fs::create_dir_all(root.join("schemes"))?;
+    let mut config = format!("[schemes]\npath = \"{}\"\n", root.join("schemes").display());
+    for (name, path) in items {
+        write!(
+            config,
+            "\n[[items]]\npath = \"{path}\"\nname = \"{name}\"\nthemes-dir = \".\"\n"
+        )?;
+    }
+    write_to_file(config_path, &config)
+}
+
+#[test]
+fn test_cli_install_and_update_tar_gz_source() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, command_vec, temp) =
+        setup("offline_tar_gz", "install --offline", false)?;
+    let archive = temp.path().join("tar-item.tar.gz");
+    write_tar_gz(&archive, "tar-item", "first")?;
+    write_config(
+        &config_path,
+        temp.path(),
+        &[("tar-item", archive.display().to_string())],
+    )?;
+    let theme = data_path.join("repos/tar-item/theme.txt");
+
+    // ---
+    // Act
+    // ---
+    let (_, install_stderr) = run_command(&command_vec)?;
+    let installed = fs::read_to_string(&theme).unwrap_or_default();
+    write_tar_gz(&archive, "tar-item", "second")?;
+    let update_vec = build_command_vec("update --offline", &config_path, &data_path)?;
+    let (_, update_stderr) = run_command(&update_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(
+        install_stderr.is_empty() && update_stderr.is_empty(),
+        "Expected empty stderr, got: {install_stderr}{update_stderr}"
+    );
+    ensure!(
+        installed == "first",
+        "Expected the archive to be unpacked without its wrapping directory, got: {installed:?}"
+    );
+    ensure!(
+        fs::read_to_string(&theme)? == "second",
+        "Expected update to unpack the replaced archive"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_install_zip_source() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, command_vec, temp) =
+        setup("offline_zip", "install --offline", false)?;
+    let archive = temp.path().join("zip-item.zip");
+    let mut zip = zip::ZipWriter::new(File::create(&archive)?);
+    zip.start_file("theme.txt", zip::write::SimpleFileOptions::default())?;
+    zip.write_all(b"zipped")?;
+    zip.start_file("README.md", zip::write::SimpleFileOptions::default())?;
+    zip.write_all(b"zip-item")?;
+    zip.finish()?;
+    write_config(
+        &config_path,
+        temp.path(),
+        &[("zip-item", archive.display().to_string())],
+    )?;
+
+    // ---
+    // Act
+    // ---
+    let (_, stderr) = run_command(&command_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(stderr.is_empty(), "Expected empty stderr, got: {stderr}");
+    ensure!(
+        fs::read_to_string(data_path.join("repos/zip-item/theme.txt"))? == "zipped",
+        "Expected the zip archive to be unpacked"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_install_git_bundle_source() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, command_vec, temp) =
+        setup("offline_git_bundle", "install --offline", false)?;
+    let source = temp.path().join("bundled");
+    fs::create_dir_all(&source)?;
+    fs::write(source.join("theme.txt"), "bundled")?;
+    git(&source, &["init", "-q", "-b", "main"])?;
+    git(&source, &["add", "-A"])?;
+    git(
+        &source,
+        &[
+            "-c",
+            "user.email=tinty@test.local",
+            "-c",
+            "user.name=tinty test",
+            "-c",
+            "commit.gpgsign=false",
+            "commit",
+            "-q",
+            "-m",
+            "bundled",
+        ],
+    )?;
+    let bundle = temp.path().join("bundled.bundle");
+    git(
+        &source,
+        &[
+            "bundle",
+            "create",
+            "-q",
+            &bundle.display().to_string(),
+            "--all",
+        ],
+    )?;
+    write_config(
+        &config_path,
+        temp.path(),
+        &[("bundled-item", format!("file://{}", bundle.display()))],
+    )?;
+
+    // ---
+    // Act
+    // ---
+    let (_, stderr) = run_command(&command_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(stderr.is_empty(), "Expected empty stderr, got: {stderr}");
+    ensure!(
+        fs::read_to_string(data_path.join("repos/bundled-item/theme.txt"))? == "bundled",
+        "Expected the bundle to be cloned"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_install_offline_rejects_network_sources() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, command_vec, temp) =
+        setup("offline_rejects_network", "install --offline", false)?;
+    write_config(
+        &config_path,
+        temp.path(),
+        &[(
+            "remote-item",
+            "https://github.com/tinted-theming/tinted-shell".to_string(),
+        )],
+    )?;
+
+    // ---
+    // Act
+    // ---
+    let (_, stderr) = run_command(&command_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(
+        stderr.contains("--offline was given") && stderr.contains("remote-item"),
+        "Expected an error naming the network source.\nstderr: {stderr}"
+    );
+    ensure!(
+        !data_path.join("repos/remote-item").exists(),
+        "Nothing should be installed"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_bundle_export_packs_every_repository() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, install_vec, temp) =
+        setup("offline_bundle_export", "install", false)?;
+    let archive = temp.path().join("tar-item.tar.gz");
+    write_tar_gz(&archive, "tar-item", "exported")?;
+    write_config(
+        &config_path,
+        temp.path(),
+        &[("tar-item", archive.display().to_string())],
+    )?;
+    write_to_file(temp.path().join("schemes/base16/example.yaml"), "scheme")?;
+    run_command(&install_vec)?;
+    let output = temp.path().join("export.tar.gz");
+    let command_vec = build_command_vec(
+        &format!("bundle export {}", output.display()),
+        &config_path,
+        &data_path,
+    )?;
+
+    // ---
+    // Act
+    // ---
+    let (stdout, stderr) = run_command(&command_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(stderr.is_empty(), "Expected empty stderr, got: {stderr}");
+    ensure!(
+        stdout.contains("Bundle written to"),
+        "Expected a summary.\nstdout: {stdout}"
+    );
+    let mut entries = Vec::new();
+    for entry in tar::Archive::new(GzDecoder::new(File::open(&output)?)).entries()? {
+        entries.push(entry?.path()?.display().to_string());
+    }
+    ensure!(
+        entries
+            .iter()
+            .any(|path| path == "schemes/base16/example.yaml")
+            && entries.iter().any(|path| path == "tar-item/theme.txt"),
+        "Expected the schemes repo and the item in the bundle, got: {entries:?}"
+    );
+
+    Ok(())
+}
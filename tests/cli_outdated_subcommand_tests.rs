@@ -0,0 +1,252 @@
+//! Integration tests for `tinty outdated` and `tinty update --check`.
+//!
+//! Fully offline: the item is a throwaway local git repository addressed with a
+//! `file://` URL (so it is cloned and fetched like a remote), and the schemes
+//! repo is a plain local directory.
+
+mod utils;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{anyhow, ensure, Context, Result};
+use utils::{build_command_vec, run_command, setup, write_to_file};
+
+const ITEM_NAME: &str = "outdated-item";
+
+fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .with_context(|| format!("failed to run git {args:?} in {}", dir.display()))?;
+    ensure!(
+        output.status.success(),
+        "git {args:?} failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    Ok(String::from_utf8(output.stdout)?.trim().to_string())
+}
+
+/// Commits `contents` to `theme.txt` in `dir` and returns the new commit SHA.
+fn commit(dir: &Path, contents: &str) -> Result<String> {
+    fs::write(dir.join("theme.txt"), contents)?;
+    git(dir, &["add", "-A"])?;
+    git(dir, &["commit", "-q", "-m", contents])?;
+    git(dir, &["rev-parse", "HEAD"])
+}
+
+/// Runs a tinty command and returns its stdout, stderr and whether it exited
+/// successfully.
+fn run_with_status(command_vec: &[String]) -> Result<(String, String, bool)> {
+    let (program, args) = command_vec
+        .split_first()
+        .ok_or_else(|| anyhow!("empty command"))?;
+    let output = Command::new(program).args(args).output()?;
+
+    Ok((
+        String::from_utf8(output.stdout)?,
+        String::from_utf8(output.stderr)?,
+        output.status.success(),
+    ))
+}
+
+/// Installs a `file://` item on `main` next to a local schemes directory and
+/// returns the config path, data path, the item's source directory and the
+/// temp dir guard.
+fn arrange(name: &str) -> Result<(PathBuf, PathBuf, PathBuf, tempfile::TempDir)> {
+    let (config_path, data_path, install_vec, temp) = setup(name, "install", false)?;
+    let source = temp.path().join("item-source");
+    let schemes = temp.path().join("schemes");
+    fs::create_dir_all(&source)?;
+    fs::create_dir_all(&schemes)?;
+    git(&source, &["init", "-q", "-b", "main"])?;
+    git(&source, &["config", "user.email", "tinty@test.local"])?;
+    git(&source, &["config", "user.name", "tinty test"])?;
+    git(&source, &["config", "commit.gpgsign", "false"])?;
+    commit(&source, "first")?;
+
+    write_to_file(
+        &config_path,
+        &format!(
+            "[schemes]\npath = \"{}\"\n\n[[items]]\npath = \"file://{}\"\nname = \"{ITEM_NAME}\"\nthemes-dir = \".\"\nrevision = \"main\"\n",
+            schemes.display(),
+            source.display()
+        ),
+    )?;
+    run_command(&install_vec)?;
+
+    Ok((config_path, data_path, source, temp))
+}
+
+#[test]
+fn test_cli_outdated_up_to_date() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, _, _temp) = arrange("outdated_up_to_date")?;
+    let command_vec = build_command_vec("outdated", &config_path, &data_path)?;
+
+    // ---
+    // Act
+    // ---
+    let (stdout, stderr, is_success) = run_with_status(&command_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(
+        is_success,
+        "Expected a zero exit status.\nstdout: {stdout}\nstderr: {stderr}"
+    );
+    ensure!(
+        stdout.contains(&format!("{ITEM_NAME}: up to date"))
+            && stdout.contains("schemes: local path, nothing to check"),
+        "Expected every repository to be reported as current.\nstdout: {stdout}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_outdated_reports_commits_behind_without_updating() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, source, _temp) = arrange("outdated_behind")?;
+    let item_path = data_path.join("repos").join(ITEM_NAME);
+    let installed = git(&item_path, &["rev-parse", "HEAD"])?;
+    commit(&source, "second")?;
+    let latest = commit(&source, "third")?;
+    fs::write(item_path.join("theme.txt"), "local edit")?;
+    let command_vec = build_command_vec("outdated --json", &config_path, &data_path)?;
+
+    // ---
+    // Act
+    // ---
+    let (stdout, stderr, is_success) = run_with_status(&command_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(!is_success, "Expected a non-zero exit status");
+    ensure!(
+        stderr.contains("1 of 2 repositories are out of date"),
+        "Expected an out-of-date summary.\nstderr: {stderr}"
+    );
+    let reports: serde_json::Value = serde_json::from_str(&stdout)?;
+    let item = reports
+        .get(0)
+        .ok_or_else(|| anyhow!("no report for the item: {stdout}"))?;
+    ensure!(
+        item.get("current").and_then(serde_json::Value::as_str) == Some(installed.as_str())
+            && item.get("target").and_then(serde_json::Value::as_str) == Some(latest.as_str())
+            && item.get("behind").and_then(serde_json::Value::as_u64) == Some(2)
+            && item.get("dirty").and_then(serde_json::Value::as_bool) == Some(true)
+            && item.get("outdated").and_then(serde_json::Value::as_bool) == Some(true),
+        "Unexpected report for the item: {item}"
+    );
+    ensure!(
+        git(&item_path, &["rev-parse", "HEAD"])? == installed
+            && fs::read_to_string(item_path.join("theme.txt"))? == "local edit",
+        "The check should not touch the installed repository"
+    );
+    ensure!(
+        git(&item_path, &["remote"])? == "origin",
+        "The temporary remote should be removed"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_update_check_reports_without_updating() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, source, _temp) = arrange("update_check")?;
+    let item_path = data_path.join("repos").join(ITEM_NAME);
+    let installed = git(&item_path, &["rev-parse", "HEAD"])?;
+    commit(&source, "second")?;
+    let command_vec = build_command_vec("update --check", &config_path, &data_path)?;
+
+    // ---
+    // Act
+    // ---
+    let (stdout, _, is_success) = run_with_status(&command_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(!is_success, "Expected a non-zero exit status");
+    ensure!(
+        stdout.contains(&format!("{ITEM_NAME}: 1 commit behind")),
+        "Expected the item to be reported as behind.\nstdout: {stdout}"
+    );
+    ensure!(
+        git(&item_path, &["rev-parse", "HEAD"])? == installed,
+        "`update --check` should not update the repository"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_update_check_rejects_update_only_flags() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, _, _temp) = arrange("update_check_flags")?;
+
+    for flag in ["--offline", "--jobs 2", "--reapply", "--no-reapply"] {
+        let command_vec =
+            build_command_vec(&format!("update --check {flag}"), &config_path, &data_path)?;
+
+        // ---
+        // Act
+        // ---
+        let (stdout, stderr, is_success) = run_with_status(&command_vec)?;
+
+        // ------
+        // Assert
+        // ------
+        ensure!(
+            !is_success && stdout.is_empty() && stderr.contains("cannot be used with"),
+            "Expected `update --check {flag}` to be a usage error.\nstderr: {stderr}"
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_outdated_reports_unreachable_repository_and_checks_the_rest() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, source, _temp) = arrange("outdated_unreachable")?;
+    fs::rename(&source, source.with_file_name("moved-item-source"))?;
+    let command_vec = build_command_vec("outdated", &config_path, &data_path)?;
+
+    // ---
+    // Act
+    // ---
+    let (stdout, stderr, is_success) = run_with_status(&command_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(!is_success, "Expected a non-zero exit status");
+    ensure!(
+        stdout.contains(&format!("{ITEM_NAME}: could not be checked: Error checking {ITEM_NAME}"))
+            && stdout.contains("schemes: local path, nothing to check"),
+        "Expected the unreachable item in the report next to the other repositories.\nstdout: {stdout}"
+    );
+    ensure!(
+        stderr.contains("1 of 2 repositories could not be checked"),
+        "Expected a could-not-be-checked summary.\nstderr: {stderr}"
+    );
+
+    Ok(())
+}
@@ -0,0 +1,169 @@
+//! Integration tests for `--jobs`: `install` and `update` process items
+//! concurrently, print each item's output in config order and report every
+//! failure together at the end.
+//!
+//! Fully offline: items are throwaway local git repositories addressed with
+//! `file://` URLs, and the schemes repo is a plain local directory.
+
+mod utils;
+
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::{ensure, Context, Result};
+use utils::{build_command_vec, run_command, setup, write_to_file};
+
+const ITEM_NAMES: [&str; 4] = ["item-d", "item-a", "item-c", "item-b"];
+
+fn git(dir: &Path, args: &[&str]) -> Result<()> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .with_context(|| format!("failed to run git {args:?} in {}", dir.display()))?;
+    ensure!(
+        output.status.success(),
+        "git {args:?} failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    Ok(())
+}
+
+/// Creates a git repository with one commit for each item and a local schemes
+/// directory.
+fn create_sources(root: &Path) -> Result<()> {
+    fs::create_dir_all(root.join("schemes"))?;
+
+    for name in ITEM_NAMES {
+        let source = root.join(name);
+        fs::create_dir_all(&source)?;
+        fs::write(source.join("theme.txt"), name)?;
+        git(&source, &["init", "-q", "-b", "main"])?;
+        git(&source, &["add", "-A"])?;
+        git(
+            &source,
+            &[
+                "-c",
+                "user.email=tinty@test.local",
+                "-c",
+                "user.name=tinty test",
+                "-c",
+                "commit.gpgsign=false",
+                "commit",
+                "-q",
+                "-m",
+                name,
+            ],
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Writes a config listing the sources from `create_sources`, plus an item
+/// whose source does not exist when `with_broken`.
+fn write_config(config_path: &Path, root: &Path, with_broken: bool) -> Result<()> {
+    let mut config = format!("[schemes]\npath = \"{}\"\n", root.join("schemes").display());
+
+    for name in ITEM_NAMES {
+        write!(
+            config,
+            "\n[[items]]\npath = \"file://{}\"\nname = \"{name}\"\nthemes-dir = \".\"\nrevision = \"main\"\n",
+            root.join(name).display()
+        )?;
+    }
+
+    if with_broken {
+        write!(
+            config,
+            "\n[[items]]\npath = \"file://{}\"\nname = \"broken-item\"\nthemes-dir = \".\"\n",
+            root.join("does-not-exist").display()
+        )?;
+    }
+
+    write_to_file(config_path, &config)
+}
+
+/// The position of each line in `stdout` that mentions an item, in item order.
+fn positions(stdout: &str) -> Vec<Option<usize>> {
+    ITEM_NAMES
+        .iter()
+        .map(|name| stdout.find(&format!("{name} ")))
+        .collect()
+}
+
+#[test]
+fn test_cli_install_with_jobs_keeps_config_order() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, command_vec, temp) =
+        setup("parallel_install_order", "install --jobs 4", false)?;
+    create_sources(temp.path())?;
+    write_config(&config_path, temp.path(), false)?;
+
+    // ---
+    // Act
+    // ---
+    let (stdout, stderr) = run_command(&command_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(stderr.is_empty(), "Expected empty stderr, got: {stderr}");
+    let positions = positions(&stdout);
+    ensure!(
+        positions.iter().all(Option::is_some) && positions.is_sorted(),
+        "Expected one line per item in config order.\nstdout: {stdout}"
+    );
+    for name in ITEM_NAMES {
+        ensure!(
+            data_path
+                .join("repos")
+                .join(name)
+                .join("theme.txt")
+                .exists(),
+            "Expected {name} to be installed"
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_update_with_jobs_reports_all_failures() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, install_vec, temp) =
+        setup("parallel_update_failures", "install", false)?;
+    create_sources(temp.path())?;
+    write_config(&config_path, temp.path(), false)?;
+    run_command(&install_vec)?;
+    write_config(&config_path, temp.path(), true)?;
+    // An installed item whose source has gone away fails to fetch.
+    fs::create_dir_all(data_path.join("repos/broken-item"))?;
+    git(&data_path.join("repos/broken-item"), &["init", "-q"])?;
+    let command_vec = build_command_vec("update --jobs 2", &config_path, &data_path)?;
+
+    // ---
+    // Act
+    // ---
+    let (stdout, stderr) = run_command(&command_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(
+        positions(&stdout).iter().all(Option::is_some),
+        "Expected the other items to be updated.\nstdout: {stdout}\nstderr: {stderr}"
+    );
+    ensure!(
+        stderr.contains("Failed to update 1 of 6 repositories") && stderr.contains("broken-item: "),
+        "Expected an aggregated error naming the failed item.\nstderr: {stderr}"
+    );
+
+    Ok(())
+}
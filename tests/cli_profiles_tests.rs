@@ -0,0 +1,210 @@
+//! Integration tests for `[profiles.<name>]`, selected with `--profile` or
+//! `TINTY_PROFILE`.
+//!
+//! Fully offline: the schemes repo and the items are plain local directories.
+
+mod utils;
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::{ensure, Result};
+use utils::{
+    build_command_vec, run_command, run_command_with_env, setup, write_to_file, ARTIFACTS_DIR,
+    CURRENT_SCHEME_FILE_NAME,
+};
+
+const PROFILE_ENV_VAR: &str = "TINTY_PROFILE";
+
+/// Writes a `base16-mocha` scheme, two items with a theme for it each, and a
+/// `presentation` profile that only themes the `editor` item.
+fn write_sources(config_path: &Path, root: &Path) -> Result<()> {
+    let scheme = fs::read_to_string("./tests/fixtures/schemes/tinty-generated.yaml")?
+        .replace("slug: tinty-generated", "slug: mocha");
+    write_to_file(root.join("schemes/base16/mocha.yaml"), &scheme)?;
+    write_to_file(
+        root.join("editor/themes/base16-mocha.vim"),
+        "editor mocha\n",
+    )?;
+    write_to_file(
+        root.join("terminal/themes/base16-mocha.sh"),
+        "terminal mocha\n",
+    )?;
+
+    write_to_file(
+        config_path,
+        &format!(
+            "[schemes]\npath = \"{schemes}\"\n\n[[items]]\npath = \"{editor}\"\nname = \"editor\"\nthemes-dir = \"themes\"\n\n[[items]]\npath = \"{terminal}\"\nname = \"terminal\"\nthemes-dir = \"themes\"\n\n[profiles.presentation]\nitems = [\"editor\"]\nhooks = [\"echo presenting $TINTY_PROFILE\"]\n",
+            schemes = root.join("schemes").display(),
+            editor = root.join("editor").display(),
+            terminal = root.join("terminal").display(),
+        ),
+    )
+}
+
+/// The file names in `dir`, sorted.
+fn file_names(dir: &Path) -> Result<Vec<String>> {
+    let mut names = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        names.push(entry?.file_name().to_string_lossy().into_owned());
+    }
+    names.sort();
+    Ok(names)
+}
+
+#[test]
+fn test_cli_apply_with_profile_uses_its_items_hooks_and_artifacts() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, install_vec, temp) = setup("profiles_apply", "install", false)?;
+    write_sources(&config_path, temp.path())?;
+    run_command(&install_vec)?;
+    let apply_vec = build_command_vec(
+        "apply base16-mocha --profile presentation",
+        &config_path,
+        &data_path,
+    )?;
+    let current_vec = build_command_vec("current", &config_path, &data_path)?;
+
+    // ---
+    // Act
+    // ---
+    let (stdout, stderr) = run_command(&apply_vec)?;
+    let (current_stdout, current_stderr) =
+        run_command_with_env(&current_vec, &[(PROFILE_ENV_VAR, "presentation")])?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(stderr.is_empty(), "Expected empty stderr, got: {stderr}");
+    ensure!(
+        stdout.contains("presenting presentation"),
+        "Expected the profile's hook to run with {PROFILE_ENV_VAR} set.\nstdout: {stdout}"
+    );
+    let artifacts = data_path.join("profiles/presentation").join(ARTIFACTS_DIR);
+    let names = file_names(&artifacts)?;
+    ensure!(
+        names == [CURRENT_SCHEME_FILE_NAME, "editor-themes-file.vim"],
+        "Expected only the profile's item to be themed, got: {names:?}"
+    );
+    ensure!(
+        !data_path.join(ARTIFACTS_DIR).exists(),
+        "Expected the default artifacts to be left alone"
+    );
+    ensure!(
+        current_stderr.is_empty() && current_stdout.trim() == "base16-mocha",
+        "Expected the profile's current scheme.\nstdout: {current_stdout}\nstderr: {current_stderr}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_unknown_profile_errors() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, _, temp) = setup("profiles_unknown", "install", false)?;
+    write_sources(&config_path, temp.path())?;
+    let install_vec = build_command_vec("install --profile travel", &config_path, &data_path)?;
+
+    // ---
+    // Act
+    // ---
+    let (_, stderr) = run_command(&install_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(
+        stderr.contains("Profile \"travel\"") && stderr.contains("Defined profiles: presentation"),
+        "Expected an unknown profile error.\nstderr: {stderr}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_apply_with_profile_keeps_default_symlinks() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, install_vec, temp) = setup("profiles_symlinks", "install", false)?;
+    write_sources(&config_path, temp.path())?;
+    run_command(&install_vec)?;
+    let apply_vec = build_command_vec("apply base16-mocha", &config_path, &data_path)?;
+    let profile_apply_vec = build_command_vec(
+        "apply base16-mocha --profile presentation",
+        &config_path,
+        &data_path,
+    )?;
+
+    // ---
+    // Act
+    // ---
+    run_command(&apply_vec)?;
+    let (_, stderr) = run_command(&profile_apply_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(stderr.is_empty(), "Expected empty stderr, got: {stderr}");
+    for name in ["editor-themes-file.vim", "terminal-themes-file.sh"] {
+        let target = fs::read_link(data_path.join(name))?;
+        ensure!(
+            target == data_path.join(ARTIFACTS_DIR).join(name),
+            "Expected {name} to keep pointing at the default artifacts, got: {}",
+            target.display()
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_prune_removes_stale_profile_artifacts() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, install_vec, temp) = setup("profiles_prune", "install", false)?;
+    write_sources(&config_path, temp.path())?;
+    run_command(&install_vec)?;
+    let apply_vec = build_command_vec(
+        "apply base16-mocha --profile presentation",
+        &config_path,
+        &data_path,
+    )?;
+    let prune_vec = build_command_vec("prune --yes", &config_path, &data_path)?;
+    let artifact = data_path
+        .join("profiles/presentation")
+        .join(ARTIFACTS_DIR)
+        .join("editor-themes-file.vim");
+
+    // ---
+    // Act
+    // ---
+    run_command(&apply_vec)?;
+    let config = fs::read_to_string(&config_path)?
+        .replace(
+            &format!(
+                "[[items]]\npath = \"{}\"\nname = \"editor\"\nthemes-dir = \"themes\"\n\n",
+                temp.path().join("editor").display()
+            ),
+            "",
+        )
+        .replace("items = [\"editor\"]\n", "");
+    write_to_file(&config_path, &config)?;
+    let (stdout, stderr) = run_command(&prune_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(stderr.is_empty(), "Expected empty stderr, got: {stderr}");
+    ensure!(
+        !artifact.exists() && stdout.contains(&artifact.display().to_string()),
+        "Expected the profile's artifact of the removed item to be pruned.\nstdout: {stdout}"
+    );
+
+    Ok(())
+}
@@ -0,0 +1,265 @@
+//! Integration tests for `tinty prune` and `tinty uninstall`.
+//!
+//! Fully offline: items are throwaway local git repositories addressed with
+//! `file://` URLs, and the schemes repo is a plain local directory holding the
+//! `tinty-generated` fixture.
+
+mod utils;
+
+use std::fs;
+use std::os::unix::fs::symlink;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{ensure, Context, Result};
+use utils::{build_command_vec, run_command, setup, write_to_file, ARTIFACTS_DIR};
+
+const KEPT: &str = "kept-item";
+const REMOVED: &str = "removed-item";
+
+fn git(dir: &Path, args: &[&str]) -> Result<()> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .with_context(|| format!("failed to run git {args:?} in {}", dir.display()))?;
+    ensure!(
+        output.status.success(),
+        "git {args:?} failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    Ok(())
+}
+
+fn item_config(root: &Path, name: &str) -> String {
+    format!(
+        "\n[[items]]\npath = \"file://{}\"\nname = \"{name}\"\nthemes-dir = \"themes\"\n",
+        root.join(name).display()
+    )
+}
+
+/// Leaves a data directory as if `apply` had run for both items and
+/// `removed-item` was then deleted from the config, with a theme built into
+/// `kept-item` for a custom scheme that no longer exists. Returns the config
+/// and data paths with the temp dir guard.
+fn arrange(name: &str) -> Result<(PathBuf, PathBuf, tempfile::TempDir)> {
+    let (config_path, data_path, install_vec, temp) = setup(name, "install", false)?;
+    let root = temp.path();
+    write_to_file(
+        root.join("schemes/base16/tinty-generated.yaml"),
+        &fs::read_to_string("./tests/fixtures/schemes/tinty-generated.yaml")?,
+    )?;
+    for item in [KEPT, REMOVED] {
+        let source = root.join(item);
+        fs::create_dir_all(source.join("themes"))?;
+        fs::write(source.join("themes/README.md"), item)?;
+        git(&source, &["init", "-q", "-b", "main"])?;
+        git(&source, &["add", "-A"])?;
+        git(
+            &source,
+            &[
+                "-c",
+                "user.email=tinty@test.local",
+                "-c",
+                "user.name=tinty test",
+                "-c",
+                "commit.gpgsign=false",
+                "commit",
+                "-q",
+                "-m",
+                item,
+            ],
+        )?;
+    }
+    let schemes = format!("[schemes]\npath = \"{}\"\n", root.join("schemes").display());
+    write_to_file(
+        &config_path,
+        &format!(
+            "{schemes}{}{}",
+            item_config(root, KEPT),
+            item_config(root, REMOVED)
+        ),
+    )?;
+    run_command(&install_vec)?;
+    write_to_file(
+        &config_path,
+        &format!("{schemes}{}", item_config(root, KEPT)),
+    )?;
+
+    for item in [KEPT, REMOVED] {
+        let artifact = data_path
+            .join(ARTIFACTS_DIR)
+            .join(format!("{item}-themes-file.sh"));
+        write_to_file(&artifact, "theme")?;
+        symlink(&artifact, data_path.join(format!("{item}-themes-file.sh")))?;
+    }
+    let themes = data_path.join("repos").join(KEPT).join("themes");
+    fs::write(themes.join("base16-tinty-generated.sh"), "built")?;
+    fs::write(themes.join("base16-deleted-custom.sh"), "built")?;
+
+    Ok((config_path, data_path, temp))
+}
+
+#[test]
+fn test_cli_prune_dry_run_lists_without_removing() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, _temp) = arrange("prune_dry_run")?;
+    let command_vec = build_command_vec("prune --dry-run", &config_path, &data_path)?;
+
+    // ---
+    // Act
+    // ---
+    let (stdout, stderr) = run_command(&command_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(stderr.is_empty(), "Expected empty stderr, got: {stderr}");
+    ensure!(
+        stdout.contains("Would remove:")
+            && stdout.contains(&format!("repos/{REMOVED}"))
+            && stdout.contains(&format!("{REMOVED}-themes-file.sh"))
+            && stdout.contains("base16-deleted-custom.sh"),
+        "Expected the stale paths to be listed.\nstdout: {stdout}"
+    );
+    ensure!(
+        !stdout
+            .lines()
+            .any(|line| line.ends_with(&format!("repos/{KEPT}"))),
+        "Expected the configured item to be kept.\nstdout: {stdout}"
+    );
+    ensure!(
+        data_path.join("repos").join(REMOVED).exists(),
+        "A dry run should not remove anything"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_prune_removes_stale_paths() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, _temp) = arrange("prune_removes")?;
+    let command_vec = build_command_vec("prune --yes", &config_path, &data_path)?;
+    let kept_themes = data_path.join("repos").join(KEPT).join("themes");
+
+    // ---
+    // Act
+    // ---
+    let (stdout, stderr) = run_command(&command_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(stderr.is_empty(), "Expected empty stderr, got: {stderr}");
+    ensure!(
+        stdout.contains("Removed 4 paths"),
+        "Expected four removals.\nstdout: {stdout}"
+    );
+    ensure!(
+        !data_path.join("repos").join(REMOVED).exists()
+            && !data_path
+                .join(ARTIFACTS_DIR)
+                .join(format!("{REMOVED}-themes-file.sh"))
+                .exists()
+            && fs::symlink_metadata(data_path.join(format!("{REMOVED}-themes-file.sh"))).is_err()
+            && !kept_themes.join("base16-deleted-custom.sh").exists(),
+        "Expected the removed item and the stale build output to be gone"
+    );
+    ensure!(
+        kept_themes.join("base16-tinty-generated.sh").exists()
+            && data_path.join(format!("{KEPT}-themes-file.sh")).exists(),
+        "Expected the configured item and themes for existing schemes to be kept"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_uninstall_configured_item() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, _temp) = arrange("uninstall_configured")?;
+    let command_vec =
+        build_command_vec(&format!("uninstall {KEPT} --yes"), &config_path, &data_path)?;
+
+    // ---
+    // Act
+    // ---
+    let (stdout, stderr) = run_command(&command_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(stderr.is_empty(), "Expected empty stderr, got: {stderr}");
+    ensure!(
+        stdout.contains("Removed 3 paths") && stdout.contains("is still in your config"),
+        "Expected the item to be removed with a reminder.\nstdout: {stdout}"
+    );
+    ensure!(
+        !data_path.join("repos").join(KEPT).exists()
+            && fs::symlink_metadata(data_path.join(format!("{KEPT}-themes-file.sh"))).is_err()
+            && data_path.join("repos").join(REMOVED).exists(),
+        "Expected only the uninstalled item to be removed"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_prune_keeps_scheme_source_themes_and_foreign_symlinks() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, temp) = arrange("prune_keeps_sources")?;
+    let company = temp.path().join("company");
+    write_to_file(
+        company.join("base16/brand.yaml"),
+        &fs::read_to_string("./tests/fixtures/schemes/tinty-generated.yaml")?
+            .replace("slug: tinty-generated", "slug: brand"),
+    )?;
+    let config = fs::read_to_string(&config_path)?;
+    write_to_file(
+        &config_path,
+        &format!(
+            "{config}\n[[scheme-sources]]\nname = \"company\"\npath = \"{}\"\n",
+            company.display()
+        ),
+    )?;
+    run_command(&build_command_vec("install", &config_path, &data_path)?)?;
+    let kept_themes = data_path.join("repos").join(KEPT).join("themes");
+    fs::write(kept_themes.join("base16-brand.sh"), "built")?;
+    let foreign_link = data_path.join("not-from-tinty");
+    symlink(temp.path().join("missing"), &foreign_link)?;
+    let command_vec = build_command_vec("prune --yes", &config_path, &data_path)?;
+
+    // ---
+    // Act
+    // ---
+    let (stdout, stderr) = run_command(&command_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(stderr.is_empty(), "Expected empty stderr, got: {stderr}");
+    ensure!(
+        stdout.contains("Removed 4 paths")
+            && !kept_themes.join("base16-deleted-custom.sh").exists(),
+        "Expected only the stale paths to be removed.\nstdout: {stdout}"
+    );
+    ensure!(
+        kept_themes.join("base16-brand.sh").exists(),
+        "Expected the theme built for the scheme source scheme to be kept"
+    );
+    ensure!(
+        fs::symlink_metadata(&foreign_link).is_ok(),
+        "Expected a broken symlink Tinty didn't create to be kept"
+    );
+
+    Ok(())
+}
@@ -0,0 +1,207 @@
+//! Integration tests for `--reapply` on `tinty update` and `tinty sync`:
+//! re-applying the current scheme when an update changed its theme.
+//!
+//! Fully offline: the item and the schemes repo are throwaway local git
+//! repositories addressed with `file://` URLs.
+
+mod utils;
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::{ensure, Context, Result};
+use utils::{build_command_vec, run_command, setup, write_to_file};
+
+const ITEM_NAME: &str = "reapply-item";
+
+fn git(dir: &Path, args: &[&str]) -> Result<()> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .with_context(|| format!("failed to run git {args:?} in {}", dir.display()))?;
+    ensure!(
+        output.status.success(),
+        "git {args:?} failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    Ok(())
+}
+
+fn commit(dir: &Path, message: &str) -> Result<()> {
+    git(dir, &["add", "-A"])?;
+    git(
+        dir,
+        &[
+            "-c",
+            "user.email=tinty@test.local",
+            "-c",
+            "user.name=tinty test",
+            "-c",
+            "commit.gpgsign=false",
+            "commit",
+            "-q",
+            "-m",
+            message,
+        ],
+    )
+}
+
+/// Creates the item, with themes for `base16-mocha` and `base16-other`, and
+/// the schemes repo, then a config whose hook prints the operation and the
+/// applied theme.
+fn create_sources(config_path: &Path, root: &Path, extra_config: &str) -> Result<()> {
+    let item = root.join(ITEM_NAME);
+    write_to_file(item.join("themes/base16-mocha.sh"), "mocha v1")?;
+    write_to_file(item.join("themes/base16-other.sh"), "other v1")?;
+    git(&item, &["init", "-q", "-b", "main"])?;
+    commit(&item, "Initial themes")?;
+
+    let schemes = root.join("schemes");
+    let fixture = fs::read_to_string("./tests/fixtures/schemes/tinty-generated.yaml")?
+        .replace("slug: tinty-generated", "slug: mocha");
+    write_to_file(schemes.join("base16/mocha.yaml"), &fixture)?;
+    git(&schemes, &["init", "-q", "-b", "main"])?;
+    commit(&schemes, "Initial schemes")?;
+
+    write_to_file(
+        config_path,
+        &format!(
+            r#"{extra_config}
+[schemes]
+path = "file://{}"
+revision = "main"
+
+[[items]]
+path = "file://{}"
+name = "{ITEM_NAME}"
+themes-dir = "themes"
+revision = "main"
+hook = "echo \"operation: %o\"; cat %f"
+"#,
+            schemes.display(),
+            item.display()
+        ),
+    )
+}
+
+/// Commits a new version of the item's `base16-<slug>` theme.
+fn change_theme(root: &Path, slug: &str) -> Result<()> {
+    let item = root.join(ITEM_NAME);
+    write_to_file(
+        item.join(format!("themes/base16-{slug}.sh")),
+        &format!("{slug} v2"),
+    )?;
+    commit(&item, &format!("Update {slug}"))
+}
+
+#[test]
+fn test_cli_update_reapply_when_current_theme_changed() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, install_vec, temp) = setup("reapply_update", "install", false)?;
+    create_sources(&config_path, temp.path(), "")?;
+    run_command(&install_vec)?;
+    run_command(&build_command_vec(
+        "apply base16-mocha",
+        &config_path,
+        &data_path,
+    )?)?;
+    change_theme(temp.path(), "mocha")?;
+    let update_vec = build_command_vec("update --reapply", &config_path, &data_path)?;
+
+    // ---
+    // Act
+    // ---
+    let (stdout, stderr) = run_command(&update_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(stderr.is_empty(), "Expected empty stderr, got: {stderr}");
+    ensure!(
+        stdout.contains("Re-applying base16-mocha, its theme changed\n"),
+        "Expected a re-apply notice.\nstdout: {stdout}"
+    );
+    ensure!(
+        stdout.contains("operation: update\nmocha v2"),
+        "Expected the hook to run with the new theme.\nstdout: {stdout}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_update_without_reapply_leaves_theme() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, install_vec, temp) =
+        setup("reapply_update_default", "install", false)?;
+    create_sources(&config_path, temp.path(), "")?;
+    run_command(&install_vec)?;
+    run_command(&build_command_vec(
+        "apply base16-mocha",
+        &config_path,
+        &data_path,
+    )?)?;
+    change_theme(temp.path(), "mocha")?;
+    let update_vec = build_command_vec("update", &config_path, &data_path)?;
+
+    // ---
+    // Act
+    // ---
+    let (stdout, stderr) = run_command(&update_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(stderr.is_empty(), "Expected empty stderr, got: {stderr}");
+    ensure!(
+        !stdout.contains("operation:"),
+        "Expected no re-apply without --reapply.\nstdout: {stdout}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_sync_reapply_from_config_skips_other_themes() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, install_vec, temp) = setup("reapply_sync", "install", false)?;
+    create_sources(&config_path, temp.path(), "reapply = true\n")?;
+    run_command(&install_vec)?;
+    run_command(&build_command_vec(
+        "apply base16-mocha",
+        &config_path,
+        &data_path,
+    )?)?;
+    let sync_vec = build_command_vec("sync", &config_path, &data_path)?;
+
+    // ---
+    // Act
+    // ---
+    change_theme(temp.path(), "other")?;
+    let (other_stdout, _) = run_command(&sync_vec)?;
+    change_theme(temp.path(), "mocha")?;
+    let (stdout, stderr) = run_command(&sync_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(stderr.is_empty(), "Expected empty stderr, got: {stderr}");
+    ensure!(
+        !other_stdout.contains("operation:"),
+        "Expected no re-apply when another theme changed.\nstdout: {other_stdout}"
+    );
+    ensure!(
+        stdout.contains("operation: sync\nmocha v2"),
+        "Expected the hook to run with the new theme.\nstdout: {stdout}"
+    );
+
+    Ok(())
+}
@@ -0,0 +1,329 @@
+//! Integration tests for scheme name resolution shared by `apply`, `info` and
+//! `cycle`: bare slugs, scheme names, `[aliases]`, ambiguity errors and "did
+//! you mean" suggestions.
+//!
+//! Uses local fixture repos to avoid network dependency.
+
+mod utils;
+
+use anyhow::{ensure, Result};
+use std::fs;
+use std::path::Path;
+
+use crate::utils::{setup, write_to_file, ARTIFACTS_DIR, CURRENT_SCHEME_FILE_NAME};
+
+const CONFIG: &str = r#"[[items]]
+path = "https://github.com/tinted-theming/tinted-shell"
+name = "tinted-shell"
+themes-dir = "scripts"
+supported-systems = ["base16", "base24"]
+"#;
+
+/// Writes the `tinty-generated` fixture as `<system>/<slug>.yaml` in the
+/// schemes repo, plus a matching theme file in a pre-installed tinted-shell.
+fn write_scheme(data_path: &Path, system: &str, slug: &str) -> Result<()> {
+    let fixture = fs::read_to_string("./tests/fixtures/schemes/tinty-generated.yaml")?
+        .replace("system: base16", &format!("system: {system}"))
+        .replace("slug: tinty-generated", &format!("slug: {slug}"));
+    write_to_file(
+        data_path
+            .join("repos/schemes")
+            .join(system)
+            .join(format!("{slug}.yaml")),
+        &fixture,
+    )?;
+    write_to_file(
+        data_path
+            .join("repos/tinted-shell/scripts")
+            .join(format!("{system}-{slug}.sh")),
+        "echo theme\n",
+    )?;
+    fs::create_dir_all(data_path.join(ARTIFACTS_DIR))?;
+
+    Ok(())
+}
+
+fn current_scheme(data_path: &Path) -> Result<String> {
+    Ok(fs::read_to_string(
+        data_path.join(ARTIFACTS_DIR).join(CURRENT_SCHEME_FILE_NAME),
+    )?)
+}
+
+#[test]
+fn test_cli_apply_resolves_unprefixed_slug() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, command_vec, _temp_dir) = setup(
+        "test_cli_apply_resolves_unprefixed_slug",
+        "apply tinty-generated",
+        false,
+    )?;
+    write_to_file(&config_path, CONFIG)?;
+    write_scheme(&data_path, "base16", "tinty-generated")?;
+
+    // ---
+    // Act
+    // ---
+    let (_, stderr) = utils::run_command(&command_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(stderr.is_empty(), "Expected empty stderr, got: {stderr}");
+    ensure!(
+        current_scheme(&data_path)? == "base16-tinty-generated",
+        "Expected the slug to resolve to base16-tinty-generated"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_apply_resolves_scheme_name() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, command_vec, _temp_dir) = setup(
+        "test_cli_apply_resolves_scheme_name",
+        "apply \"tinty generated\"",
+        false,
+    )?;
+    write_to_file(&config_path, CONFIG)?;
+    write_scheme(&data_path, "base16", "tinty-generated")?;
+
+    // ---
+    // Act
+    // ---
+    let (_, stderr) = utils::run_command(&command_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(stderr.is_empty(), "Expected empty stderr, got: {stderr}");
+    ensure!(
+        current_scheme(&data_path)? == "base16-tinty-generated",
+        "Expected the name to resolve to base16-tinty-generated"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_apply_ambiguous_slug() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, command_vec, _temp_dir) = setup(
+        "test_cli_apply_ambiguous_slug",
+        "apply tinty-generated",
+        false,
+    )?;
+    write_to_file(&config_path, CONFIG)?;
+    write_scheme(&data_path, "base16", "tinty-generated")?;
+    write_scheme(&data_path, "base24", "tinty-generated")?;
+
+    // ---
+    // Act
+    // ---
+    let (_, stderr) = utils::run_command(&command_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(
+        stderr.contains("is ambiguous")
+            && stderr.contains("base16-tinty-generated, base24-tinty-generated"),
+        "Expected an ambiguity error listing both schemes, got: {stderr}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_apply_suggests_closest_scheme() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, command_vec, _temp_dir) = setup(
+        "test_cli_apply_suggests_closest_scheme",
+        "apply base16-tinty-generatd",
+        false,
+    )?;
+    write_to_file(&config_path, CONFIG)?;
+    write_scheme(&data_path, "base16", "tinty-generated")?;
+
+    // ---
+    // Act
+    // ---
+    let (_, stderr) = utils::run_command(&command_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(
+        stderr.contains("Scheme does not exist: base16-tinty-generatd")
+            && stderr.contains("Did you mean: base16-tinty-generated?"),
+        "Expected a suggestion, got: {stderr}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_info_resolves_unprefixed_slug() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, command_vec, _temp_dir) = setup(
+        "test_cli_info_resolves_unprefixed_slug",
+        "info tinty-generated",
+        false,
+    )?;
+    write_to_file(&config_path, CONFIG)?;
+    write_scheme(&data_path, "base16", "tinty-generated")?;
+
+    // ---
+    // Act
+    // ---
+    let (stdout, stderr) = utils::run_command(&command_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(stderr.is_empty(), "Expected empty stderr, got: {stderr}");
+    ensure!(
+        stdout.contains("System: base16") && stdout.contains("Slug: tinty-generated"),
+        "Expected scheme info, got: {stdout}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_cycle_rejects_unknown_ring_scheme() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, command_vec, _temp_dir) =
+        setup("test_cli_cycle_rejects_unknown_ring_scheme", "cycle", false)?;
+    write_to_file(
+        &config_path,
+        &format!(
+            "default-cycle-ring = \"default\"\n\n[[rings]]\nname = \"default\"\nschemes = [\"tinty-generated\", \"tinty-generatd\"]\n\n{CONFIG}"
+        ),
+    )?;
+    write_scheme(&data_path, "base16", "tinty-generated")?;
+
+    // ---
+    // Act
+    // ---
+    let (_, stderr) = utils::run_command(&command_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(
+        stderr.contains("Invalid scheme \"tinty-generatd\" in ring \"default\"")
+            && stderr.contains("Did you mean: base16-tinty-generated?"),
+        "Expected a ring validation error with a suggestion, got: {stderr}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_apply_resolves_alias() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, command_vec, _temp_dir) =
+        setup("test_cli_apply_resolves_alias", "apply work", false)?;
+    write_to_file(
+        &config_path,
+        &format!("[aliases]\nwork = \"tinty-generated\"\n\n{CONFIG}"),
+    )?;
+    write_scheme(&data_path, "base16", "tinty-generated")?;
+
+    // ---
+    // Act
+    // ---
+    let (_, stderr) = utils::run_command(&command_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(stderr.is_empty(), "Expected empty stderr, got: {stderr}");
+    ensure!(
+        current_scheme(&data_path)? == "base16-tinty-generated",
+        "Expected the alias to resolve to base16-tinty-generated"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_current_with_alias() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, command_vec, _temp_dir) =
+        setup("test_cli_current_with_alias", "current --with-alias", false)?;
+    write_to_file(
+        &config_path,
+        &format!(
+            "[aliases]\nwork = \"base16-tinty-generated\"\nday = \"Tinty Generated\"\nnight = \"base16-other\"\n\n{CONFIG}"
+        ),
+    )?;
+    write_scheme(&data_path, "base16", "tinty-generated")?;
+    write_to_file(
+        data_path.join(ARTIFACTS_DIR).join(CURRENT_SCHEME_FILE_NAME),
+        "base16-tinty-generated",
+    )?;
+
+    // ---
+    // Act
+    // ---
+    let (stdout, stderr) = utils::run_command(&command_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(stderr.is_empty(), "Expected empty stderr, got: {stderr}");
+    ensure!(
+        stdout == "base16-tinty-generated (day, work)\n",
+        "Expected the scheme followed by its aliases, got: {stdout}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_apply_invalid_alias_target() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, command_vec, _temp_dir) =
+        setup("test_cli_apply_invalid_alias_target", "apply work", false)?;
+    write_to_file(
+        &config_path,
+        &format!("[aliases]\nwork = \"tinty-generatd\"\n\n{CONFIG}"),
+    )?;
+    write_scheme(&data_path, "base16", "tinty-generated")?;
+
+    // ---
+    // Act
+    // ---
+    let (_, stderr) = utils::run_command(&command_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(
+        stderr.contains("Alias \"work\" points at \"tinty-generatd\", which is not a scheme")
+            && stderr.contains("Did you mean: base16-tinty-generated?"),
+        "Expected an alias error with a suggestion, got: {stderr}"
+    );
+
+    Ok(())
+}
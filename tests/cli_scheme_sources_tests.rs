@@ -0,0 +1,355 @@
+//! Integration tests for `[[scheme-sources]]`: priority between sources,
+//! `<source>:<id>` names and building item themes for source schemes.
+//!
+//! Fully offline: the schemes repo, the scheme source and the item are plain
+//! local directories.
+
+mod utils;
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::{ensure, Result};
+use utils::{
+    build_command_vec, run_command, setup, write_to_file, ARTIFACTS_DIR, CURRENT_SCHEME_FILE_NAME,
+};
+
+const ITEM_NAME: &str = "template-item";
+
+/// Writes the `tinty-generated` fixture as `<dir>/base16/<slug>.yaml`, named
+/// `name`.
+fn write_scheme(dir: &Path, slug: &str, name: &str) -> Result<()> {
+    let fixture = fs::read_to_string("./tests/fixtures/schemes/tinty-generated.yaml")?
+        .replace("slug: tinty-generated", &format!("slug: {slug}"))
+        .replace("name: Tinty Generated", &format!("name: {name}"));
+    write_to_file(dir.join("base16").join(format!("{slug}.yaml")), &fixture)
+}
+
+/// Writes the official schemes, a `company` scheme source that shadows
+/// `base16-mocha`, and a template item with a prebuilt theme for the official
+/// `base16-mocha` only.
+fn write_sources(config_path: &Path, root: &Path) -> Result<()> {
+    write_scheme(&root.join("schemes"), "mocha", "Official Mocha")?;
+    write_scheme(&root.join("company"), "mocha", "Company Mocha")?;
+    write_scheme(&root.join("company"), "brand", "Company Brand")?;
+
+    let item = root.join(ITEM_NAME);
+    write_to_file(
+        item.join("templates/config.yaml"),
+        "default:\n  filename: themes/base16-{{ scheme-slug }}.txt\n  supported-systems: [base16]\n",
+    )?;
+    write_to_file(item.join("templates/default.mustache"), "{{scheme-name}}\n")?;
+    write_to_file(item.join("themes/base16-mocha.txt"), "Official Mocha\n")?;
+
+    write_to_file(
+        config_path,
+        &format!(
+            "[schemes]\npath = \"{}\"\n\n[[scheme-sources]]\nname = \"company\"\npath = \"{}\"\npriority = 10\n\n[[items]]\npath = \"{}\"\nname = \"{ITEM_NAME}\"\nthemes-dir = \"themes\"\n",
+            root.join("schemes").display(),
+            root.join("company").display(),
+            item.display()
+        ),
+    )
+}
+
+/// The contents of the single theme file `apply` wrote to `artifacts/`.
+fn applied_theme(data_path: &Path) -> Result<String> {
+    let mut themes = Vec::new();
+    for entry in fs::read_dir(data_path.join(ARTIFACTS_DIR))? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|extension| extension == "txt") {
+            themes.push(fs::read_to_string(path)?);
+        }
+    }
+    ensure!(themes.len() == 1, "Expected one theme, got: {themes:?}");
+    Ok(themes.concat())
+}
+
+#[test]
+fn test_cli_apply_prefers_higher_priority_source_and_builds_its_theme() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, install_vec, temp) =
+        setup("scheme_sources_priority", "install", false)?;
+    write_sources(&config_path, temp.path())?;
+    run_command(&install_vec)?;
+    let apply_vec = build_command_vec("apply mocha", &config_path, &data_path)?;
+
+    // ---
+    // Act
+    // ---
+    let (_, stderr) = run_command(&apply_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(stderr.is_empty(), "Expected empty stderr, got: {stderr}");
+    ensure!(
+        data_path.join("repos/company/base16/mocha.yaml").exists(),
+        "Expected the scheme source to be installed into repos/"
+    );
+    let theme = applied_theme(&data_path)?;
+    ensure!(
+        theme == "Company Mocha\n",
+        "Expected the company scheme to win, got: {theme:?}"
+    );
+    ensure!(
+        fs::read_to_string(temp.path().join(ITEM_NAME).join("themes/base16-mocha.txt"))?
+            == "Official Mocha\n",
+        "Expected the item's own themes to be left untouched"
+    );
+    ensure!(
+        fs::read_to_string(data_path.join(ARTIFACTS_DIR).join(CURRENT_SCHEME_FILE_NAME))?
+            == "base16-mocha",
+        "Expected the winning scheme to be recorded by its plain ID"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_apply_source_qualified_name() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, install_vec, temp) =
+        setup("scheme_sources_qualified", "install", false)?;
+    write_sources(&config_path, temp.path())?;
+    run_command(&install_vec)?;
+    let apply_vec = build_command_vec("apply schemes:mocha", &config_path, &data_path)?;
+    let list_vec = build_command_vec("list", &config_path, &data_path)?;
+
+    // ---
+    // Act
+    // ---
+    let (_, stderr) = run_command(&apply_vec)?;
+    let (list_stdout, _) = run_command(&list_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(stderr.is_empty(), "Expected empty stderr, got: {stderr}");
+    let theme = applied_theme(&data_path)?;
+    ensure!(
+        theme == "Official Mocha\n",
+        "Expected the shadowed official scheme, got: {theme:?}"
+    );
+    ensure!(
+        fs::read_to_string(data_path.join(ARTIFACTS_DIR).join(CURRENT_SCHEME_FILE_NAME))?
+            == "schemes:base16-mocha",
+        "Expected the shadowed scheme to be recorded by its qualified name"
+    );
+    ensure!(
+        list_stdout.lines().collect::<Vec<_>>()
+            == ["base16-brand", "base16-mocha", "schemes:base16-mocha"],
+        "Expected every scheme to be listed, got:\n{list_stdout}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_scheme_source_name_must_be_unique() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, _, command_vec, temp) = setup("scheme_sources_unique", "install", false)?;
+    write_sources(&config_path, temp.path())?;
+    let config = fs::read_to_string(&config_path)?.replace("\"company\"", "\"template-item\"");
+    write_to_file(&config_path, &config)?;
+
+    // ---
+    // Act
+    // ---
+    let (_, stderr) = run_command(&command_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(
+        stderr.contains("scheme-sources.name \"template-item\" is already used"),
+        "Expected a duplicate name error.\nstderr: {stderr}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_search_finds_schemes_from_every_source() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, install_vec, temp) =
+        setup("scheme_sources_search", "install", false)?;
+    write_sources(&config_path, temp.path())?;
+    write_scheme(&temp.path().join("team"), "mocha", "Team Mocha")?;
+    write_scheme(&temp.path().join("team"), "harbor", "Team Harbor")?;
+    let config = fs::read_to_string(&config_path)?;
+    write_to_file(
+        &config_path,
+        &format!(
+            "{config}\n[[scheme-sources]]\nname = \"team\"\npath = \"{}\"\npriority = 5\n",
+            temp.path().join("team").display()
+        ),
+    )?;
+    run_command(&install_vec)?;
+    let mocha_vec = build_command_vec("search mocha", &config_path, &data_path)?;
+    let harbor_vec = build_command_vec("search harbor", &config_path, &data_path)?;
+
+    // ---
+    // Act
+    // ---
+    let (mocha_stdout, mocha_stderr) = run_command(&mocha_vec)?;
+    let (harbor_stdout, harbor_stderr) = run_command(&harbor_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(
+        mocha_stderr.is_empty() && harbor_stderr.is_empty(),
+        "Expected empty stderr, got: {mocha_stderr}{harbor_stderr}"
+    );
+    ensure!(
+        mocha_stdout.lines().collect::<Vec<_>>()
+            == ["base16-mocha", "schemes:base16-mocha", "team:base16-mocha"],
+        "Expected the winning scheme, then the shadowed ones.\nstdout: {mocha_stdout}"
+    );
+    ensure!(
+        harbor_stdout.trim() == "base16-harbor",
+        "Expected the scheme only the second source has.\nstdout: {harbor_stdout}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_info_resolves_source_schemes_and_aliases() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, install_vec, temp) =
+        setup("scheme_sources_info", "install", false)?;
+    write_sources(&config_path, temp.path())?;
+    let config = fs::read_to_string(&config_path)?;
+    write_to_file(
+        &config_path,
+        &format!("{config}\n[aliases]\nofficial = \"schemes:base16-mocha\"\n"),
+    )?;
+    run_command(&install_vec)?;
+    let source_vec = build_command_vec("info brand", &config_path, &data_path)?;
+    let alias_vec = build_command_vec("info official", &config_path, &data_path)?;
+
+    // ---
+    // Act
+    // ---
+    let (source_stdout, source_stderr) = run_command(&source_vec)?;
+    let (alias_stdout, alias_stderr) = run_command(&alias_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(
+        source_stderr.is_empty() && source_stdout.contains("Company Brand"),
+        "Expected info for the source scheme.\nstdout: {source_stdout}\nstderr: {source_stderr}"
+    );
+    ensure!(
+        alias_stderr.is_empty() && alias_stdout.contains("Official Mocha"),
+        "Expected info for the aliased scheme.\nstdout: {alias_stdout}\nstderr: {alias_stderr}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_status_finds_theme_for_source_qualified_current_scheme() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, install_vec, temp) =
+        setup("scheme_sources_status", "install", false)?;
+    write_sources(&config_path, temp.path())?;
+    run_command(&install_vec)?;
+    let status_vec = build_command_vec("status --json", &config_path, &data_path)?;
+    let mut statuses = Vec::new();
+
+    // ---
+    // Act
+    // ---
+    for scheme in ["schemes:mocha", "brand"] {
+        run_command(&build_command_vec(
+            &format!("apply {scheme}"),
+            &config_path,
+            &data_path,
+        )?)?;
+        let (stdout, _) = run_command(&status_vec)?;
+        statuses.push(serde_json::from_str::<serde_json::Value>(&stdout)?);
+    }
+
+    // ------
+    // Assert
+    // ------
+    for status in statuses {
+        let has_current_theme = status
+            .get("repos")
+            .and_then(serde_json::Value::as_array)
+            .and_then(|repos| {
+                repos.iter().find(|repo| {
+                    repo.get("name").and_then(serde_json::Value::as_str) == Some(ITEM_NAME)
+                })
+            })
+            .and_then(|repo| repo.pointer("/themes-dir/has-current-theme"))
+            .and_then(serde_json::Value::as_bool);
+        ensure!(
+            has_current_theme == Some(true),
+            "Expected the item to have a theme for the current scheme, got: {status}"
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_apply_skips_item_without_templates_for_source_scheme() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, install_vec, temp) =
+        setup("scheme_sources_no_templates", "install", false)?;
+    write_sources(&config_path, temp.path())?;
+    let plain = temp.path().join("plain-item");
+    write_to_file(plain.join("themes/base16-mocha.txt"), "Official Mocha\n")?;
+    let config = fs::read_to_string(&config_path)?;
+    write_to_file(
+        &config_path,
+        &format!(
+            "{config}\n[[items]]\npath = \"{}\"\nname = \"plain-item\"\nthemes-dir = \"themes\"\n",
+            plain.display()
+        ),
+    )?;
+    run_command(&install_vec)?;
+    let apply_vec = build_command_vec("apply mocha", &config_path, &data_path)?;
+
+    // ---
+    // Act
+    // ---
+    let (stdout, stderr) = run_command(&apply_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(stderr.is_empty(), "Expected empty stderr, got: {stderr}");
+    ensure!(
+        stdout.contains(
+            "plain-item has no templates/ to build a theme for base16-mocha from, skipping"
+        ),
+        "Expected the item without templates to be skipped.\nstdout: {stdout}"
+    );
+    let theme = applied_theme(&data_path)?;
+    ensure!(
+        theme == "Company Mocha\n",
+        "Expected only the theme built from the source scheme, got: {theme:?}"
+    );
+
+    Ok(())
+}
@@ -0,0 +1,173 @@
+//! Integration tests for the `search` subcommand.
+//!
+//! These tests are offline: a small schemes repo is written straight into the
+//! data directory, so no schemes need to be cloned.
+
+mod utils;
+
+use std::fmt::Write as _;
+use std::path::Path;
+
+use anyhow::{ensure, Result};
+use utils::{run_command, setup, write_to_file};
+
+/// Writes `<system>/<slug>.yaml` into the data dir's schemes repo with the
+/// given `background` (`base00`) and `blue` (`base0D`) colors.
+fn write_scheme(
+    data_path: &Path,
+    system: &str,
+    slug: &str,
+    variant: &str,
+    author: &str,
+    background: &str,
+    blue: &str,
+) -> Result<()> {
+    let mut palette = String::new();
+    for index in 0..16 {
+        let slot = format!("base0{index:X}");
+        let color = match slot.as_str() {
+            "base00" => background,
+            "base0D" => blue,
+            _ => "#808080",
+        };
+        let _ = writeln!(palette, "  {slot}: '{color}'");
+    }
+
+    write_to_file(
+        data_path
+            .join("repos")
+            .join("schemes")
+            .join(system)
+            .join(format!("{slug}.yaml")),
+        &format!(
+            "system: {system}\nname: {slug}\nslug: {slug}\nauthor: {author}\nvariant: {variant}\npalette:\n{palette}"
+        ),
+    )
+}
+
+fn write_schemes(data_path: &Path) -> Result<()> {
+    write_scheme(
+        data_path,
+        "base16",
+        "nord",
+        "dark",
+        "Arctic Ice Studio",
+        "#2e3440",
+        "#5e81ac",
+    )?;
+    write_scheme(
+        data_path,
+        "base16",
+        "nord-light",
+        "light",
+        "Arctic Ice Studio",
+        "#e5e9f0",
+        "#5e81ac",
+    )?;
+    write_scheme(
+        data_path,
+        "base16",
+        "midnight",
+        "dark",
+        "Someone Else",
+        "#0a0a0a",
+        "#5a7fb0",
+    )?;
+    write_scheme(
+        data_path,
+        "base16",
+        "ember",
+        "dark",
+        "Someone Else",
+        "#1c1c1c",
+        "#d75f00",
+    )
+}
+
+fn search(name: &str, args: &str) -> Result<(String, String)> {
+    let (_, data_path, command_vec, _temp_dir) = setup(name, &format!("search {args}"), false)?;
+    write_schemes(&data_path)?;
+
+    run_command(&command_vec)
+}
+
+#[test]
+fn test_cli_search_subcommand_free_text_sorted_by_relevance() -> Result<()> {
+    // ---
+    // Act
+    // ---
+    let (stdout, stderr) = search("test_cli_search_subcommand_free_text", "nord")?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(stderr.is_empty(), "unexpected stderr: {stderr}");
+    ensure!(
+        stdout == "base16-nord\nbase16-nord-light\n",
+        "expected the exact match first, got:\n{stdout}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_search_subcommand_metadata_filters() -> Result<()> {
+    // ---
+    // Act
+    // ---
+    let (stdout, stderr) = search(
+        "test_cli_search_subcommand_metadata_filters",
+        "--variant dark --author \"arctic ice\"",
+    )?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(stderr.is_empty(), "unexpected stderr: {stderr}");
+    ensure!(stdout == "base16-nord\n", "unexpected stdout:\n{stdout}");
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_search_subcommand_color_and_lightness_filters() -> Result<()> {
+    // ---
+    // Act
+    // ---
+    let (stdout, stderr) = search(
+        "test_cli_search_subcommand_color_and_lightness_filters",
+        "--variant dark --bg-lightness ..25 --near-color base0D=#5e81ac --max-delta 10",
+    )?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(stderr.is_empty(), "unexpected stderr: {stderr}");
+    ensure!(
+        stdout == "base16-nord\nbase16-midnight\n",
+        "expected the closest color first, got:\n{stdout}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_search_subcommand_invalid_near_color() -> Result<()> {
+    // ---
+    // Act
+    // ---
+    let (_, stderr) = search(
+        "test_cli_search_subcommand_invalid_near_color",
+        "--near-color base0D",
+    )?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(
+        stderr.contains("Invalid near color"),
+        "expected a near color error, got: {stderr}"
+    );
+
+    Ok(())
+}
@@ -0,0 +1,183 @@
+//! Integration tests for `shared-cache`: data directories installing and
+//! updating Git sources through shared bare mirrors.
+//!
+//! Fully offline: the item and the schemes repo are throwaway local git
+//! repositories addressed with `file://` URLs.
+
+mod utils;
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::{ensure, Context, Result};
+use utils::{build_command_vec, run_command, setup, write_to_file};
+
+const ITEM_NAME: &str = "cached-item";
+
+fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .with_context(|| format!("failed to run git {args:?} in {}", dir.display()))?;
+    ensure!(
+        output.status.success(),
+        "git {args:?} failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn commit(dir: &Path, message: &str) -> Result<()> {
+    git(dir, &["add", "-A"])?;
+    git(
+        dir,
+        &[
+            "-c",
+            "user.email=tinty@test.local",
+            "-c",
+            "user.name=tinty test",
+            "-c",
+            "commit.gpgsign=false",
+            "commit",
+            "-q",
+            "-m",
+            message,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Creates the item and schemes repositories, and a config using a shared
+/// cache under `root/cache`.
+fn create_sources(config_path: &Path, root: &Path) -> Result<()> {
+    let item = root.join(ITEM_NAME);
+    write_to_file(item.join("themes/base16-mocha.sh"), "mocha v1")?;
+    git(&item, &["init", "-q", "-b", "main"])?;
+    commit(&item, "Initial themes")?;
+
+    let schemes = root.join("schemes");
+    write_to_file(schemes.join("base16/mocha.yaml"), "mocha")?;
+    git(&schemes, &["init", "-q", "-b", "main"])?;
+    commit(&schemes, "Initial schemes")?;
+
+    write_to_file(
+        config_path,
+        &format!(
+            "shared-cache = true\nshared-cache-dir = \"{}\"\n\n[schemes]\npath = \"file://{}\"\nrevision = \"main\"\n\n[[items]]\npath = \"file://{}\"\nname = \"{ITEM_NAME}\"\nthemes-dir = \"themes\"\nrevision = \"main\"\n",
+            root.join("cache").display(),
+            schemes.display(),
+            item.display()
+        ),
+    )
+}
+
+fn installed_theme(data_path: &Path) -> Result<String> {
+    Ok(fs::read_to_string(
+        data_path
+            .join("repos")
+            .join(ITEM_NAME)
+            .join("themes/base16-mocha.sh"),
+    )?)
+}
+
+#[test]
+fn test_cli_install_from_shared_cache_offline() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, _, install_vec, temp) = setup("shared_cache_offline", "install", false)?;
+    create_sources(&config_path, temp.path())?;
+    let other_data_path = temp.path().join("other-data");
+    let other_install_vec = build_command_vec("install --offline", &config_path, &other_data_path)?;
+
+    // ---
+    // Act
+    // ---
+    run_command(&install_vec)?;
+    // Only the cache has the repositories now.
+    fs::remove_dir_all(temp.path().join(ITEM_NAME))?;
+    fs::remove_dir_all(temp.path().join("schemes"))?;
+    let (stdout, stderr) = run_command(&other_install_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(stderr.is_empty(), "Expected empty stderr, got: {stderr}");
+    ensure!(
+        stdout.contains(&format!("{ITEM_NAME} installed")) && stdout.contains("schemes installed"),
+        "Expected both repositories to be installed.\nstdout: {stdout}"
+    );
+    ensure!(
+        installed_theme(&other_data_path)? == "mocha v1",
+        "Expected the item to be checked out from the cache"
+    );
+    let origin = git(
+        &other_data_path.join("repos").join(ITEM_NAME),
+        &["remote", "get-url", "origin"],
+    )?;
+    ensure!(
+        origin == format!("file://{}", temp.path().join(ITEM_NAME).display()),
+        "Expected origin to stay the configured URL, got: {origin}"
+    );
+    ensure!(
+        fs::read_dir(temp.path().join("cache"))?
+            .filter_map(Result::ok)
+            .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "git"))
+            .count()
+            == 2,
+        "Expected one mirror per repository"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_update_through_shared_cache() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, install_vec, temp) =
+        setup("shared_cache_update", "install", false)?;
+    create_sources(&config_path, temp.path())?;
+    let other_data_path = temp.path().join("other-data");
+    run_command(&install_vec)?;
+    run_command(&build_command_vec(
+        "install",
+        &config_path,
+        &other_data_path,
+    )?)?;
+    let item = temp.path().join(ITEM_NAME);
+    write_to_file(item.join("themes/base16-mocha.sh"), "mocha v2")?;
+    commit(&item, "Update mocha")?;
+
+    // ---
+    // Act
+    // ---
+    let (_, stderr) = run_command(&build_command_vec("update", &config_path, &data_path)?)?;
+    let (_, other_stderr) = run_command(&build_command_vec(
+        "update --offline",
+        &config_path,
+        &other_data_path,
+    )?)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(stderr.is_empty(), "Expected empty stderr, got: {stderr}");
+    ensure!(
+        other_stderr.is_empty(),
+        "Expected empty stderr, got: {other_stderr}"
+    );
+    ensure!(
+        installed_theme(&data_path)? == "mocha v2",
+        "Expected the update to be fetched through the cache"
+    );
+    ensure!(
+        installed_theme(&other_data_path)? == "mocha v2",
+        "Expected the other data directory to update from the refreshed cache offline"
+    );
+
+    Ok(())
+}
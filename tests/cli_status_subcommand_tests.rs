@@ -0,0 +1,154 @@
+//! Integration tests for `tinty status`.
+//!
+//! Fully offline: the item is a throwaway local git repository addressed with a
+//! `file://` URL, and the schemes repo is a plain local directory.
+
+mod utils;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{anyhow, ensure, Context, Result};
+use serde_json::Value;
+use utils::{
+    build_command_vec, run_command, setup, write_to_file, ARTIFACTS_DIR, CURRENT_SCHEME_FILE_NAME,
+};
+
+const ITEM_NAME: &str = "status-item";
+
+fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .with_context(|| format!("failed to run git {args:?} in {}", dir.display()))?;
+    ensure!(
+        output.status.success(),
+        "git {args:?} failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    Ok(String::from_utf8(output.stdout)?.trim().to_string())
+}
+
+/// Installs a `file://` item with a `base16-tinty-generated.sh` theme and a
+/// local schemes directory, applies nothing, and leaves a stale repository
+/// behind. Returns the config and data paths with the temp dir guard.
+fn arrange(name: &str) -> Result<(PathBuf, PathBuf, tempfile::TempDir)> {
+    let (config_path, data_path, install_vec, temp) = setup(name, "install", false)?;
+    let source = temp.path().join("item-source");
+    let schemes = temp.path().join("schemes");
+    fs::create_dir_all(source.join("themes"))?;
+    fs::create_dir_all(&schemes)?;
+    fs::write(source.join("themes/base16-tinty-generated.sh"), "theme")?;
+    git(&source, &["init", "-q", "-b", "main"])?;
+    git(&source, &["add", "-A"])?;
+    git(
+        &source,
+        &[
+            "-c",
+            "user.email=tinty@test.local",
+            "-c",
+            "user.name=tinty test",
+            "-c",
+            "commit.gpgsign=false",
+            "commit",
+            "-q",
+            "-m",
+            "first",
+        ],
+    )?;
+
+    write_to_file(
+        &config_path,
+        &format!(
+            "[schemes]\npath = \"{}\"\n\n[[items]]\npath = \"file://{}\"\nname = \"{ITEM_NAME}\"\nthemes-dir = \"themes\"\nallow-dirty-update = true\n",
+            schemes.display(),
+            source.display()
+        ),
+    )?;
+    run_command(&install_vec)?;
+    fs::create_dir_all(data_path.join("repos/old-item"))?;
+
+    Ok((config_path, data_path, temp))
+}
+
+#[test]
+fn test_cli_status_reports_installation() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, _temp) = arrange("status_text")?;
+    let item_path = data_path.join("repos").join(ITEM_NAME);
+    let commit = git(&item_path, &["rev-parse", "HEAD"])?;
+    fs::write(item_path.join("themes/base16-tinty-generated.sh"), "edit")?;
+    write_to_file(
+        data_path.join(ARTIFACTS_DIR).join(CURRENT_SCHEME_FILE_NAME),
+        "base16-tinty-generated",
+    )?;
+    let command_vec = build_command_vec("status", &config_path, &data_path)?;
+
+    // ---
+    // Act
+    // ---
+    let (stdout, stderr) = run_command(&command_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(stderr.is_empty(), "Expected empty stderr, got: {stderr}");
+    for expected in [
+        "Current scheme: base16-tinty-generated",
+        "(local path)",
+        &format!("revision: main (checked out {commit})"),
+        "working tree: uncommitted changes",
+        "allow-dirty-update: true",
+        "themes-dir: themes (has a theme for the current scheme)",
+        "old-item",
+    ] {
+        ensure!(
+            stdout.contains(expected),
+            "Expected {expected:?} in the status.\nstdout: {stdout}"
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_status_json() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, _temp) = arrange("status_json")?;
+    fs::remove_dir_all(data_path.join("repos").join(ITEM_NAME))?;
+    let command_vec = build_command_vec("status --json", &config_path, &data_path)?;
+
+    // ---
+    // Act
+    // ---
+    let (stdout, stderr) = run_command(&command_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(stderr.is_empty(), "Expected empty stderr, got: {stderr}");
+    let status: Value = serde_json::from_str(&stdout)?;
+    let item = status
+        .get("repos")
+        .and_then(|repos| repos.get(1))
+        .ok_or_else(|| anyhow!("no status for the item: {stdout}"))?;
+    ensure!(
+        status.get("current-scheme") == Some(&Value::Null)
+            && status.get("stale-repos") == Some(&serde_json::json!(["old-item"])),
+        "Unexpected status: {status}"
+    );
+    ensure!(
+        item.get("installed") == Some(&Value::Bool(false))
+            && item.get("dirty") == Some(&Value::Null)
+            && item.pointer("/themes-dir/exists") == Some(&Value::Bool(false)),
+        "Unexpected status for the missing item: {item}"
+    );
+
+    Ok(())
+}
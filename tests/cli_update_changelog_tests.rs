@@ -0,0 +1,185 @@
+//! Integration tests for the summary `tinty update` prints of what changed in
+//! each repository.
+//!
+//! Fully offline: the item and the schemes repo are throwaway local git
+//! repositories addressed with `file://` URLs.
+
+mod utils;
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::{ensure, Context, Result};
+use utils::{build_command_vec, run_command, setup, write_to_file};
+
+const ITEM_NAME: &str = "changing-item";
+
+fn git(dir: &Path, args: &[&str]) -> Result<()> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .with_context(|| format!("failed to run git {args:?} in {}", dir.display()))?;
+    ensure!(
+        output.status.success(),
+        "git {args:?} failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    Ok(())
+}
+
+fn commit(dir: &Path, message: &str) -> Result<()> {
+    git(dir, &["add", "-A"])?;
+    git(
+        dir,
+        &[
+            "-c",
+            "user.email=tinty@test.local",
+            "-c",
+            "user.name=tinty test",
+            "-c",
+            "commit.gpgsign=false",
+            "commit",
+            "-q",
+            "-m",
+            message,
+        ],
+    )
+}
+
+/// Creates the item and schemes repositories with one commit each, and a
+/// config pointing at them.
+fn create_sources(config_path: &Path, root: &Path) -> Result<()> {
+    let item = root.join(ITEM_NAME);
+    write_to_file(item.join("themes/base16-mocha.sh"), "mocha")?;
+    write_to_file(item.join("themes/base16-old.sh"), "old")?;
+    write_to_file(item.join("README.md"), "readme")?;
+    git(&item, &["init", "-q", "-b", "main"])?;
+    commit(&item, "Initial themes")?;
+
+    let schemes = root.join("schemes");
+    write_to_file(schemes.join("base16/old.yaml"), "old")?;
+    git(&schemes, &["init", "-q", "-b", "main"])?;
+    commit(&schemes, "Initial schemes")?;
+
+    write_to_file(
+        config_path,
+        &format!(
+            "[schemes]\npath = \"file://{}\"\nrevision = \"main\"\n\n[[items]]\npath = \"file://{}\"\nname = \"{ITEM_NAME}\"\nthemes-dir = \"themes\"\nrevision = \"main\"\n",
+            schemes.display(),
+            item.display()
+        ),
+    )
+}
+
+/// Adds, modifies and removes a theme, and swaps a scheme for another.
+fn change_sources(root: &Path) -> Result<()> {
+    let item = root.join(ITEM_NAME);
+    write_to_file(item.join("themes/base16-new.sh"), "new")?;
+    write_to_file(item.join("themes/base16-mocha.sh"), "mocha v2")?;
+    fs::remove_file(item.join("themes/base16-old.sh"))?;
+    write_to_file(item.join("README.md"), "readme v2")?;
+    commit(&item, "Add new theme")?;
+    write_to_file(item.join("themes/base16-mocha.sh"), "mocha v3")?;
+    commit(&item, "Tweak mocha")?;
+
+    let schemes = root.join("schemes");
+    fs::remove_file(schemes.join("base16/old.yaml"))?;
+    write_to_file(schemes.join("base16/new.yaml"), "new")?;
+    commit(&schemes, "Replace old scheme")
+}
+
+#[test]
+fn test_cli_update_prints_changes() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, install_vec, temp) =
+        setup("update_changelog_text", "install", false)?;
+    create_sources(&config_path, temp.path())?;
+    run_command(&install_vec)?;
+    change_sources(temp.path())?;
+    let update_vec = build_command_vec("update", &config_path, &data_path)?;
+
+    // ---
+    // Act
+    // ---
+    let (stdout, stderr) = run_command(&update_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(stderr.is_empty(), "Expected empty stderr, got: {stderr}");
+    for expected in [
+        "changing-item: ",
+        ", 2 commits\n  Tweak mocha\n  Add new theme\n",
+        "  themes: 1 added, 1 removed, 1 modified\n    + base16-new.sh\n    - base16-old.sh\n    ~ base16-mocha.sh\n",
+        ", 1 commit\n  Replace old scheme\n",
+        "  schemes: 1 added, 1 removed\n    + base16-new\n    - base16-old\n",
+    ] {
+        ensure!(
+            stdout.contains(expected),
+            "Expected {expected:?} in the summary.\nstdout: {stdout}"
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_update_json_changes() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, install_vec, temp) =
+        setup("update_changelog_json", "install", false)?;
+    create_sources(&config_path, temp.path())?;
+    run_command(&install_vec)?;
+    let update_vec = build_command_vec("update --json", &config_path, &data_path)?;
+
+    // ---
+    // Act
+    // ---
+    let (unchanged_stdout, _) = run_command(&update_vec)?;
+    change_sources(temp.path())?;
+    let (stdout, stderr) = run_command(&update_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(stderr.is_empty(), "Expected empty stderr, got: {stderr}");
+    ensure!(
+        unchanged_stdout.trim() == "[]",
+        "Expected no changes when nothing moved, got: {unchanged_stdout}"
+    );
+    let changelogs: serde_json::Value = serde_json::from_str(&stdout)?;
+    let item = changelogs
+        .get(0)
+        .context("Expected the item's changelog first")?;
+    ensure!(
+        item.get("name") == Some(&serde_json::json!(ITEM_NAME))
+            && item.get("commits") == Some(&serde_json::json!(["Tweak mocha", "Add new theme"]))
+            && item.get("themes")
+                == Some(&serde_json::json!({
+                    "added": ["base16-new.sh"],
+                    "removed": ["base16-old.sh"],
+                    "modified": ["base16-mocha.sh"],
+                })),
+        "Unexpected item changelog: {item}"
+    );
+    let schemes = changelogs
+        .get(1)
+        .context("Expected the schemes repo's changelog second")?;
+    ensure!(
+        schemes.get("schemes")
+            == Some(&serde_json::json!({
+                "added": ["base16-new"],
+                "removed": ["base16-old"],
+                "modified": [],
+            })),
+        "Unexpected schemes changelog: {schemes}"
+    );
+
+    Ok(())
+}
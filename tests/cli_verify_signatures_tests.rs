@@ -0,0 +1,281 @@
+//! Integration tests for `verify-signatures` and `allowed-signers`.
+//!
+//! Fully offline: the item is a throwaway local git repository addressed with a
+//! `file://` URL whose commits are signed with throwaway SSH keys, and the
+//! schemes repo is a plain local directory.
+
+mod utils;
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{ensure, Context, Result};
+use utils::{build_command_vec, run_command, run_command_with_env, setup, write_to_file};
+
+const ITEM_NAME: &str = "signed-item";
+
+fn run(dir: &Path, program: &str, args: &[&str]) -> Result<()> {
+    let output = Command::new(program)
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .with_context(|| format!("failed to run {program} {args:?} in {}", dir.display()))?;
+    ensure!(
+        output.status.success(),
+        "{program} {args:?} failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    Ok(())
+}
+
+/// Creates an `allowed` SSH key listed in `allowed_signers`, and an `other`
+/// key that is not.
+fn create_keys(root: &Path) -> Result<()> {
+    for key in ["allowed", "other"] {
+        run(
+            root,
+            "ssh-keygen",
+            &["-q", "-t", "ed25519", "-N", "", "-C", key, "-f", key],
+        )?;
+    }
+    let public_key = fs::read_to_string(root.join("allowed.pub"))?;
+    fs::write(
+        root.join("allowed_signers"),
+        format!("tinty@test.local {public_key}"),
+    )?;
+    Ok(())
+}
+
+/// Commits a new file to the item's source repository, signed with `key`
+/// (`"allowed"` or `"other"`) or unsigned when `key` is `None`.
+fn commit(root: &Path, file_name: &str, key: Option<&str>) -> Result<()> {
+    let source = root.join(ITEM_NAME);
+    if !source.exists() {
+        fs::create_dir_all(&source)?;
+        run(&source, "git", &["init", "-q", "-b", "main"])?;
+    }
+    fs::write(source.join(file_name), file_name)?;
+    run(&source, "git", &["add", "-A"])?;
+
+    let signing_key = key.map(|key| format!("user.signingkey={}", root.join(key).display()));
+    let mut args = vec![
+        "-c",
+        "user.email=tinty@test.local",
+        "-c",
+        "user.name=tinty test",
+        "-c",
+        "gpg.format=ssh",
+    ];
+    match &signing_key {
+        Some(signing_key) => args.extend(["-c", signing_key, "-c", "commit.gpgsign=true"]),
+        None => args.extend(["-c", "commit.gpgsign=false"]),
+    }
+    args.extend(["commit", "-q", "-m", file_name]);
+    run(&source, "git", &args)
+}
+
+/// Creates a GPG key in a new keyring at `<root>/gnupg` and commits a new file
+/// to the item's source repository signed with it. Returns the keyring.
+fn commit_with_gpg(root: &Path, file_name: &str) -> Result<PathBuf> {
+    let gnupg_home = root.join("gnupg");
+    fs::create_dir_all(&gnupg_home)?;
+    fs::set_permissions(&gnupg_home, fs::Permissions::from_mode(0o700))?;
+    let gnupg_home_arg = gnupg_home.display().to_string();
+    run(
+        root,
+        "gpg",
+        &[
+            "--homedir",
+            &gnupg_home_arg,
+            "--batch",
+            "--passphrase",
+            "",
+            "--quick-gen-key",
+            "tinty test <tinty@test.local>",
+            "ed25519",
+            "sign",
+            "never",
+        ],
+    )?;
+
+    let source = root.join(ITEM_NAME);
+    fs::create_dir_all(&source)?;
+    run(&source, "git", &["init", "-q", "-b", "main"])?;
+    fs::write(source.join(file_name), file_name)?;
+    run(&source, "git", &["add", "-A"])?;
+    let output = Command::new("git")
+        .args([
+            "-c",
+            "user.email=tinty@test.local",
+            "-c",
+            "user.name=tinty test",
+            "-c",
+            "gpg.format=openpgp",
+            "-c",
+            "user.signingkey=tinty@test.local",
+            "commit",
+            "-q",
+            "-S",
+            "-m",
+            file_name,
+        ])
+        .current_dir(&source)
+        .env("GNUPGHOME", &gnupg_home)
+        .output()?;
+    ensure!(
+        output.status.success(),
+        "git commit -S failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    Ok(gnupg_home)
+}
+
+fn write_config(config_path: &Path, root: &Path) -> Result<()> {
+    fs::create_dir_all(root.join("schemes"))?;
+    write_to_file(
+        config_path,
+        &format!(
+            "[schemes]\npath = \"{}\"\n\n[[items]]\npath = \"file://{}\"\nname = \"{ITEM_NAME}\"\nthemes-dir = \".\"\nrevision = \"main\"\nverify-signatures = true\nallowed-signers = \"{}\"\n",
+            root.join("schemes").display(),
+            root.join(ITEM_NAME).display(),
+            root.join("allowed_signers").display()
+        ),
+    )
+}
+
+#[test]
+fn test_cli_verify_signatures_accepts_allowed_key_and_rejects_others() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, install_vec, temp) =
+        setup("verify_signatures_update", "install", false)?;
+    create_keys(temp.path())?;
+    commit(temp.path(), "first.sh", Some("allowed"))?;
+    write_config(&config_path, temp.path())?;
+    let repo_path = data_path.join("repos").join(ITEM_NAME);
+    let update_vec = build_command_vec("update", &config_path, &data_path)?;
+
+    // ---
+    // Act
+    // ---
+    let (install_stdout, install_stderr) = run_command(&install_vec)?;
+    commit(temp.path(), "second.sh", Some("other"))?;
+    let (_, update_stderr) = run_command(&update_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(
+        install_stderr.is_empty() && install_stdout.contains(&format!("{ITEM_NAME} installed")),
+        "Expected a commit signed by the allowed key to install.\nstdout: {install_stdout}\nstderr: {install_stderr}"
+    );
+    ensure!(
+        update_stderr.contains("not signed by an allowed key"),
+        "Expected update to refuse a commit signed by another key.\nstderr: {update_stderr}"
+    );
+    ensure!(
+        repo_path.join("first.sh").exists() && !repo_path.join("second.sh").exists(),
+        "Expected the working tree to stay at the verified commit"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_verify_signatures_rejects_unsigned_install() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, command_vec, temp) =
+        setup("verify_signatures_unsigned", "install", false)?;
+    create_keys(temp.path())?;
+    commit(temp.path(), "first.sh", None)?;
+    write_config(&config_path, temp.path())?;
+
+    // ---
+    // Act
+    // ---
+    let (_, stderr) = run_command(&command_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(
+        stderr.contains("it is not signed"),
+        "Expected install to refuse an unsigned commit.\nstderr: {stderr}"
+    );
+    ensure!(
+        !data_path.join("repos").join(ITEM_NAME).exists(),
+        "Expected the rejected clone to be removed"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_verify_signatures_requires_git_source() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, _, command_vec, temp) = setup("verify_signatures_local", "install", false)?;
+    let local = temp.path().join("local-item");
+    fs::create_dir_all(&local)?;
+    write_to_file(
+        &config_path,
+        &format!(
+            "[[items]]\npath = \"{}\"\nname = \"local-item\"\nthemes-dir = \".\"\nverify-signatures = true\n",
+            local.display()
+        ),
+    )?;
+
+    // ---
+    // Act
+    // ---
+    let (_, stderr) = run_command(&command_vec)?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(
+        stderr.contains("Signatures can only be verified for Git sources"),
+        "Expected the config to be rejected.\nstderr: {stderr}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_verify_signatures_rejects_gpg_signature_with_ssh_allowed_signers() -> Result<()> {
+    // -------
+    // Arrange
+    // -------
+    let (config_path, data_path, command_vec, temp) =
+        setup("verify_signatures_gpg_bypass", "install", false)?;
+    create_keys(temp.path())?;
+    let gnupg_home = commit_with_gpg(temp.path(), "first.sh")?;
+    write_config(&config_path, temp.path())?;
+    let gnupg_home = gnupg_home.display().to_string();
+
+    // ---
+    // Act
+    // ---
+    let (_, stderr) = run_command_with_env(&command_vec, &[("GNUPGHOME", gnupg_home.as_str())])?;
+
+    // ------
+    // Assert
+    // ------
+    ensure!(
+        stderr.contains("not signed by an allowed key"),
+        "Expected install to refuse a GPG signature when only SSH signers are allowed.\nstderr: {stderr}"
+    );
+    ensure!(
+        !data_path.join("repos").join(ITEM_NAME).exists(),
+        "Expected the rejected clone to be removed"
+    );
+
+    Ok(())
+}